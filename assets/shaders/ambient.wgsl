@@ -0,0 +1,59 @@
+// Ambient shader for MIDI-RS Black MIDI Visualizer
+// Two soft glows slowly drifting and changing color over the background while idle
+
+struct Uniforms {
+    screen_size: vec2<f32>,
+    playhead_position: f32,
+    time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Opacity of a glow at its center, low enough to stay in the background
+const STRENGTH: f32 = 0.12;
+// Radius of a glow as a fraction of the window height
+const RADIUS: f32 = 0.6;
+// Drift speed; one cycle of the motion takes about two minutes
+const SPEED: f32 = 0.05;
+
+// One triangle covering the whole screen, no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = corner;
+    return out;
+}
+
+// Fully saturated color of hue `h` (0.0 to 1.0)
+fn hue_color(h: f32) -> vec3<f32> {
+    let k = abs(fract(h + vec3<f32>(0.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0;
+    return clamp(k, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Gaussian falloff around `center`, round regardless of the window shape
+fn glow(uv: vec2<f32>, center: vec2<f32>, aspect: f32) -> f32 {
+    let offset = (uv - center) * vec2<f32>(aspect, 1.0);
+    return exp(-dot(offset, offset) / (RADIUS * RADIUS));
+}
+
+// Premultiplied, drawn with premultiplied blending over the cleared background
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let t = uniforms.time * SPEED;
+    let aspect = uniforms.screen_size.x / max(uniforms.screen_size.y, 1.0);
+
+    let first = glow(in.uv, vec2<f32>(0.5 + 0.35 * sin(t * 1.3), 0.5 + 0.3 * cos(t * 1.7)), aspect);
+    let second = glow(in.uv, vec2<f32>(0.5 + 0.35 * cos(t * 1.1 + 2.0), 0.5 + 0.3 * sin(t * 0.9 + 1.0)), aspect);
+    let hue = t * 0.2;
+    let color = hue_color(hue) * first + hue_color(hue + 0.33) * second;
+
+    return vec4<f32>(color, min(first + second, 1.0)) * STRENGTH;
+}
@@ -23,6 +23,11 @@ struct InstanceInput {
     @location(2) instance_position: vec2<f32>,
     @location(3) instance_size: vec2<f32>,
     @location(4) instance_color: vec4<f32>,
+    @location(5) instance_shading: f32,
+    @location(6) instance_outline: f32,
+    @location(7) instance_corner_radius: f32,
+    @location(8) instance_shape: u32,
+    @location(9) instance_envelope: vec3<f32>,
 }
 
 // Vertex output to fragment shader
@@ -31,6 +36,38 @@ struct VertexOutput {
     @location(0) color: vec4<f32>,
     @location(1) tex_coords: vec2<f32>,
     @location(2) world_position: vec2<f32>,
+    @location(3) shading: f32,
+    // Outline width as a fraction of the quad on each axis
+    @location(4) outline: vec2<f32>,
+    // Size of the quad in pixels
+    @location(5) size_px: vec2<f32>,
+    // Radius of the rounded bottom corners in pixels
+    @location(6) corner_radius: f32,
+    @location(7) @interpolate(flat) shape: u32,
+    // Envelope strength, then bottom edge and height of the whole note
+    @location(8) envelope: vec3<f32>,
+}
+
+// Note shapes, matching `shape_id` in note_renderer.rs
+const SHAPE_RECTANGLE: u32 = 0u;
+const SHAPE_ROUNDED: u32 = 1u;
+const SHAPE_CAPSULE: u32 = 2u;
+const SHAPE_DIAMOND: u32 = 3u;
+
+// Signed distance in pixels from the outline of a shape filling the quad, negative inside
+fn shape_distance(shape: u32, tex_coords: vec2<f32>, size_px: vec2<f32>) -> f32 {
+    let half = size_px * 0.5;
+    let q = abs(tex_coords * size_px - half);
+    if shape == SHAPE_DIAMOND {
+        return (q.x * half.y + q.y * half.x - half.x * half.y) / length(half);
+    }
+    // Rounded corners take part of the narrower side, capsules all of it
+    var radius = min(half.x, half.y);
+    if shape == SHAPE_ROUNDED {
+        radius *= 0.6;
+    }
+    let inner = q - (half - vec2<f32>(radius, radius));
+    return length(max(inner, vec2<f32>(0.0, 0.0))) + min(max(inner.x, inner.y), 0.0) - radius;
 }
 
 @vertex
@@ -56,6 +93,17 @@ fn vs_main(
     out.color = instance.instance_color;
     out.tex_coords = vertex.tex_coords;
     out.world_position = world_pos;
+    out.shading = instance.instance_shading;
+
+    // Convert the outline from pixels, leaving at least half of small notes filled
+    let size_px = max(instance.instance_size * uniforms.screen_size, vec2<f32>(1.0, 1.0));
+    out.outline = min(vec2<f32>(instance.instance_outline) / size_px, vec2<f32>(0.25, 0.25));
+
+    // Rounded corners take at most half of the narrower side
+    out.size_px = size_px;
+    out.corner_radius = min(instance.instance_corner_radius, min(size_px.x, size_px.y) * 0.5);
+    out.shape = instance.instance_shape;
+    out.envelope = instance.instance_envelope;
     
     return out;
 }
@@ -64,9 +112,28 @@ fn vs_main(
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Simple flat color rendering for performance
     var color = in.color;
+
+    // Optional beveled shading: lighter left edge and top cap, darker right edge
+    if in.shading > 0.0 {
+        let left_light = (1.0 - smoothstep(0.0, 0.35, in.tex_coords.x)) * 0.25;
+        let top_cap = smoothstep(0.8, 1.0, in.tex_coords.y) * 0.2;
+        let right_shadow = smoothstep(0.6, 1.0, in.tex_coords.x) * 0.3;
+        let lit = mix(color.rgb, vec3<f32>(1.0, 1.0, 1.0), (left_light + top_cap) * in.shading);
+        color = vec4<f32>(lit * (1.0 - right_shadow * in.shading), color.a);
+    }
     
+    // Optional envelope: notes start at their bottom edge, which lights up like an attack,
+    // and fade toward their end at the top
+    if in.envelope.x > 0.0 {
+        let from_start = in.world_position.y - in.envelope.y;
+        let along = clamp(from_start / max(in.envelope.z, 1e-6), 0.0, 1.0);
+        let attack = (1.0 - smoothstep(0.0, 12.0, from_start * uniforms.screen_size.y)) * 0.35;
+        let lit = mix(color.rgb, vec3<f32>(1.0, 1.0, 1.0), attack * in.envelope.x);
+        color = vec4<f32>(lit * (1.0 - along * 0.6 * in.envelope.x), color.a);
+    }
+
     // Add slight glow effect based on distance from playhead (near bottom)
-    let playhead_y = 0.15; // Playhead is at 15% from bottom
+    let playhead_y = uniforms.playhead_position; // Just above the piano
     let playhead_distance = abs(in.world_position.y - playhead_y);
     let glow_factor = 1.0 - smoothstep(0.0, 0.05, playhead_distance);
     
@@ -78,6 +145,33 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         color.a
     );
     
+    // Darken the edges so adjacent notes of the same color stay apart
+    if in.shape == SHAPE_RECTANGLE {
+        let edge = min(in.tex_coords, vec2<f32>(1.0, 1.0) - in.tex_coords);
+        if edge.x < in.outline.x || edge.y < in.outline.y {
+            color = vec4<f32>(color.rgb * 0.45, color.a);
+        }
+    } else {
+        // Cut the shape out of the quad, blending its edge over about a pixel
+        let d = shape_distance(in.shape, in.tex_coords, in.size_px);
+        let outline_px = min(in.outline.x * in.size_px.x, in.outline.y * in.size_px.y);
+        if d > -outline_px {
+            color = vec4<f32>(color.rgb * 0.45, color.a);
+        }
+        color.a *= clamp(0.5 - d, 0.0, 1.0);
+    }
+
+    // Round off the bottom corners, blending the curve over about a pixel
+    if in.corner_radius > 0.0 {
+        let p = in.tex_coords * in.size_px;
+        let r = in.corner_radius;
+        if p.y < r {
+            let center = vec2<f32>(clamp(p.x, r, in.size_px.x - r), r);
+            let coverage = clamp(r - distance(p, center) + 0.5, 0.0, 1.0);
+            color.a *= coverage;
+        }
+    }
+
     // Fade notes at the top of the screen (far future)
     let fade_start = 0.85;
     let fade_end = 0.98;
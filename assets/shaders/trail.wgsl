@@ -0,0 +1,35 @@
+// Trail shader for MIDI-RS Black MIDI Visualizer
+// Fades the persistent trail texture each frame and draws it over the scene
+
+@group(0) @binding(0)
+var trail_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var trail_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// One triangle covering the whole screen, no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    // Texture rows run top to bottom, clip space bottom to top
+    out.tex_coords = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+// The trail holds premultiplied colors, so it is drawn with premultiplied blending
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(trail_texture, trail_sampler, in.tex_coords);
+}
+
+// Subtracted after scaling by the decay, so faint streaks reach zero despite 8-bit rounding
+@fragment
+fn fs_fade(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0 / 255.0);
+}
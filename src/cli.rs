@@ -0,0 +1,243 @@
+// src/cli.rs
+
+use crate::config::{AppConfig, GpuBackend, PowerPreference};
+use std::path::PathBuf;
+
+/// Config file used when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = "config.json";
+
+/// Options given on the command line
+///
+/// Settings flags override the config file for one run only: [`CommandLine::apply`] puts
+/// them on top of the loaded config and [`CommandLine::restore`] takes them back out
+/// before it is saved again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandLine {
+    /// MIDI files for the playlist, with `-` reading one from standard input
+    pub playlist: Vec<PathBuf>,
+    /// `--stress N`: synthesize N notes instead of loading files
+    pub stress_note_count: Option<usize>,
+    /// `--backend NAME`: graphics API to render with
+    pub backend: Option<GpuBackend>,
+    /// `--power high|low`: which GPU to prefer
+    pub power_preference: Option<PowerPreference>,
+    /// `--control-window`: open the control window
+    pub control_window: bool,
+    /// `--live`: play notes from a MIDI device instead of files
+    pub live: bool,
+    /// `--midi-port NAME`: MIDI port to play live or practice with, implies `--live`
+    /// unless `--practice` is given
+    pub midi_port: Option<String>,
+    /// `--practice`: wait at every chord of the files until it is played on the MIDI device
+    pub practice: bool,
+    /// `--list-midi-ports`: print the MIDI input ports and exit
+    pub list_midi_ports: bool,
+    /// `--config PATH`: config file to load and save instead of `config.json`
+    pub config_file: Option<String>,
+    /// `--fullscreen`: start in fullscreen
+    pub fullscreen: bool,
+    /// `--speed X`: initial playback speed
+    pub speed: Option<f32>,
+    /// `--fps N`: FPS cap, 0 renders uncapped
+    pub fps: Option<u32>,
+    /// `--no-overlay`: start with the performance overlay hidden
+    pub no_overlay: bool,
+    /// `--key-range LOW-HIGH`: lowest and highest MIDI key shown
+    pub key_range: Option<[u8; 2]>,
+}
+
+impl CommandLine {
+    /// Parse the arguments after the program name
+    /// Flags with missing or invalid values are skipped with a warning
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut cli = CommandLine::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--stress" => match args.next().and_then(|count| count.parse().ok()) {
+                    Some(count) => cli.stress_note_count = Some(count),
+                    None => log::warn!("--stress expects a note count"),
+                },
+                "--backend" => match args.next().as_deref().and_then(GpuBackend::from_name) {
+                    Some(backend) => cli.backend = Some(backend),
+                    None => log::warn!("--backend expects one of: auto, vulkan, dx12, metal, gl"),
+                },
+                "--power" => match args.next().as_deref().and_then(PowerPreference::from_name) {
+                    Some(preference) => cli.power_preference = Some(preference),
+                    None => log::warn!("--power expects high or low"),
+                },
+                "--control-window" => cli.control_window = true,
+                "--live" => cli.live = true,
+                "--midi-port" => match args.next() {
+                    Some(port) => {
+                        cli.midi_port = Some(port);
+                        cli.live = true;
+                    }
+                    None => log::warn!("--midi-port expects a port name"),
+                },
+                "--practice" => cli.practice = true,
+                "--list-midi-ports" => cli.list_midi_ports = true,
+                "--config" => match args.next() {
+                    Some(path) => cli.config_file = Some(path),
+                    None => log::warn!("--config expects a file path"),
+                },
+                "--fullscreen" => cli.fullscreen = true,
+                "--speed" => match args.next().and_then(|speed| speed.parse::<f32>().ok()) {
+                    Some(speed) if speed > 0.0 => cli.speed = Some(speed),
+                    _ => log::warn!("--speed expects a playback speed, e.g. 1.5"),
+                },
+                "--fps" => match args.next().and_then(|fps| fps.parse().ok()) {
+                    Some(fps) => cli.fps = Some(fps),
+                    None => log::warn!("--fps expects a frame rate, 0 for uncapped"),
+                },
+                "--no-overlay" => cli.no_overlay = true,
+                "--key-range" => match args.next().as_deref().and_then(parse_key_range) {
+                    Some(range) => cli.key_range = Some(range),
+                    None => log::warn!("--key-range expects the lowest and highest key (0-127), e.g. 21-108"),
+                },
+                _ => cli.playlist.push(PathBuf::from(arg)),
+            }
+        }
+        // Practice plays the files, the MIDI port is only listened to
+        if cli.practice {
+            cli.live = false;
+        }
+        cli
+    }
+
+    /// Get the config file to load and save
+    pub fn config_file(&self) -> &str {
+        self.config_file.as_deref().unwrap_or(DEFAULT_CONFIG_FILE)
+    }
+
+    /// Override the config with the settings given on the command line
+    pub fn apply(&self, config: &mut AppConfig) {
+        if let Some(backend) = self.backend {
+            config.display.backend = backend;
+        }
+        if let Some(preference) = self.power_preference {
+            config.display.power_preference = preference;
+        }
+        if self.control_window {
+            config.display.control_window = true;
+        }
+        if let Some(fps) = self.fps {
+            config.performance.frame_lock = Some(fps);
+        }
+        if self.no_overlay {
+            config.performance.enable_performance_overlay = false;
+        }
+        if let Some(range) = self.key_range {
+            config.display.key_range = range;
+        }
+    }
+
+    /// Put the settings overridden by [`CommandLine::apply`] back to their `original` values,
+    /// so the overrides are not saved
+    ///
+    /// A setting is only put back while it still has the overriding value; one changed while
+    /// running is kept.
+    pub fn restore(&self, config: &mut AppConfig, original: &AppConfig) {
+        if self.backend.is_some_and(|backend| config.display.backend == backend) {
+            config.display.backend = original.display.backend;
+        }
+        if self.power_preference.is_some_and(|preference| config.display.power_preference == preference) {
+            config.display.power_preference = original.display.power_preference;
+        }
+        if self.control_window && config.display.control_window {
+            config.display.control_window = original.display.control_window;
+        }
+        if self.fps.is_some() && config.performance.frame_lock == self.fps {
+            config.performance.frame_lock = original.performance.frame_lock;
+        }
+        if self.no_overlay && !config.performance.enable_performance_overlay {
+            config.performance.enable_performance_overlay = original.performance.enable_performance_overlay;
+        }
+        if self.key_range.is_some_and(|range| config.display.key_range == range) {
+            config.display.key_range = original.display.key_range;
+        }
+    }
+}
+
+/// Parse a `LOW-HIGH` key range of MIDI keys, e.g. `21-108` for an 88-key piano
+fn parse_key_range(range: &str) -> Option<[u8; 2]> {
+    let (low, high) = range.split_once('-')?;
+    let low: u8 = low.trim().parse().ok()?;
+    let high: u8 = high.trim().parse().ok()?;
+    (low <= high && high < 128).then_some([low, high])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CommandLine {
+        CommandLine::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let cli = parse(&[
+            "song.mid",
+            "--fullscreen",
+            "--speed",
+            "1.5",
+            "--fps",
+            "144",
+            "--config",
+            "alt.json",
+            "--no-overlay",
+            "--key-range",
+            "21-108",
+            "-",
+        ]);
+        // Files keep working around the flags
+        assert_eq!(cli.playlist, vec![PathBuf::from("song.mid"), PathBuf::from("-")]);
+        assert!(cli.fullscreen && cli.no_overlay);
+        assert_eq!(cli.speed, Some(1.5));
+        assert_eq!(cli.fps, Some(144));
+        assert_eq!(cli.config_file(), "alt.json");
+        assert_eq!(cli.key_range, Some([21, 108]));
+
+        assert_eq!(parse(&[]).config_file(), DEFAULT_CONFIG_FILE);
+        // Invalid values are skipped
+        let cli = parse(&["--key-range", "108-21", "--speed", "fast", "--midi-port", "Keys"]);
+        assert_eq!(cli.key_range, None);
+        assert_eq!(cli.speed, None);
+        assert!(cli.live);
+        assert!(cli.playlist.is_empty());
+
+        let cli = parse(&["--midi-port", "Keys", "--practice", "song.mid"]);
+        assert!(cli.practice && !cli.live);
+        assert_eq!(cli.midi_port.as_deref(), Some("Keys"));
+    }
+
+    #[test]
+    fn test_overrides_are_not_saved() {
+        let cli = parse(&["--fps", "0", "--no-overlay", "--key-range", "36-96"]);
+        let mut original = AppConfig::default();
+        original.performance.enable_performance_overlay = true;
+        let mut config = original.clone();
+        cli.apply(&mut config);
+        assert_eq!(config.performance.frame_lock, Some(0));
+        assert!(!config.performance.enable_performance_overlay);
+        assert_eq!(config.display.key_range, [36, 96]);
+
+        // Changes made while running are kept, the overrides are taken back out
+        config.display.show_minimap = false;
+        cli.restore(&mut config, &original);
+        assert_eq!(config.performance.frame_lock, original.performance.frame_lock);
+        assert!(config.performance.enable_performance_overlay);
+        assert_eq!(config.display.key_range, original.display.key_range);
+        assert!(!config.display.show_minimap);
+
+        // An overridden setting changed while running keeps the change
+        cli.apply(&mut config);
+        config.display.key_range = [21, 108];
+        config.performance.frame_lock = Some(30);
+        cli.restore(&mut config, &original);
+        assert_eq!(config.display.key_range, [21, 108]);
+        assert_eq!(config.performance.frame_lock, Some(30));
+        assert!(config.performance.enable_performance_overlay);
+    }
+}
@@ -3,22 +3,247 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DisplayConfig {
     pub width: u32,
     pub height: u32,
     pub target_fps: u32,
+    /// Clear color as sRGB [r, g, b, a] (0.0 to 1.0), e.g. a hex color divided by 255
     pub background_color: [f32; 4],
+    /// Let the desktop show through where the background alpha is below 1, e.g. to overlay the
+    /// notes on a game capture; needs a compositor and a GPU supporting transparent surfaces
+    pub transparent_window: bool,
+    /// Width of falling notes as a fraction of their key slot (0.1 to 1.0)
     pub note_width: f32,
     pub note_height: f32,
+    /// Gap in pixels left between consecutive notes and inset from the key slot edges
+    pub note_gap: f32,
+    /// Width in pixels of a darker outline around every note, keeping chord notes apart (0 = off)
+    pub note_outline_width: f32,
+    /// How strongly notes show their envelope (0.0 to 1.0, 0.0 = flat fill): the attack at
+    /// the start of the note is brighter and the rest tapers off toward its end
+    pub note_envelope: f32,
+    /// How much notes slow down as they approach the playhead and speed up past it (0.0 to
+    /// 0.9, 0.0 = constant scroll); they still cross the playhead exactly when they sound
+    pub playhead_easing: f32,
+    /// Opacity of falling notes (0.0 to 1.0); below 1.0 overlapping notes show through each other
+    pub note_opacity: f32,
+    /// How overlapping falling notes combine, see [`NoteBlend`]
+    pub note_blend: NoteBlend,
+    /// Outline of the falling notes, see [`NoteShape`]; the piano keys keep their own shape
+    pub note_shape: NoteShape,
+    /// Surface presentation mode, see [`PresentMode`]
+    pub present_mode: PresentMode,
+    /// Graphics API to render with, see [`GpuBackend`]
+    pub backend: GpuBackend,
+    /// Which GPU to prefer on systems with more than one, see [`PowerPreference`]
+    pub power_preference: PowerPreference,
+    /// Show the song overview strip along the right edge
+    pub show_minimap: bool,
+    /// Show a slow, faint color drift over the background while no file is loaded or playback
+    /// is paused at the start, instead of a still window
+    pub idle_animation: bool,
+    /// Show a gauge along the left edge of how many notes per second pass the playhead
+    pub show_density_gauge: bool,
+    /// Show how often each key is played across the song as bars above the piano
+    pub show_pitch_histogram: bool,
+    /// Draw faint guide lines above every C key and label the C keys with their octave
+    pub show_octave_lines: bool,
+    /// Draw a line across the screen where notes are hit
+    pub show_playhead: bool,
+    /// Draw the piano keyboard along the bottom; hidden, the falling notes use the whole window
+    pub show_piano: bool,
+    /// Height of the piano keyboard as a fraction of the window height (0.05 to 0.4)
+    pub piano_height: f32,
+    /// Radius in pixels of the rounded bottom corners of the piano keys (0 = square keys)
+    pub key_corner_radius: f32,
+    /// Lowest and highest MIDI key shown (0-127), e.g. [21, 108] for an 88-key piano;
+    /// the keys in between fill the window width
+    pub key_range: [u8; 2],
+    /// Fit `key_range` to the keys each song plays when it loads, as the fit key range
+    /// command does
+    pub auto_fit_key_range: bool,
+    /// Show synced lyrics of karaoke files above the playhead
+    pub show_lyrics: bool,
+    /// Stack the tracks of the file in separate lanes instead of overlaying them
+    pub track_lanes: bool,
+    /// Open a second window with the buttons and statistics, keeping the main window clean
+    pub control_window: bool,
+    pub playhead_color: [f32; 4],
+    /// Thickness of the playhead line in pixels
+    pub playhead_thickness: f32,
+    /// Saturation and brightness of the channel colors, see [`HsvParams`]
+    pub note_colors: HsvParams,
+}
+
+/// Saturation and brightness of the note colors; the hue comes from the channel
+///
+/// A note's brightness is `base_value + velocity * velocity_weight`, with the velocity
+/// from 0.0 to 1.0 and the result capped at 1.0.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HsvParams {
+    /// Color saturation (0.0 gray to 1.0 vivid)
+    pub saturation: f32,
+    /// Brightness of the softest notes (0.0 to 1.0)
+    pub base_value: f32,
+    /// Brightness added by full velocity
+    pub velocity_weight: f32,
+}
+
+impl Default for HsvParams {
+    fn default() -> Self {
+        HsvParams {
+            saturation: 0.8,
+            base_value: 0.5,
+            velocity_weight: 0.5,
+        }
+    }
+}
+
+impl HsvParams {
+    /// Get the brightness of a note with `intensity` (0.0 to 1.0), the HSV value
+    pub fn value(&self, intensity: f32) -> f32 {
+        (self.base_value + intensity.clamp(0.0, 1.0) * self.velocity_weight).clamp(0.0, 1.0)
+    }
+}
+
+/// How rendered frames are presented to the window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PresentMode {
+    /// Vsync, supported everywhere
+    AutoVsync,
+    /// No vsync where supported, falling back to vsync otherwise
+    AutoNoVsync,
+    /// Present immediately; uncapped FPS with possible tearing
+    Immediate,
+    /// Low-latency vsync that replaces queued frames
+    Mailbox,
+}
+
+/// Graphics API used for rendering
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum GpuBackend {
+    /// Pick the best available API
+    Auto,
+    Vulkan,
+    Dx12,
+    Metal,
+    /// OpenGL / OpenGL ES
+    Gl,
+}
+
+impl GpuBackend {
+    /// Parse a backend name such as `vulkan` or `DX12`, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(GpuBackend::Auto),
+            "vulkan" => Some(GpuBackend::Vulkan),
+            "dx12" => Some(GpuBackend::Dx12),
+            "metal" => Some(GpuBackend::Metal),
+            "gl" | "opengl" => Some(GpuBackend::Gl),
+            _ => None,
+        }
+    }
+}
+
+/// Which GPU to prefer when several are available, e.g. on hybrid-GPU laptops
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PowerPreference {
+    /// Prefer the discrete GPU
+    HighPerformance,
+    /// Prefer the integrated GPU to save power
+    LowPower,
+}
+
+impl PowerPreference {
+    /// Parse `high` / `high-performance` or `low` / `low-power`, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "high" | "high-performance" => Some(PowerPreference::HighPerformance),
+            "low" | "low-power" => Some(PowerPreference::LowPower),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct QualityConfig {
     pub max_note_count: u32,
+    /// Which notes are kept when more than `max_note_count` are visible
+    pub note_priority: NotePriority,
+    /// Which overlapping notes are drawn on top
+    pub note_draw_order: NoteDrawOrder,
     pub use_instancing: bool,
+    /// Notes the note buffers are first allocated for, growing by at least doubling when more
+    /// are visible; larger values avoid reallocating during the first seconds of dense files,
+    /// smaller ones save GPU memory on light ones
+    pub instance_batch_size: u32,
     pub frustum_culling: bool,
+    /// Extra time kept beyond the screen edges when culling, as a fraction of the time window (0.0 to 1.0).
+    /// Notes enter the buffer before they scroll in, so they don't pop in at the top on fast scroll
+    pub cull_margin: f32,
     pub particle_density: f32,
     pub effect_quality: EffectQuality,
+    /// Lower the rendered note count automatically when FPS drops
+    pub adaptive: bool,
+    /// Flash notes towards white as they reach the playhead
+    pub note_flash: bool,
+    /// Seconds over which notes fade out after they end below the playhead; 0 keeps them bright
+    pub played_note_fade: f32,
+    /// Grow notes in from their center as they enter at the top of the screen
+    pub note_spawn_animation: bool,
+    /// Fade notes out as they leave past the bottom of the screen
+    pub note_despawn_fade: bool,
+    /// How far upcoming notes fade towards the top of the screen (0.0 to 1.0); 0 disables the focus effect
+    pub focus_falloff: f32,
+    /// Fraction of the note trails kept each frame (0.0 to 0.95); 0 disables trails.
+    /// Only drawn on High and Ultra effect quality
+    pub trail_decay: f32,
+    /// Shade notes with a beveled 3D look instead of a flat fill
+    pub note_shading: bool,
+    /// Bend falling notes sideways along pitch bend events
+    pub pitch_bend: bool,
+    /// Modulate note brightness with aftertouch (pressure) events
+    pub aftertouch: bool,
+    /// Modulate note brightness with channel volume (CC7) and expression (CC11) automation
+    pub channel_volume: bool,
+    /// Simulated synth voices per channel; notes that lose their voice are dimmed. None disables it
+    pub voice_limit: Option<u32>,
+    /// Which note gives up its voice when a channel exceeds `voice_limit`
+    pub voice_stealing: VoiceStealing,
+}
+
+/// Which notes are drawn when more are visible than `quality.max_note_count` allows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NotePriority {
+    /// Keep the notes closest to the playhead, the loudest among equals
+    Playhead,
+    /// Keep the loudest notes, the closest to the playhead among equals
+    Velocity,
+    /// Keep the first notes in file order (the old behavior)
+    FileOrder,
+}
+
+/// Which of the visible notes are drawn last, on top of the notes they overlap
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NoteDrawOrder {
+    /// Later notes in the file on top (the old behavior)
+    FileOrder,
+    /// Louder notes on top
+    Velocity,
+    /// Notes closer to the playhead on top
+    Playhead,
+}
+
+/// Which sounding note a synth drops when it runs out of voices
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum VoiceStealing {
+    /// Drop the note that started first
+    Oldest,
+    /// Drop the note with the lowest velocity, the oldest among equals
+    Quietest,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -29,26 +254,181 @@ pub enum EffectQuality {
     Ultra,
 }
 
+/// Screen corner the performance overlay is anchored to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OverlayPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PerformanceConfig {
     pub enable_performance_overlay: bool,
+    pub overlay_position: OverlayPosition,
+    /// Slow mode preset: the 30 FPS cap combined with reduced effects
     pub slow_mode: bool,
+    /// Halve particle density to lower the visual load
+    pub reduced_effects: bool,
     pub playback_speed: f32,
+    /// FPS cap overriding `display.target_fps`; `Some(0)` renders uncapped
     pub frame_lock: Option<u32>,
+    /// Lowest frame rate playback keeps pace with; after a slower frame, e.g. a hitch, the
+    /// time beyond `1 / min_fps` is skipped rather than caught up on, so playback slows down
+    /// instead of the frames falling further behind (1 to 240)
+    pub min_fps: u32,
+    /// Pause playback when the window loses focus
+    pub pause_on_focus_loss: bool,
+    /// Resume playback on focus regain if it was paused by focus loss
+    pub resume_on_focus_gain: bool,
+    /// Minimum time between window title updates (in seconds); 0.0 updates every frame
+    pub title_update_interval: f32,
+    /// Time between refreshes of the overlay statistics while playing (in seconds); 0.0
+    /// refreshes every frame
+    pub overlay_update_interval: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MidiConfig {
     pub streaming_enabled: bool,
     pub buffer_size: usize,
+    /// Notes shorter than this (in seconds) are dropped when parsing; 0.0 keeps everything
+    pub min_note_duration: f32,
+    /// Notes after this time (in seconds) are dropped when parsing; None keeps everything
+    pub max_song_duration: Option<f32>,
+    /// Duration (in seconds) of notes never released before the end of their track
+    pub unclosed_note_duration: f32,
+    /// Pitch bend range in semitones at full bend
+    pub pitch_bend_range: f32,
+    /// Ticks per beat used instead of the file header's, for files that play far too fast or
+    /// slow; None keeps the file's
+    pub forced_ticks_per_beat: Option<u16>,
+    /// Tempo in BPM for the whole song, replacing the file's tempo events; None keeps them
+    pub forced_tempo: Option<f32>,
+}
+
+/// Selectable minimum note durations (in seconds) for runtime adjustment
+const MIN_NOTE_DURATION_STEPS: &[f32] = &[0.0, 0.001, 0.002, 0.005, 0.01, 0.02, 0.05];
+
+/// How overlapping falling notes are blended
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NoteBlend {
+    /// Notes in front cover the ones behind them
+    Alpha,
+    /// Overlapping notes add up and glow brighter where they are dense
+    Additive,
+}
+
+/// Outline of the falling notes, cut out of their quad by the note shader
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NoteShape {
+    /// Hard-edged rectangles
+    Rectangle,
+    /// Rectangles with rounded corners
+    Rounded,
+    /// Rectangles with fully rounded ends, like a pill
+    Capsule,
+    /// A diamond touching the middle of each side of the note
+    Diamond,
+}
+
+/// How notes on muted channels are drawn
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum GhostMode {
+    /// Muted channels are not drawn at all
+    Hide,
+    /// Muted channels are drawn as faint gray notes
+    Ghost,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelConfig {
+    /// Muted MIDI channels (0-15)
+    pub muted: Vec<u8>,
+    /// Soloed MIDI channels (0-15); while any are soloed all other channels are muted
+    pub soloed: Vec<u8>,
+    pub ghost_mode: GhostMode,
+    /// Alpha multiplier for ghosted notes
+    pub ghost_alpha: f32,
+}
+
+/// Keyboard control settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlsConfig {
+    /// Playback speed change per press of Up/Down
+    pub speed_step: f32,
+    /// Seconds seeked per press of Left/Right
+    pub seek_step: f32,
+    /// Seconds a playback speed change takes to take full effect; 0.0 changes it instantly
+    pub speed_ramp: f32,
+    /// Keep adjusting speed and position while the key is held
+    pub key_repeat: bool,
+    /// Adjustments per second while a key is held
+    pub key_repeat_rate: f32,
 }
 
+/// Settings for rendering frames to export, apart from the interactive window
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Frames are rendered this many times larger and scaled down for smoother edges (1 to 4)
+    pub supersample: u32,
+}
+
+/// Which hand's notes stand out while practicing, see [`HandsConfig`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum HandFocus {
+    /// Both hands are drawn as usual
+    Both,
+    /// The notes below the split key
+    Left,
+    /// The notes from the split key up
+    Right,
+}
+
+/// Practicing one hand: its notes are highlighted while the other hand's are dimmed
+///
+/// The hands are split at a key, since most piano files don't say which hand plays what.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandsConfig {
+    /// Lowest key of the right hand (MIDI key, default 60 = middle C)
+    pub split_key: u8,
+    /// Hand in focus
+    pub focus: HandFocus,
+    /// Opacity of the other hand's notes (0.0 to 1.0), which are drawn in gray
+    pub unfocused_opacity: f32,
+    /// How far the focused hand's notes are brightened towards white (0.0 to 1.0)
+    pub highlight: f32,
+}
+
+/// State remembered between runs of the application
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Path of the MIDI file that was loaded when the application last exited
+    pub last_file: Option<String>,
+    /// Outer window position in physical pixels
+    pub window_position: Option<[i32; 2]>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub display: DisplayConfig,
     pub quality: QualityConfig,
     pub performance: PerformanceConfig,
     pub midi: MidiConfig,
+    pub channels: ChannelConfig,
+    pub controls: ControlsConfig,
+    pub export: ExportConfig,
+    pub hands: HandsConfig,
+    pub session: SessionConfig,
 }
 
 impl Default for DisplayConfig {
@@ -58,20 +438,191 @@ impl Default for DisplayConfig {
             height: 1080,
             target_fps: 60,
             background_color: [0.05, 0.05, 0.05, 1.0],
-            note_width: 2.0,
+            transparent_window: false,
+            note_width: 0.9,
             note_height: 0.15,
+            note_gap: 1.0,
+            note_outline_width: 0.0,
+            note_envelope: 0.0,
+            playhead_easing: 0.0,
+            note_opacity: 1.0,
+            note_blend: NoteBlend::Alpha,
+            note_shape: NoteShape::Rectangle,
+            present_mode: PresentMode::AutoVsync,
+            backend: GpuBackend::Auto,
+            power_preference: PowerPreference::HighPerformance,
+            show_minimap: true,
+            idle_animation: true,
+            show_density_gauge: false,
+            show_pitch_histogram: false,
+            show_octave_lines: true,
+            show_playhead: true,
+            show_piano: true,
+            piano_height: 0.12,
+            key_corner_radius: 3.0,
+            key_range: [0, 127],
+            auto_fit_key_range: false,
+            show_lyrics: true,
+            track_lanes: false,
+            control_window: false,
+            playhead_color: [1.0, 1.0, 1.0, 0.8],
+            playhead_thickness: 2.0,
+            note_colors: HsvParams::default(),
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Get `note_width` clamped to a usable fraction of the key slot
+    /// Values above 1.0 (such as the old default of 2.0) fill the whole slot
+    pub fn note_width_fraction(&self) -> f32 {
+        if self.note_width.is_finite() {
+            self.note_width.clamp(0.1, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Get `note_outline_width` limited to 0 to 4 pixels
+    pub fn note_outline_px(&self) -> f32 {
+        if self.note_outline_width.is_finite() {
+            self.note_outline_width.clamp(0.0, 4.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Get `note_envelope` limited to 0 to 1
+    pub fn note_envelope_strength(&self) -> f32 {
+        if self.note_envelope.is_finite() {
+            self.note_envelope.clamp(0.0, 1.0)
+        } else {
+            0.0
         }
     }
+
+    /// Get `playhead_easing` limited to 0 to 0.9; at 1 notes would stand still on the playhead
+    pub fn playhead_easing_strength(&self) -> f32 {
+        if self.playhead_easing.is_finite() {
+            self.playhead_easing.clamp(0.0, 0.9)
+        } else {
+            0.0
+        }
+    }
+
+    /// Get `key_corner_radius` limited to 0 to 12 pixels
+    pub fn key_corner_px(&self) -> f32 {
+        if self.key_corner_radius.is_finite() {
+            self.key_corner_radius.clamp(0.0, 12.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Get the top edge of the piano keyboard (normalized 0-1), 0.0 when it is hidden
+    pub fn piano_top(&self) -> f32 {
+        if !self.show_piano {
+            0.0
+        } else if self.piano_height.is_finite() {
+            self.piano_height.clamp(0.05, 0.4)
+        } else {
+            0.12
+        }
+    }
+
+    /// Get the height of the playhead above the bottom of the window (normalized 0-1),
+    /// a small gap above the piano
+    pub fn playhead_position(&self) -> f32 {
+        self.piano_top() + PLAYHEAD_GAP
+    }
+
+    /// Get the lowest and highest key shown, in order and within the MIDI range
+    pub fn key_bounds(&self) -> (u8, u8) {
+        let [a, b] = self.key_range;
+        (a.min(b).min(127), a.max(b).min(127))
+    }
+
+    /// Show the keys from `low` to `high`, e.g. the range a song plays, with a few keys to
+    /// spare on each side
+    pub fn fit_key_range(&mut self, low: u8, high: u8) {
+        let (low, high) = (low.min(high).min(127), low.max(high).min(127));
+        self.key_range = [low.saturating_sub(KEY_RANGE_FIT_MARGIN), (high + KEY_RANGE_FIT_MARGIN).min(127)];
+    }
+
+    /// Get how many times wider keys are drawn than with all 128 keys on screen
+    pub fn key_scale(&self) -> f32 {
+        let (low, high) = self.key_bounds();
+        128.0 / (high - low + 1) as f32
+    }
+
+    /// Map a horizontal position in the full 128-key layout to the window, with the shown
+    /// key range stretched to the full width
+    pub fn key_x(&self, x: f32) -> f32 {
+        let (low, _) = self.key_bounds();
+        (x - low as f32 / 128.0) * self.key_scale()
+    }
 }
 
+/// Keys left on each side of the played ones when fitting the key range to a song
+const KEY_RANGE_FIT_MARGIN: u8 = 2;
+
+/// Space between the top of the piano and the playhead (normalized 0-1)
+const PLAYHEAD_GAP: f32 = 0.03;
+
 impl Default for QualityConfig {
     fn default() -> Self {
         QualityConfig {
             max_note_count: 1_000_000,
+            note_priority: NotePriority::Playhead,
+            note_draw_order: NoteDrawOrder::FileOrder,
             use_instancing: true,
+            instance_batch_size: 10_000,
             frustum_culling: true,
+            cull_margin: 0.1,
             particle_density: 1.0,
             effect_quality: EffectQuality::High,
+            adaptive: false,
+            note_flash: true,
+            played_note_fade: 0.0,
+            note_spawn_animation: false,
+            note_despawn_fade: false,
+            focus_falloff: 0.0,
+            trail_decay: 0.0,
+            note_shading: true,
+            pitch_bend: true,
+            aftertouch: true,
+            channel_volume: true,
+            voice_limit: None,
+            voice_stealing: VoiceStealing::Oldest,
+        }
+    }
+}
+
+impl QualityConfig {
+    /// Get the initial note buffer capacity, at least one note
+    pub fn instance_batch_size(&self) -> u32 {
+        self.instance_batch_size.max(1)
+    }
+
+    /// Get the culling margin as a fraction of the time window, limited to 0.0 - 1.0
+    pub fn cull_margin(&self) -> f32 {
+        self.cull_margin.clamp(0.0, 1.0)
+    }
+
+    /// Get the note shading strength, flat (0.0) when disabled or on low effect quality
+    pub fn note_shading_strength(&self) -> f32 {
+        if self.note_shading && self.effect_quality != EffectQuality::Low {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Get the note trail decay, 0.0 when trails are off or below High effect quality
+    pub fn trail_decay_strength(&self) -> f32 {
+        match self.effect_quality {
+            EffectQuality::High | EffectQuality::Ultra => self.trail_decay.clamp(0.0, 0.95),
+            EffectQuality::Low | EffectQuality::Medium => 0.0,
         }
     }
 }
@@ -80,31 +631,188 @@ impl Default for PerformanceConfig {
     fn default() -> Self {
         PerformanceConfig {
             enable_performance_overlay: false,
+            overlay_position: OverlayPosition::TopLeft,
             slow_mode: false,
+            reduced_effects: false,
             playback_speed: 1.0,
             frame_lock: None,
+            min_fps: 15,
+            pause_on_focus_loss: false,
+            resume_on_focus_gain: true,
+            title_update_interval: 0.25,
+            overlay_update_interval: 0.25,
         }
     }
 }
 
+/// FPS cap applied by slow mode
+pub const SLOW_MODE_FPS: u32 = 30;
+
+/// FPS caps cycled through by the frame lock control (0 = uncapped)
+const FRAME_LOCK_STEPS: &[u32] = &[30, 60, 120, 144, 0];
+
+impl PerformanceConfig {
+    /// Get the active FPS cap, or None when rendering uncapped
+    pub fn frame_cap(&self, target_fps: u32) -> Option<u32> {
+        match self.frame_lock.unwrap_or(target_fps) {
+            0 => None,
+            fps => Some(fps),
+        }
+    }
+
+    /// Get the lowest frame rate playback keeps pace with, limited to a sane range
+    pub fn min_fps(&self) -> u32 {
+        self.min_fps.clamp(1, 240)
+    }
+
+    /// Switch `frame_lock` to the next common FPS cap
+    pub fn cycle_frame_lock(&mut self) {
+        let next = self
+            .frame_lock
+            .and_then(|lock| FRAME_LOCK_STEPS.iter().position(|&step| step == lock))
+            .map_or(0, |index| (index + 1) % FRAME_LOCK_STEPS.len());
+        self.frame_lock = Some(FRAME_LOCK_STEPS[next]);
+    }
+}
+
 impl Default for MidiConfig {
     fn default() -> Self {
         MidiConfig {
             streaming_enabled: true,
             buffer_size: 65536,
+            min_note_duration: 0.001,
+            max_song_duration: None,
+            unclosed_note_duration: 0.1,
+            pitch_bend_range: 2.0,
+            forced_ticks_per_beat: None,
+            forced_tempo: None,
+        }
+    }
+}
+
+impl MidiConfig {
+    /// Move `min_note_duration` to the next larger or smaller step
+    pub fn step_min_note_duration(&mut self, increase: bool) {
+        let current = self.min_note_duration;
+        let next = if increase {
+            MIN_NOTE_DURATION_STEPS.iter().copied().find(|&d| d > current)
+        } else {
+            MIN_NOTE_DURATION_STEPS.iter().copied().rev().find(|&d| d < current)
+        };
+        if let Some(duration) = next {
+            self.min_note_duration = duration;
+        }
+    }
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        ChannelConfig {
+            muted: Vec::new(),
+            soloed: Vec::new(),
+            ghost_mode: GhostMode::Ghost,
+            ghost_alpha: 0.15,
+        }
+    }
+}
+
+impl Default for ControlsConfig {
+    fn default() -> Self {
+        ControlsConfig {
+            speed_step: 0.1,
+            seek_step: 5.0,
+            speed_ramp: 0.1,
+            key_repeat: true,
+            key_repeat_rate: 15.0,
         }
     }
 }
 
-impl Default for AppConfig {
+impl Default for ExportConfig {
     fn default() -> Self {
-        AppConfig {
-            display: DisplayConfig::default(),
-            quality: QualityConfig::default(),
-            performance: PerformanceConfig::default(),
-            midi: MidiConfig::default(),
+        ExportConfig { supersample: 2 }
+    }
+}
+
+impl ExportConfig {
+    /// Get the supersampling factor, limited to 1 - 4
+    pub fn supersample(&self) -> u32 {
+        self.supersample.clamp(1, 4)
+    }
+}
+
+impl Default for HandsConfig {
+    fn default() -> Self {
+        HandsConfig {
+            split_key: 60,
+            focus: HandFocus::Both,
+            unfocused_opacity: 0.3,
+            highlight: 0.2,
+        }
+    }
+}
+
+impl HandsConfig {
+    /// Check whether notes on `pitch` belong to the hand out of focus
+    pub fn is_unfocused(&self, pitch: u8) -> bool {
+        match self.focus {
+            HandFocus::Both => false,
+            HandFocus::Left => pitch >= self.split_key,
+            HandFocus::Right => pitch < self.split_key,
         }
     }
+
+    /// Focus the right hand, then the left one, then both again
+    pub fn cycle_focus(&mut self) {
+        self.focus = match self.focus {
+            HandFocus::Both => HandFocus::Right,
+            HandFocus::Right => HandFocus::Left,
+            HandFocus::Left => HandFocus::Both,
+        };
+    }
+}
+
+impl ChannelConfig {
+    /// Check whether a channel is silenced: by another channel's solo, or by its own mute
+    /// unless it is soloed itself
+    pub fn is_muted(&self, channel: u8) -> bool {
+        if self.soloed.is_empty() {
+            self.muted.contains(&channel)
+        } else {
+            !self.soloed.contains(&channel)
+        }
+    }
+
+    pub fn is_soloed(&self, channel: u8) -> bool {
+        self.soloed.contains(&channel)
+    }
+
+    /// Mute or unmute a channel
+    pub fn toggle_mute(&mut self, channel: u8) {
+        toggle_channel(&mut self.muted, channel);
+    }
+
+    /// Solo a channel or take its solo back
+    pub fn toggle_solo(&mut self, channel: u8) {
+        toggle_channel(&mut self.soloed, channel);
+    }
+
+    pub fn toggle_ghost_mode(&mut self) {
+        self.ghost_mode = match self.ghost_mode {
+            GhostMode::Hide => GhostMode::Ghost,
+            GhostMode::Ghost => GhostMode::Hide,
+        };
+    }
+}
+
+/// Add a channel to a sorted channel list or remove it
+fn toggle_channel(channels: &mut Vec<u8>, channel: u8) {
+    if let Some(index) = channels.iter().position(|&c| c == channel) {
+        channels.remove(index);
+    } else {
+        channels.push(channel);
+        channels.sort_unstable();
+    }
 }
 
 impl AppConfig {
@@ -122,12 +830,45 @@ impl AppConfig {
 
     pub fn slow_mode_config() -> Self {
         let mut config = AppConfig::default();
-        config.performance.slow_mode = true;
-        config.performance.frame_lock = Some(30);
-        config.quality.particle_density = 0.5;
+        config.set_slow_mode(true);
         config
     }
 
+    /// Enable or disable slow mode, setting both the FPS cap and reduced effects
+    pub fn set_slow_mode(&mut self, enabled: bool) {
+        self.performance.frame_lock = if enabled { Some(SLOW_MODE_FPS) } else { None };
+        self.set_reduced_effects(enabled);
+    }
+
+    /// Toggle slow mode
+    pub fn toggle_slow_mode(&mut self) {
+        self.set_slow_mode(!self.performance.slow_mode);
+    }
+
+    /// Enable or disable reduced effects without touching the FPS cap
+    pub fn set_reduced_effects(&mut self, enabled: bool) {
+        self.performance.reduced_effects = enabled;
+        self.quality.particle_density = if enabled { 0.5 } else { 1.0 };
+        self.sync_slow_mode();
+    }
+
+    /// Toggle reduced effects
+    pub fn toggle_reduced_effects(&mut self) {
+        self.set_reduced_effects(!self.performance.reduced_effects);
+    }
+
+    /// Switch to the next common FPS cap without touching effects
+    pub fn cycle_frame_lock(&mut self) {
+        self.performance.cycle_frame_lock();
+        self.sync_slow_mode();
+    }
+
+    /// Slow mode is active exactly when both of its parts are
+    fn sync_slow_mode(&mut self) {
+        self.performance.slow_mode =
+            self.performance.frame_lock == Some(SLOW_MODE_FPS) && self.performance.reduced_effects;
+    }
+
     pub fn performance_mode_config() -> Self {
         let mut config = AppConfig::default();
         config.performance.slow_mode = false;
@@ -3,5 +3,16 @@
 pub mod midi;
 pub mod renderer;
 pub mod config;
+pub mod cli;
+pub mod live;
 pub mod performance;
-pub mod ui;
\ No newline at end of file
+pub mod practice;
+pub mod session;
+pub mod ui;
+/// The types most programs using the library need, for a single `use midi_rs::prelude::*`
+pub mod prelude;
+
+// The main types, also at the top level so they don't need the module path
+pub use config::AppConfig;
+pub use midi::{MidiParser, MidiPlayer, Note, ParseError};
+pub use renderer::{PipelineError, RenderPipeline, Visualizer};
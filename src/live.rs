@@ -0,0 +1,285 @@
+// src/live.rs
+
+use crate::midi::Note;
+use std::collections::HashMap;
+
+/// Shortest on-screen duration of a live note, so quick taps are still visible (in seconds)
+const MIN_LIVE_NOTE_DURATION: f32 = 0.05;
+/// How far device time may drift from playback time before the clocks are lined up again,
+/// e.g. after a reset or a speed change (in seconds)
+const MAX_CLOCK_DRIFT: f64 = 1.0;
+
+/// Error types for live MIDI input
+#[derive(Debug)]
+pub enum LiveError {
+    /// The application was built without the `live` feature
+    Unsupported,
+    /// No MIDI input ports are connected
+    NoPorts,
+    /// No port name contains the requested text
+    PortNotFound(String),
+    /// The MIDI backend failed to open or connect
+    Backend(String),
+}
+
+impl std::fmt::Display for LiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiveError::Unsupported => write!(f, "live MIDI input needs a build with the `live` feature"),
+            LiveError::NoPorts => write!(f, "no MIDI input ports found"),
+            LiveError::PortNotFound(name) => write!(f, "no MIDI input port matches \"{}\"", name),
+            LiveError::Backend(e) => write!(f, "MIDI backend error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LiveError {}
+
+/// Notes played on a live MIDI device, turned into [`Note`]s the renderers can draw
+///
+/// Notes start at the playback time their note-on arrived; held notes grow until they are
+/// released. Feed raw messages with [`Self::handle_message`] and get the current notes with
+/// [`Self::snapshot`] once per frame.
+#[derive(Debug, Default)]
+pub struct LiveNotes {
+    /// Released notes, sorted by start time
+    released: Vec<Note>,
+    /// Held notes: (pitch, channel) -> (start time, velocity)
+    held: HashMap<(u8, u8), (f32, u8)>,
+    /// Playback time minus device time, fixed by the first message
+    clock_offset: Option<f64>,
+    /// Keys pressed since the last [`Self::take_pressed`] or [`Self::snapshot`], including
+    /// ones already released
+    pressed: Vec<u8>,
+}
+
+impl LiveNotes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle a raw MIDI message received at `stamp` microseconds of device time
+    ///
+    /// Device timestamps count from an unspecified point, so the first message is taken to
+    /// arrive at playback time `now` and later ones keep their spacing from it, until the
+    /// two clocks drift apart. Times never run ahead of `now`, so notes don't appear above
+    /// the playhead.
+    pub fn handle_message(&mut self, stamp: u64, message: &[u8], now: f32) {
+        let device_time = stamp as f64 / 1_000_000.0;
+        let mut offset = *self.clock_offset.get_or_insert(now as f64 - device_time);
+        if (device_time + offset - now as f64).abs() > MAX_CLOCK_DRIFT {
+            offset = now as f64 - device_time;
+            self.clock_offset = Some(offset);
+        }
+        let time = ((device_time + offset) as f32).min(now);
+
+        let [status, key, velocity] = match message {
+            &[status, key, velocity, ..] => [status, key & 0x7F, velocity & 0x7F],
+            _ => return,
+        };
+        let channel = status & 0x0F;
+        match status & 0xF0 {
+            // A note-on with zero velocity is a note-off
+            0x90 if velocity > 0 => self.note_on(key, velocity, channel, time),
+            0x80 | 0x90 => self.note_off(key, channel, time),
+            _ => {}
+        }
+    }
+
+    /// Start a note; pressing a held key again restarts it
+    pub fn note_on(&mut self, pitch: u8, velocity: u8, channel: u8, time: f32) {
+        self.note_off(pitch, channel, time);
+        self.held.insert((pitch, channel), (time, velocity));
+        self.pressed.push(pitch);
+    }
+
+    /// Release a held note
+    pub fn note_off(&mut self, pitch: u8, channel: u8, time: f32) {
+        if let Some((start, velocity)) = self.held.remove(&(pitch, channel)) {
+            let duration = (time - start).max(MIN_LIVE_NOTE_DURATION);
+            let note = Note::new(pitch, velocity, start, duration, channel);
+            let index = self.released.partition_point(|other| other.start_time <= start);
+            self.released.insert(index, note);
+        }
+    }
+
+    /// Take the keys pressed since the last call, in the order they were pressed
+    /// Quick taps that were released again before the next frame are included
+    pub fn take_pressed(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pressed)
+    }
+
+    /// Get the pitches of the held notes, for lighting up the piano
+    pub fn held_pitches(&self) -> impl Iterator<Item = u8> + '_ {
+        self.held.keys().map(|&(pitch, _)| pitch)
+    }
+
+    /// Write the notes to draw at playback time `now` into `notes`, sorted by start time
+    ///
+    /// Held notes last until `now`. Notes that ended more than `keep` seconds ago are
+    /// forgotten, as are notes after `now` when playback was moved back.
+    pub fn snapshot(&mut self, now: f32, keep: f32, notes: &mut Vec<Note>) {
+        self.released
+            .retain(|note| note.end_time() >= now - keep && note.start_time <= now);
+        // Live play shows the presses as notes, only practice mode takes them
+        self.pressed.clear();
+
+        notes.clear();
+        notes.extend_from_slice(&self.released);
+        notes.extend(self.held.iter().map(|(&(pitch, channel), &(start, velocity))| {
+            let start = start.min(now);
+            Note::new(pitch, velocity, start, (now - start).max(MIN_LIVE_NOTE_DURATION), channel)
+        }));
+        notes.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    }
+}
+
+#[cfg(feature = "live")]
+mod device {
+    use super::{LiveError, LiveNotes};
+    use midir::{Ignore, MidiInput, MidiInputConnection};
+    use std::sync::mpsc;
+
+    /// Client name shown to the MIDI system
+    const CLIENT_NAME: &str = "MIDI-RS";
+
+    /// A connected MIDI input port
+    pub struct LiveInput {
+        port_name: String,
+        messages: mpsc::Receiver<(u64, Vec<u8>)>,
+        /// Closes the port when dropped
+        _connection: MidiInputConnection<()>,
+    }
+
+    impl LiveInput {
+        /// List the names of the available MIDI input ports
+        pub fn list_ports() -> Result<Vec<String>, LiveError> {
+            let input = MidiInput::new(CLIENT_NAME).map_err(|e| LiveError::Backend(e.to_string()))?;
+            Ok(input
+                .ports()
+                .iter()
+                .filter_map(|port| input.port_name(port).ok())
+                .collect())
+        }
+
+        /// Connect to the first port whose name contains `port`, or the first port if None
+        pub fn connect(port: Option<&str>) -> Result<Self, LiveError> {
+            let mut input = MidiInput::new(CLIENT_NAME).map_err(|e| LiveError::Backend(e.to_string()))?;
+            // Only note messages are used, skip sysex, clock and active sensing
+            input.ignore(Ignore::All);
+
+            let ports = input.ports();
+            let named: Vec<(String, _)> = ports
+                .iter()
+                .filter_map(|p| input.port_name(p).ok().map(|name| (name, p.clone())))
+                .collect();
+            let (port_name, selected) = match port {
+                Some(wanted) => named
+                    .into_iter()
+                    .find(|(name, _)| name.to_lowercase().contains(&wanted.to_lowercase()))
+                    .ok_or_else(|| LiveError::PortNotFound(wanted.to_string()))?,
+                None => named.into_iter().next().ok_or(LiveError::NoPorts)?,
+            };
+
+            let (sender, messages) = mpsc::channel();
+            let connection = input
+                .connect(
+                    &selected,
+                    "midi-rs-input",
+                    move |stamp, message, _| {
+                        let _ = sender.send((stamp, message.to_vec()));
+                    },
+                    (),
+                )
+                .map_err(|e| LiveError::Backend(e.to_string()))?;
+
+            Ok(LiveInput {
+                port_name,
+                messages,
+                _connection: connection,
+            })
+        }
+
+        pub fn port_name(&self) -> &str {
+            &self.port_name
+        }
+
+        /// Pass the messages received since the last call to `notes`
+        pub fn drain(&self, notes: &mut LiveNotes, now: f32) {
+            for (stamp, message) in self.messages.try_iter() {
+                notes.handle_message(stamp, &message, now);
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "live"))]
+mod device {
+    use super::{LiveError, LiveNotes};
+
+    /// Stand-in for a MIDI input port in builds without the `live` feature
+    pub struct LiveInput;
+
+    impl LiveInput {
+        pub fn list_ports() -> Result<Vec<String>, LiveError> {
+            Err(LiveError::Unsupported)
+        }
+
+        pub fn connect(_port: Option<&str>) -> Result<Self, LiveError> {
+            Err(LiveError::Unsupported)
+        }
+
+        pub fn port_name(&self) -> &str {
+            ""
+        }
+
+        pub fn drain(&self, _notes: &mut LiveNotes, _now: f32) {}
+    }
+}
+
+pub use device::LiveInput;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_notes() {
+        let mut live = LiveNotes::new();
+        // The first message lines device time up with playback time
+        live.handle_message(5_000_000, &[0x90, 60, 100], 1.0);
+        live.handle_message(5_500_000, &[0x91, 64, 80], 1.5);
+        // Note-on with zero velocity releases the note
+        live.handle_message(6_000_000, &[0x90, 60, 0], 2.0);
+        // Released keys still count as pressed until they are taken
+        assert_eq!(live.take_pressed(), vec![60, 64]);
+        assert!(live.take_pressed().is_empty());
+
+        let mut notes = Vec::new();
+        live.snapshot(2.5, 10.0, &mut notes);
+        let timings: Vec<(u8, u8, f32, f32)> =
+            notes.iter().map(|n| (n.pitch, n.channel, n.start_time, n.duration)).collect();
+        // The released note keeps its length, the held one reaches the playhead
+        assert_eq!(timings, vec![(60, 0, 1.0, 1.0), (64, 1, 1.5, 1.0)]);
+        assert_eq!(live.held_pitches().collect::<Vec<_>>(), vec![64]);
+
+        // Released notes are forgotten once they scrolled away
+        live.snapshot(4.0, 1.0, &mut notes);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].pitch, 64);
+
+        // Other messages are ignored and times never run ahead of the playhead
+        live.handle_message(8_200_000, &[0xB0, 64, 127], 4.0);
+        live.handle_message(8_200_000, &[0x81, 64, 0], 4.0);
+        live.snapshot(4.0, 10.0, &mut notes);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].end_time(), 4.0);
+        assert!(live.held_pitches().next().is_none());
+
+        // After playback was reset the clocks are lined up again
+        live.handle_message(8_300_000, &[0x90, 67, 100], 0.0);
+        live.snapshot(0.5, 10.0, &mut notes);
+        assert_eq!(notes.len(), 1);
+        assert_eq!((notes[0].pitch, notes[0].start_time), (67, 0.0));
+    }
+}
@@ -1,18 +1,43 @@
 // src/main.rs
 
+use midi_rs::cli::CommandLine;
 use midi_rs::config::AppConfig;
-use midi_rs::midi::{MidiParser, MidiPlayer, Note};
-use midi_rs::performance::PerformanceMonitor;
-use midi_rs::renderer::{NoteRenderer, PerformanceOverlay, PianoRenderer, RenderPipeline};
-use midi_rs::ui::{InputAction, InputHandler, UIControls};
+use midi_rs::live::{LiveInput, LiveNotes};
+use midi_rs::midi::{
+    export_notes, format_time, Markers, MidiParser, MidiPlayer, Note, NoteColorMap, NoteEventEmitter, ParsedMidi,
+};
+use midi_rs::performance::{AdaptiveQuality, FrameLimiter, PerformanceMonitor, RenderStats};
+use midi_rs::practice::PracticeMode;
+use midi_rs::renderer::{
+    AmbientRenderer, GpuTimer, MinimapRenderer, NoteRenderer, PerformanceOverlay, PianoRenderer,
+    PlayheadRenderer, RenderPipeline, TextRenderer, TrailRenderer,
+    visualizer::{draw_scene, update_scene},
+};
+use midi_rs::session::PlaybackSession;
+use midi_rs::ui::{
+    apply_player_action, ChannelLegend, ControlWindow, DensityGauge, InputAction, InputHandler, LyricsView, MessageBanner,
+    PitchHistogram, UIControls, WelcomeScreen, WindowVisibility,
+};
 
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, MouseButton, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::WindowBuilder;
+use winit::window::{Window, WindowBuilder};
 
-const CONFIG_FILE: &str = "config.json";
+/// Playback state of the last file, kept apart from the preferences in the config file
+const SESSION_FILE: &str = "session.json";
+
+/// Length of the song synthesized by `--stress` (in seconds)
+const STRESS_TEST_DURATION: f32 = 60.0;
+
+/// File argument that reads the MIDI data from standard input
+const STDIN_PATH: &str = "-";
+
+/// How often playback moves on while the main window is minimized or covered
+const HIDDEN_FRAME_INTERVAL: Duration = Duration::from_millis(100);
 
 fn main() {
     // Initialize logging
@@ -20,24 +45,70 @@ fn main() {
 
     log::info!("Starting MIDI-RS Black MIDI Visualizer");
 
-    // Parse command line arguments
-    let args: Vec<String> = std::env::args().collect();
-    let midi_file_path = args.get(1).cloned();
+    // Parse command line arguments, see `CommandLine`; every argument that is not a flag is
+    // a MIDI file for the playlist, with `-` reading one from standard input
+    let cli = CommandLine::parse(std::env::args().skip(1));
+    if cli.list_midi_ports {
+        match LiveInput::list_ports() {
+            Ok(ports) if ports.is_empty() => println!("No MIDI input ports found"),
+            Ok(ports) => ports.iter().for_each(|port| println!("{}", port)),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        return;
+    }
+    let mut playlist = cli.playlist.clone();
+    let stress_note_count = cli.stress_note_count;
+    let config_file = cli.config_file().to_string();
 
-    if let Some(ref path) = midi_file_path {
-        log::info!("MIDI file specified: {}", path);
+    if !playlist.is_empty() {
+        for path in &playlist {
+            log::info!("MIDI file specified: {}", path.display());
+        }
     } else {
         log::info!("No MIDI file specified. Drag and drop a .mid file to load.");
     }
 
+    // Standard input can only be read once, so keep the data for reloads
+    let stdin_data = if playlist.iter().any(|path| path.as_os_str() == STDIN_PATH) {
+        let mut data = Vec::new();
+        match std::io::stdin().read_to_end(&mut data) {
+            Ok(size) => log::info!("Read {} bytes of MIDI data from standard input", size),
+            Err(e) => log::error!("Failed to read MIDI data from standard input: {}", e),
+        }
+        Some(data)
+    } else {
+        None
+    };
+
     // Print controls
     println!("\n=== MIDI-RS Controls ===");
     println!("Keyboard shortcuts:");
     println!("  Space      - Play/Pause");
-    println!("  Up/Down    - Adjust speed (0.5x - 2.0x)");
+    println!("  Up/Down    - Adjust speed (0.5x - 2.0x), hold to keep adjusting");
+    println!("  Left/Right - Seek backward/forward, hold to keep seeking");
+    println!("  Backspace  - Hold to play backward");
     println!("  P          - Toggle performance overlay");
     println!("  S          - Toggle slow mode (30 FPS)");
+    println!("  F          - Cycle FPS cap (30/60/120/144/uncapped)");
+    println!("  E          - Toggle reduced effects");
     println!("  R          - Reset to start");
+    println!("  [ / ]      - Lower/raise minimum note duration filter");
+    println!("  PgUp/PgDn  - Previous/next file in playlist");
+    println!("  M          - Toggle minimap (click it to jump)");
+    println!("  D          - Toggle note density gauge");
+    println!("  U          - Show/hide how often each key is played");
+    println!("  A          - Fit the key range to the keys the song plays");
+    println!("  J          - Practice the right hand, the left hand or both");
+    println!("  L          - Toggle octave guide lines and labels");
+    println!("  K          - Toggle karaoke lyrics");
+    println!("  T          - Toggle per-track lanes");
+    println!("  H          - Show/hide the piano keyboard");
+    println!("  , / .      - Jump to previous/next section marker");
+    println!("  1-9, 0     - Mute/unmute channels 1-10");
+    println!("  Shift+1-0  - Solo channels 1-10, muting all others");
+    println!("  G          - Show muted channels as ghost notes / hide them");
+    println!("  X          - Export unmuted notes to a .mid file");
+    println!("  F9         - Turn frustum culling off/on (debugging)");
     println!("  F11        - Toggle fullscreen");
     println!("  Q/ESC      - Quit");
     println!();
@@ -46,68 +117,264 @@ fn main() {
     println!("  [⏮] Reset");
     println!("  [-/+] Speed control");
     println!("  [🐢] Slow mode toggle");
+    println!("  [⏱] FPS cap");
+    println!("  [✨] Reduced effects toggle");
     println!("  [📊] Overlay toggle");
     println!();
-    println!("Drag & Drop - Load MIDI file");
+    println!("Drag & Drop - Load MIDI file(s) into the playlist");
     println!("========================\n");
 
     // Load or create config
-    let config = AppConfig::load_from_file(CONFIG_FILE).unwrap_or_else(|_| {
+    let file_config = AppConfig::load_from_file(&config_file).unwrap_or_else(|_| {
         log::info!("No config file found, using defaults");
         let default = AppConfig::default();
-        if let Err(e) = default.save_to_file(CONFIG_FILE) {
+        if let Err(e) = default.save_to_file(&config_file) {
             log::warn!("Failed to save default config: {}", e);
         }
         default
     });
+    // Command line settings apply to this run only and are taken back out when saving
+    let mut config = file_config.clone();
+    cli.apply(&mut config);
+
+    // Connect the live input; files are not loaded while playing live
+    let mut live = if cli.live {
+        match LiveInput::connect(cli.midi_port.as_deref()) {
+            Ok(input) => {
+                log::info!("Live input from MIDI port: {}", input.port_name());
+                playlist.clear();
+                Some((input, LiveNotes::new()))
+            }
+            Err(e) => {
+                log::error!("Failed to open live MIDI input: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Connect the keyboard to practice with; unlike live input the files still play
+    let mut practice_input = if cli.practice {
+        match LiveInput::connect(cli.midi_port.as_deref()) {
+            Ok(input) => {
+                log::info!("Practicing with MIDI port: {}", input.port_name());
+                Some((input, LiveNotes::new()))
+            }
+            Err(e) => {
+                log::error!("Failed to open MIDI input for practice mode: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut practice = PracticeMode::new();
+    // The notes played while practicing are not drawn, snapshots only let them expire
+    let mut practice_notes = Vec::new();
+
+    // Reopen the last file when nothing was given on the command line
+    if playlist.is_empty() && stress_note_count.is_none() && live.is_none() {
+        if let Some(last_file) = &config.session.last_file {
+            let path = PathBuf::from(last_file);
+            if path.exists() {
+                log::info!("Reopening last file: {}", last_file);
+                playlist.push(path);
+            } else {
+                log::warn!("Last opened file no longer exists: {}", last_file);
+            }
+        }
+    }
 
     // Create event loop
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
 
     // Create window
-    let window = Arc::new(
-        WindowBuilder::new()
-            .with_title("MIDI-RS Visualizer")
-            .with_inner_size(winit::dpi::LogicalSize::new(
-                config.display.width,
-                config.display.height,
-            ))
-            .build(&event_loop)
-            .unwrap(),
-    );
+    let mut window_builder = WindowBuilder::new()
+        .with_title("MIDI-RS Visualizer")
+        .with_inner_size(winit::dpi::LogicalSize::new(
+            config.display.width,
+            config.display.height,
+        ))
+        .with_transparent(config.display.transparent_window);
+    if let Some([x, y]) = config.session.window_position {
+        window_builder = window_builder.with_position(winit::dpi::PhysicalPosition::new(x, y));
+    }
+    let window = match window_builder.build(&event_loop) {
+        Ok(window) => Arc::new(window),
+        Err(e) => {
+            log::error!("Failed to create the window: {}", e);
+            eprintln!("Error: failed to create the window: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Initialize render pipeline
-    let mut pipeline = pollster::block_on(RenderPipeline::new(window.clone(), &config));
+    let mut pipeline = match pollster::block_on(RenderPipeline::new(window.clone(), &config)) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            log::error!("{}", e);
+            eprintln!("Error: {}", e);
+            eprintln!("Try another backend with `--backend` or `display.backend` in {}", config_file);
+            std::process::exit(1);
+        }
+    };
+
+    // Open the control window, leaving the buttons and statistics out of the main window
+    let mut control_window = None;
+    if config.display.control_window {
+        let control = WindowBuilder::new()
+            .with_title("MIDI-RS Controls")
+            .with_inner_size(winit::dpi::LogicalSize::new(640, 360))
+            .build(&event_loop)
+            .map_err(|e| e.to_string())
+            .and_then(|control| {
+                ControlWindow::new(Arc::new(control), &pipeline, &config).map_err(|e| e.to_string())
+            });
+        match control {
+            Ok(control) => control_window = Some(control),
+            Err(e) => log::warn!("Failed to open the control window: {}", e),
+        }
+    }
 
     // Initialize renderers
     let mut note_renderer = NoteRenderer::new(&config);
+    // Older GPUs can't allocate buffers for the larger presets' note budgets
+    let buffer_notes = pipeline.max_buffer_notes(config.quality.use_instancing);
+    if config.quality.max_note_count > buffer_notes {
+        log::warn!(
+            "quality.max_note_count lowered from {} to {}, the most notes this GPU's buffers hold",
+            config.quality.max_note_count,
+            buffer_notes
+        );
+        note_renderer.set_max_instances(buffer_notes);
+    }
     let mut overlay = PerformanceOverlay::new(&config);
     let mut piano_renderer = PianoRenderer::new(&config);
+    let mut minimap = MinimapRenderer::new(&config);
+    let mut playhead = PlayheadRenderer::new(&config);
+    let mut trails = TrailRenderer::new(&config);
+    let mut ambient = AmbientRenderer::new();
+    let mut gpu_timer = GpuTimer::new(&pipeline);
     let mut ui_controls = UIControls::new(&config);
+    if control_window.is_some() {
+        ui_controls.toggle_visibility();
+    }
+    let mut text_renderer = TextRenderer::new();
+    let mut banner = MessageBanner::new();
+    let mut lyrics_view = LyricsView::new(&config);
+    let mut legend = ChannelLegend::new();
+    let mut density_gauge = DensityGauge::new(&config);
+    let mut pitch_histogram = PitchHistogram::new(&config);
+    let welcome = WelcomeScreen::new();
 
     // Initialize player and input handler
     let mut player = MidiPlayer::new();
+    player.set_speed_ramp(config.controls.speed_ramp);
+    if live.is_some() {
+        player.play();
+    }
+    let mut note_events = NoteEventEmitter::new();
+    if log::log_enabled!(log::Level::Trace) {
+        note_events.add_listener(Box::new(|event| log::trace!("{:?}", event)));
+    }
     let mut input_handler = InputHandler::new();
+    input_handler.set_controls(&config.controls);
+    if cli.fullscreen {
+        window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+        input_handler.set_fullscreen(true);
+    }
     let mut monitor = PerformanceMonitor::new();
+    let mut adaptive_quality = AdaptiveQuality::new(note_renderer.max_instances());
 
     // Set initial screen size for UI controls
-    ui_controls.set_screen_size(config.display.width as f32, config.display.height as f32);
+    // Cursor positions arrive in physical pixels, so the hit-testing size must be physical too
+    let size = window.inner_size();
+    log::info!("Window size: {}x{} (scale factor {})", size.width, size.height, window.scale_factor());
+    ui_controls.set_screen_size(size.width as f32, size.height as f32);
+    minimap.set_screen_size(size.width as f32, size.height as f32);
 
     // Notes storage
     let mut notes: Vec<Note> = Vec::new();
+    let mut markers = Markers::default();
+    let mut current_file: Option<String> = None;
+    let mut playlist_index = 0usize;
 
-    // Load MIDI file if provided
-    if let Some(path) = midi_file_path {
-        load_midi_file(&path, &mut notes, &mut player);
+    // Whether playback was paused automatically because the window lost focus
+    let mut paused_by_focus_loss = false;
+
+    // Load the stress test notes or the first playlist entry if provided
+    if let Some(count) = stress_note_count {
+        log::info!("Stress test: generating {} notes", count);
+        notes = Note::generate_stress_test(count, STRESS_TEST_DURATION);
+        player.set_duration(MidiParser::get_duration(&notes));
+        apply_loaded_song(
+            &notes,
+            ParsedMidi::default(),
+            None,
+            &player,
+            &mut note_renderer,
+            &mut lyrics_view,
+            &mut minimap,
+            &mut legend,
+            &mut markers,
+            &mut density_gauge,
+            &mut pitch_histogram,
+            &mut note_events,
+            &mut practice,
+            &mut config,
+        );
+    } else if let Some(path) = playlist.first() {
+        let path = path.to_string_lossy().into_owned();
+        if let Some(parsed) = load_midi_file(&path, stdin_data.as_deref(), &mut notes, &mut player, &mut overlay, &mut banner, &config) {
+            apply_loaded_song(
+                &notes,
+                parsed,
+                Some(&path),
+                &player,
+                &mut note_renderer,
+                &mut lyrics_view,
+                &mut minimap,
+                &mut legend,
+                &mut markers,
+                &mut density_gauge,
+                &mut pitch_histogram,
+                &mut note_events,
+                &mut practice,
+                &mut config,
+            );
+            current_file = Some(path);
+        }
     }
 
     // Timing
     let mut last_frame = Instant::now();
-    let mut frame_accumulator = Duration::ZERO;
+    let mut frame_limiter = FrameLimiter::new();
+    // Whether the loop went to sleep waiting for input since the last frame
+    let mut idle = false;
+    let mut visibility = WindowVisibility::new();
 
-    // Config needs to be mutable for slow mode toggle
-    let mut config = config;
+    // Resume where the previous run left off if it was playing the same file
+    if let Some(path) = &current_file {
+        match PlaybackSession::load_from_file(SESSION_FILE) {
+            Ok(session) if session.matches(path) => {
+                session.restore(&mut player, &mut config.channels);
+                log::info!(
+                    "Resuming {} at {} ({:.1}x)",
+                    path,
+                    format_time(player.get_current_time()),
+                    player.get_playback_speed()
+                );
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("No session to resume: {}", e),
+        }
+    }
+    // A speed given on the command line wins over the resumed one
+    if let Some(speed) = cli.speed {
+        player.set_playback_speed(speed);
+    }
 
     log::info!("Application initialized, entering event loop");
 
@@ -115,31 +382,161 @@ fn main() {
     event_loop
         .run(move |event, elwt| {
             match event {
-                Event::WindowEvent { event, .. } => {
+                Event::WindowEvent { window_id, event } => {
+                    visibility.handle_event(window_id == window.id(), &event);
+
+                    // Events that only concern the control window
+                    if let Some(control) = control_window.as_mut().filter(|control| control.id() == window_id) {
+                        match event {
+                            WindowEvent::CloseRequested => {
+                                // Closing it brings the buttons back to the main window
+                                control_window = None;
+                                if !ui_controls.is_visible() {
+                                    ui_controls.toggle_visibility();
+                                }
+                                window.request_redraw();
+                                return;
+                            }
+                            WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                                control.resize();
+                                control.window().request_redraw();
+                                return;
+                            }
+                            WindowEvent::RedrawRequested => {
+                                render_control_window(control, &config);
+                                return;
+                            }
+                            // Hiding the control window doesn't hide the main one
+                            WindowEvent::Occluded(_) => return,
+                            WindowEvent::CursorMoved { position, .. } => {
+                                control.handle_mouse_move(position.x as f32, position.y as f32);
+                                control.window().request_redraw();
+                                return;
+                            }
+                            WindowEvent::MouseInput {
+                                state: ElementState::Pressed,
+                                button: MouseButton::Left,
+                                ..
+                            } => {
+                                if let Some(button_action) = control.handle_mouse_click() {
+                                    apply_player_action(button_action.player_action(), &mut player, &mut overlay, &mut config);
+                                }
+                                control.window().request_redraw();
+                                window.request_redraw();
+                                return;
+                            }
+                            // Keys, dropped files and focus changes act as in the main window
+                            _ => {}
+                        }
+                    }
+
                     // Process input
                     let action = input_handler.process_event(&event);
 
+                    // Playlist entry to switch to once this event is handled, and whether
+                    // playback goes on there because the previous song played to its end
+                    let mut next_entry: Option<usize> = None;
+                    let mut advancing = false;
+
                     match action {
                         InputAction::Quit => {
-                            // Save config before exit
-                            if let Err(e) = config.save_to_file(CONFIG_FILE) {
+                            // Remember the session for the next launch
+                            // Piped input can't be reopened and live input has no file, so keep
+                            // the previous file in those cases
+                            if live.is_none() && current_file.as_deref() != Some(STDIN_PATH) {
+                                config.session.last_file = current_file.clone();
+                                let session = PlaybackSession::capture(current_file.clone(), &player, &config.channels);
+                                if let Err(e) = session.save_to_file(SESSION_FILE) {
+                                    log::warn!("Failed to save session: {}", e);
+                                }
+                            }
+                            if !input_handler.is_fullscreen() {
+                                let size = window.inner_size().to_logical::<u32>(window.scale_factor());
+                                config.display.width = size.width;
+                                config.display.height = size.height;
+                                config.session.window_position =
+                                    window.outer_position().ok().map(|p| [p.x, p.y]);
+                            }
+
+                            // Save config before exit, without the command line overrides
+                            let mut saved = config.clone();
+                            cli.restore(&mut saved, &file_config);
+                            if let Err(e) = saved.save_to_file(&config_file) {
                                 log::warn!("Failed to save config: {}", e);
                             }
                             elwt.exit();
                         }
+                        // Minimized; the surface keeps its last size until the window is restored
+                        InputAction::Resize(width, height) if width == 0 || height == 0 => {}
                         InputAction::Resize(width, height) => {
                             pipeline.resize(winit::dpi::PhysicalSize::new(width, height));
                             ui_controls.set_screen_size(width as f32, height as f32);
+                            minimap.set_screen_size(width as f32, height as f32);
+                        }
+                        InputAction::ScaleFactorChanged(scale_factor) => {
+                            // The physical size changes with the scale factor; winit may not send a resize
+                            let size = window.inner_size();
+                            log::info!(
+                                "Scale factor changed to {}, window is {}x{}",
+                                scale_factor,
+                                size.width,
+                                size.height
+                            );
+                            pipeline.resize(size);
+                            ui_controls.set_screen_size(size.width as f32, size.height as f32);
+                            minimap.set_screen_size(size.width as f32, size.height as f32);
+                        }
+                        // Focus moving between the two windows can't be told apart from
+                        // leaving the application, so focus doesn't pause with a control window
+                        // The rewind key's release is not delivered while unfocused
+                        InputAction::FocusChanged(focused) if control_window.is_some() => {
+                            if !focused {
+                                player.set_rewinding(false);
+                            }
+                        }
+                        InputAction::FocusChanged(focused) => {
+                            if !focused {
+                                player.set_rewinding(false);
+                                if config.performance.pause_on_focus_loss && player.is_playing() {
+                                    player.pause();
+                                    paused_by_focus_loss = true;
+                                    log::debug!("Paused on focus loss");
+                                }
+                            } else if paused_by_focus_loss {
+                                paused_by_focus_loss = false;
+                                if config.performance.resume_on_focus_gain {
+                                    player.play();
+                                    log::debug!("Resumed on focus gain");
+                                }
+                            }
+                        }
+                        InputAction::FileDropped(_) if live.is_some() => {
+                            log::warn!("Files can't be loaded while playing live input");
                         }
                         InputAction::FileDropped(path) => {
                             if let Some(path_str) = path.to_str() {
-                                if path_str.ends_with(".mid") || path_str.ends_with(".midi") {
-                                    load_midi_file(path_str, &mut notes, &mut player);
+                                if [".mid", ".midi", ".kar"].iter().any(|extension| path_str.ends_with(extension)) {
+                                    // Each dropped file is queued; start playing it if nothing is loaded yet
+                                    playlist.push(path.clone());
+                                    log::info!("Added to playlist ({}): {}", playlist.len(), path_str);
+                                    if current_file.is_none() {
+                                        next_entry = Some(playlist.len() - 1);
+                                    }
                                 } else {
                                     log::warn!("Dropped file is not a MIDI file: {}", path_str);
                                 }
                             }
                         }
+                        InputAction::NextFile => {
+                            if playlist_index + 1 < playlist.len() {
+                                next_entry = Some(playlist_index + 1);
+                            }
+                        }
+                        InputAction::PreviousFile => {
+                            if playlist_index > 0 {
+                                next_entry = Some(playlist_index - 1);
+                            }
+                        }
                         InputAction::ToggleFullscreen => {
                             if input_handler.is_fullscreen() {
                                 window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
@@ -147,6 +544,45 @@ fn main() {
                                 window.set_fullscreen(None);
                             }
                         }
+                        InputAction::IncreaseMinDuration | InputAction::DecreaseMinDuration => {
+                            let previous = config.midi.min_note_duration;
+                            config
+                                .midi
+                                .step_min_note_duration(action == InputAction::IncreaseMinDuration);
+                            if config.midi.min_note_duration != previous {
+                                log::info!(
+                                    "Minimum note duration: {:.1}ms",
+                                    config.midi.min_note_duration * 1000.0
+                                );
+                                if let Some(path) = &current_file {
+                                    // Reparse in place, keeping the playback position
+                                    let time = player.get_current_time();
+                                    let was_playing = player.is_playing();
+                                    if let Some(parsed) = load_midi_file(path, stdin_data.as_deref(), &mut notes, &mut player, &mut overlay, &mut banner, &config) {
+                                        apply_loaded_song(
+                                            &notes,
+                                            parsed,
+                                            Some(path),
+                                            &player,
+                                            &mut note_renderer,
+                                            &mut lyrics_view,
+                                            &mut minimap,
+                                            &mut legend,
+                                            &mut markers,
+                                            &mut density_gauge,
+                                            &mut pitch_histogram,
+                                            &mut note_events,
+                                            &mut practice,
+                                            &mut config,
+                                        );
+                                        player.seek(time);
+                                        if was_playing {
+                                            player.play();
+                                        }
+                                    }
+                                }
+                            }
+                        }
                         InputAction::OpenFile => {
                             log::info!("File open dialog not implemented - drag and drop a MIDI file instead");
                         }
@@ -155,96 +591,381 @@ fn main() {
                         }
                         InputAction::MouseClicked(x, y) => {
                             if let Some(button_action) = ui_controls.handle_mouse_click(x as f32, y as f32) {
-                                UIControls::apply_action(button_action, &mut player, &mut overlay, &mut config);
+                                apply_player_action(button_action.player_action(), &mut player, &mut overlay, &mut config);
+                            } else if let Some(time) = minimap.handle_mouse_click(x as f32, y as f32) {
+                                player.seek(time);
+                                log::debug!("Minimap: Seek to {:.1}s", time);
                             }
                         }
-                        _ => {
-                            InputHandler::apply_action(&action, &mut player, &mut overlay, &mut config);
+                        InputAction::Seek(delta) => {
+                            player.seek(player.get_current_time() + delta);
+                        }
+                        InputAction::Rewind(rewinding) => player.set_rewinding(rewinding),
+                        InputAction::NextMarker | InputAction::PreviousMarker => {
+                            let time = player.get_current_time();
+                            let marker = if action == InputAction::NextMarker {
+                                markers.next_after(time)
+                            } else {
+                                markers.previous_before(time)
+                            };
+                            match marker {
+                                Some(marker) => {
+                                    log::info!("Jumping to marker \"{}\" at {}", marker.name, format_time(marker.time));
+                                    player.seek(marker.time);
+                                }
+                                None if markers.is_empty() => log::info!("This file has no markers"),
+                                None => {}
+                            }
+                        }
+                        InputAction::Zoom(factor) => {
+                            note_renderer.set_time_window(note_renderer.time_window() * factor);
+                            log::debug!("Time window: {:.1}s", note_renderer.time_window());
+                        }
+                        InputAction::ToggleOctaveLines => {
+                            piano_renderer.toggle_octave_lines();
+                            config.display.show_octave_lines = piano_renderer.octave_lines();
+                        }
+                        InputAction::ToggleLyrics => {
+                            lyrics_view.toggle();
+                            config.display.show_lyrics = lyrics_view.is_visible();
+                        }
+                        InputAction::ToggleTrackLanes => {
+                            config.display.track_lanes = !config.display.track_lanes;
+                        }
+                        InputAction::TogglePiano => {
+                            config.display.show_piano = !config.display.show_piano;
+                            minimap.set_bottom(config.display.piano_top());
+                        }
+                        InputAction::ToggleMinimap => {
+                            minimap.toggle_visibility();
+                            config.display.show_minimap = minimap.is_visible();
+                        }
+                        InputAction::CycleHandFocus => {
+                            config.hands.cycle_focus();
+                            log::info!("Hand focus: {:?}", config.hands.focus);
                         }
+                        InputAction::ToggleFrustumCulling => {
+                            config.quality.frustum_culling = !config.quality.frustum_culling;
+                            overlay.set_frustum_culling(config.quality.frustum_culling);
+                            log::info!(
+                                "Frustum culling {}",
+                                if config.quality.frustum_culling { "enabled" } else { "disabled" }
+                            );
+                        }
+                        InputAction::FitKeyRange => {
+                            if let Some((low, high)) = pitch_histogram.range() {
+                                config.display.fit_key_range(low, high);
+                                let [low, high] = config.display.key_range;
+                                log::info!("Key range fitted to keys {}-{}", low, high);
+                            }
+                        }
+                        InputAction::TogglePitchHistogram => {
+                            pitch_histogram.toggle_visibility();
+                            config.display.show_pitch_histogram = pitch_histogram.is_visible();
+                        }
+                        InputAction::ToggleDensityGauge => {
+                            density_gauge.toggle_visibility();
+                            config.display.show_density_gauge = density_gauge.is_visible();
+                        }
+                        InputAction::ToggleChannelMute(channel) => {
+                            config.channels.toggle_mute(channel);
+                            log::info!(
+                                "Channel {} {}",
+                                channel + 1,
+                                if config.channels.is_muted(channel) { "muted" } else { "unmuted" }
+                            );
+                        }
+                        InputAction::ToggleChannelSolo(channel) => {
+                            config.channels.toggle_solo(channel);
+                            log::info!(
+                                "Channel {} {}",
+                                channel + 1,
+                                if config.channels.is_soloed(channel) { "soloed" } else { "unsoloed" }
+                            );
+                        }
+                        InputAction::ExportNotes => {
+                            let path = export_path(current_file.as_deref());
+                            let unmuted: Vec<Note> = notes
+                                .iter()
+                                .filter(|note| !config.channels.is_muted(note.channel))
+                                .copied()
+                                .collect();
+                            match export_notes(&unmuted, &path) {
+                                Ok(()) => log::info!("Exported {} notes to {}", unmuted.len(), path.display()),
+                                Err(e) => log::error!("Failed to export notes: {}", e),
+                            }
+                        }
+                        InputAction::ToggleGhostMode => {
+                            config.channels.toggle_ghost_mode();
+                            log::info!("Muted channels: {:?}", config.channels.ghost_mode);
+                        }
+                        InputAction::Player(player_action) => {
+                            apply_player_action(player_action, &mut player, &mut overlay, &mut config);
+                        }
+                        InputAction::None => {}
                     }
 
                     // Handle RedrawRequested
                     if matches!(event, WindowEvent::RedrawRequested) {
+                        // Keep adjusting speed and position while their keys are held
+                        for action in input_handler.repeat_actions(Instant::now()) {
+                            match action {
+                                InputAction::Player(player_action) => {
+                                    apply_player_action(player_action, &mut player, &mut overlay, &mut config);
+                                }
+                                InputAction::Seek(delta) => {
+                                    player.seek(player.get_current_time() + delta);
+                                }
+                                _ => {}
+                            }
+                        }
+
                         // Calculate delta time
                         let now = Instant::now();
                         let delta = now - last_frame;
                         last_frame = now;
 
                         // FPS limiting
-                        frame_accumulator += delta;
-                        let fps = config.performance.frame_lock.unwrap_or(config.display.target_fps);
-                        let target = Duration::from_secs_f64(1.0 / fps as f64);
-
-                        if frame_accumulator >= target {
-                            frame_accumulator -= target;
+                        let frame_cap = config.performance.frame_cap(config.display.target_fps);
+                        let target = frame_cap.map(|fps| Duration::from_secs_f64(1.0 / fps as f64));
+                        let min_fps = config.performance.min_fps();
+                        let hidden = is_window_hidden(&window, &visibility);
+                        let step = if hidden {
+                            // Nothing is drawn, so playback follows the real time; the limiter
+                            // starts over once the window shows again
+                            idle = true;
+                            Some(delta)
+                        } else if idle {
+                            // Render this frame right away without counting the idle time as playback
+                            idle = false;
+                            frame_limiter.restart(target);
+                            frame_limiter.advance(Duration::ZERO, target, min_fps)
+                        } else {
+                            frame_limiter.advance(delta, target, min_fps)
+                        };
 
+                        // Fixed steps when capped, the real elapsed time when uncapped
+                        if let Some(step) = step {
                             // Update player
-                            player.update(target.as_secs_f32());
+                            player.update(step.as_secs_f32());
+                            banner.update(Instant::now());
 
-                            // Get active notes for piano visualization
-                            let current_time = player.get_current_time();
-                            let active_pitches: Vec<u8> = notes
-                                .iter()
-                                .filter(|n| n.start_time <= current_time && n.end_time() >= current_time)
-                                .map(|n| n.pitch)
-                                .collect();
+                            // Live notes start at the playhead and scroll down as they are played;
+                            // live input has no duration, so playback never finishes
+                            if let Some((input, live_notes)) = &mut live {
+                                let now = player.get_current_time();
+                                input.drain(live_notes, now);
+                                live_notes.snapshot(now, note_renderer.time_window(), &mut notes);
+                            }
+
+                            // Practice mode stops at every chord until the keys of it were played
+                            if let Some((input, played)) = &mut practice_input {
+                                let now = player.get_current_time();
+                                input.drain(played, now);
+                                let pressed = played.take_pressed();
+                                played.snapshot(now, 0.0, &mut practice_notes);
+                                practice.update(&notes, &mut player, &pressed, &config.channels);
+                                piano_renderer.set_key_feedback(practice.correct_keys(), practice.wrong_keys());
+                            }
+
+                            // Advance to the next playlist entry once the current song has ended;
+                            // reaching the end paused the player, the next song plays on
+                            if player.has_just_finished() && playlist_index + 1 < playlist.len() {
+                                next_entry = Some(playlist_index + 1);
+                                advancing = true;
+                            }
+
+                            // Emit note events and get active notes for piano visualization
+                            let active_pitches: Vec<u8> = if let Some((_, live_notes)) = &live {
+                                live_notes.held_pitches().collect()
+                            } else if notes.is_empty() {
+                                Vec::new()
+                            } else {
+                                note_events.set_speed(player.get_effective_speed());
+                                note_events.update(&notes, player.get_current_time());
+                                // Notes shorter than a frame still light their key for a frame
+                                note_events.sounded_notes(&notes).map(|n| n.pitch).collect()
+                            };
 
                             // Update UI controls states
                             ui_controls.update_states(
                                 player.is_playing(),
                                 config.performance.slow_mode,
+                                config.performance.reduced_effects,
                                 overlay.is_visible(),
                             );
 
                             // Render
                             let render_start = Instant::now();
 
-                            match render_frame(
-                                &pipeline,
-                                &mut note_renderer,
-                                &mut piano_renderer,
-                                &mut ui_controls,
-                                &notes,
-                                &active_pitches,
-                                &player,
-                                &config,
-                            ) {
-                                Ok(_) => {
-                                    // Update performance monitor
-                                    let render_duration = render_start.elapsed();
-                                    monitor.frame_rendered(render_duration);
-
-                                    // Update overlay
-                                    overlay.update(
-                                        &monitor,
-                                        note_renderer.visible_count(),
-                                        player.get_playback_speed(),
-                                        player.is_playing(),
-                                    );
-
-                                    // Update window title with overlay info
-                                    let title = overlay.get_title_text(&monitor, note_renderer.visible_count());
-                                    window.set_title(&title);
-                                }
-                                Err(wgpu::SurfaceError::Lost) => {
-                                    pipeline.resize(pipeline.size);
+                            if hidden {
+                                // No surface to draw into; the control window may still be on screen
+                                if let Some(control) = &mut control_window {
+                                    update_control_window(control, &overlay, &player, &config);
                                 }
-                                Err(wgpu::SurfaceError::OutOfMemory) => {
-                                    log::error!("Out of GPU memory");
-                                    elwt.exit();
+                            } else {
+                                match render_frame(
+                                    &pipeline,
+                                    &mut note_renderer,
+                                    &mut piano_renderer,
+                                    &mut minimap,
+                                    &mut playhead,
+                                    &mut trails,
+                                    &mut ambient,
+                                    &mut gpu_timer,
+                                    &mut ui_controls,
+                                    &mut text_renderer,
+                                    &banner,
+                                    &lyrics_view,
+                                    overlay.is_visible().then_some(&legend),
+                                    &density_gauge,
+                                    &pitch_histogram,
+                                    &welcome,
+                                    &notes,
+                                    &active_pitches,
+                                    live.is_some(),
+                                    &player,
+                                    &config,
+                                ) {
+                                    Ok(_) => {
+                                        // Update performance monitor
+                                        let render_duration = render_start.elapsed();
+                                        let sampled = monitor.frame_rendered(render_duration);
+                                        if let Some(gpu_time) = gpu_timer.poll(&pipeline.device) {
+                                            monitor.gpu_frame_measured(gpu_time);
+                                        }
+                                        if sampled && log::log_enabled!(log::Level::Debug) {
+                                            let stats = RenderStats::capture(&monitor, &note_renderer, &player, &notes);
+                                            if let Ok(json) = serde_json::to_string(&stats) {
+                                                log::debug!("Render stats: {}", json);
+                                            }
+                                        }
+                                        if sampled && config.quality.adaptive {
+                                            let target_fps = frame_cap.unwrap_or(config.display.target_fps);
+                                            if let Some(limit) = adaptive_quality.update(monitor.get_fps(), target_fps as f32) {
+                                                note_renderer.set_max_instances(limit);
+                                            }
+                                        }
+
+                                        // Update overlay
+                                        overlay.set_buffer_memory(note_renderer.buffer_bytes(), note_renderer.buffer_allocations());
+                                        overlay.update(
+                                            &monitor,
+                                            note_renderer.visible_count(),
+                                            &player,
+                                            frame_cap,
+                                            note_renderer.is_instanced(),
+                                            Instant::now(),
+                                        );
+
+                                        // Update window title with overlay info, throttled to avoid flicker
+                                        // while playing; paused frames may be the last for a while, so
+                                        // they always bring the title up to date
+                                        let note_count = note_renderer.visible_count();
+                                        let title = if player.is_playing() {
+                                            overlay.title_update(&monitor, note_count, Instant::now())
+                                        } else {
+                                            overlay.flush_title(&monitor, note_count, Instant::now())
+                                        };
+                                        if let Some(title) = title {
+                                            window.set_title(&title);
+                                        }
+
+                                        if let Some(control) = &mut control_window {
+                                            update_control_window(control, &overlay, &player, &config);
+                                        }
+                                    }
+                                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                                        pipeline.resize(pipeline.size);
+                                    }
+                                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                                        log::error!("Out of GPU memory");
+                                        elwt.exit();
+                                    }
+                                    // Happens while the window is being hidden; the next frame retries
+                                    Err(wgpu::SurfaceError::Timeout) => {
+                                        log::debug!("Timed out acquiring a frame, skipping it");
+                                    }
                                 }
-                                Err(e) => {
-                                    log::error!("Render error: {:?}", e);
+                            }
+                        }
+                    }
+
+                    // Switch playlist entries, continuing playback if it was running. Entries
+                    // that fail to load are skipped in the same direction; the current song
+                    // stays when none loads.
+                    if let Some(mut index) = next_entry {
+                        let was_playing = player.is_playing() || advancing;
+                        let backwards = index < playlist_index;
+                        loop {
+                            let path = playlist[index].to_string_lossy().into_owned();
+                            let Some(parsed) = load_midi_file(&path, stdin_data.as_deref(), &mut notes, &mut player, &mut overlay, &mut banner, &config) else {
+                                match playlist_step(index, playlist.len(), backwards) {
+                                    Some(next) => index = next,
+                                    None => break,
                                 }
+                                continue;
+                            };
+                            playlist_index = index;
+                            apply_loaded_song(
+                                &notes,
+                                parsed,
+                                Some(&path),
+                                &player,
+                                &mut note_renderer,
+                                &mut lyrics_view,
+                                &mut minimap,
+                                &mut legend,
+                                &mut markers,
+                                &mut density_gauge,
+                                &mut pitch_histogram,
+                                &mut note_events,
+                                &mut practice,
+                                &mut config,
+                            );
+                            current_file = Some(path);
+                            if was_playing {
+                                player.play();
                             }
+                            break;
                         }
                     }
 
-                    // Request redraw for continuous rendering
-                    window.request_redraw();
+                    // Redraw to show the effect of any other event
+                    if !matches!(event, WindowEvent::RedrawRequested) {
+                        window.request_redraw();
+                    }
                 }
                 Event::AboutToWait => {
-                    window.request_redraw();
+                    // Render continuously while playing; a paused or empty scene doesn't animate
+                    // (note flashes follow playback time), so sleep until the next event instead
+                    // Held keys keep repeating and live input can start a note at any time, so
+                    // they need frames too, as does rewinding and practice mode waiting for keys
+                    // to be played. The idle animation wakes up at its own low frame rate.
+                    // A minimized or covered window isn't drawn, so it only wakes up now and
+                    // then to keep playback moving.
+                    let busy = (player.is_playing() && (!notes.is_empty() || live.is_some()))
+                        || player.is_rewinding()
+                        || input_handler.has_held_keys()
+                        || (practice_input.is_some() && practice.is_waiting());
+                    let hidden = is_window_hidden(&window, &visibility);
+                    if busy && !hidden {
+                        elwt.set_control_flow(ControlFlow::Poll);
+                        window.request_redraw();
+                    } else {
+                        let wake = if hidden {
+                            busy.then(|| last_frame + HIDDEN_FRAME_INTERVAL)
+                        } else {
+                            [banner.deadline(), ambient.next_frame(last_frame)].into_iter().flatten().min()
+                        };
+                        match wake {
+                            // Wake up to dismiss the error banner or animate the idle background
+                            Some(deadline) if Instant::now() >= deadline => window.request_redraw(),
+                            Some(deadline) => elwt.set_control_flow(ControlFlow::WaitUntil(deadline)),
+                            None => elwt.set_control_flow(ControlFlow::Wait),
+                        }
+                        idle = true;
+                    }
                 }
                 _ => {}
             }
@@ -254,81 +975,262 @@ fn main() {
     log::info!("MIDI-RS shut down cleanly");
 }
 
-/// Load a MIDI file
-fn load_midi_file(path: &str, notes: &mut Vec<Note>, player: &mut MidiPlayer) {
+/// Point the views of the song at newly loaded `notes`, with the rest of their parse result
+/// and the path they were loaded from (None for generated notes)
+#[allow(clippy::too_many_arguments)]
+fn apply_loaded_song(
+    notes: &[Note],
+    parsed: ParsedMidi,
+    path: Option<&str>,
+    player: &MidiPlayer,
+    note_renderer: &mut NoteRenderer,
+    lyrics_view: &mut LyricsView,
+    minimap: &mut MinimapRenderer,
+    legend: &mut ChannelLegend,
+    markers: &mut Markers,
+    density_gauge: &mut DensityGauge,
+    pitch_histogram: &mut PitchHistogram,
+    note_events: &mut NoteEventEmitter,
+    practice: &mut PracticeMode,
+    config: &mut AppConfig,
+) {
+    note_renderer.set_expression(parsed.expression);
+    note_renderer.set_color_map(path.map(NoteColorMap::load_for).unwrap_or_default());
+    lyrics_view.set_lyrics(parsed.lyrics);
+    minimap.set_markers(&parsed.markers);
+    minimap.set_tempo_map(&parsed.tempo_map);
+    legend.set_song(notes, &parsed.tracks);
+    *markers = parsed.markers;
+    minimap.set_notes(notes, player.get_duration());
+    density_gauge.set_notes(notes);
+    pitch_histogram.set_notes(notes);
+    if let Some((low, high)) = pitch_histogram.range().filter(|_| config.display.auto_fit_key_range) {
+        config.display.fit_key_range(low, high);
+    }
+    note_renderer.allocate_voices(notes, config);
+    note_renderer.assign_lanes(notes);
+    note_events.reset();
+    practice.reset();
+}
+
+/// Load a MIDI file into `notes`, returning the rest of the parse result (controller
+/// timelines, lyrics) if it was loaded successfully
+fn load_midi_file(
+    path: &str,
+    stdin_data: Option<&[u8]>,
+    notes: &mut Vec<Note>,
+    player: &mut MidiPlayer,
+    overlay: &mut PerformanceOverlay,
+    banner: &mut MessageBanner,
+    config: &AppConfig,
+) -> Option<ParsedMidi> {
     log::info!("Loading MIDI file: {}", path);
 
-    let parser = MidiParser::new();
-    match parser.parse_file(path) {
-        Ok(parsed_notes) => {
-            log::info!("Loaded {} notes", parsed_notes.len());
-            *notes = parsed_notes;
+    let parser = MidiParser::new()
+        .with_min_duration(config.midi.min_note_duration)
+        .with_max_duration(config.midi.max_song_duration)
+        .with_unclosed_duration(config.midi.unclosed_note_duration)
+        .with_ticks_per_beat(config.midi.forced_ticks_per_beat)
+        .with_tempo(config.midi.forced_tempo);
+    let result = match (path, stdin_data) {
+        (STDIN_PATH, Some(data)) => parser.parse_bytes_detailed(data),
+        _ => parser.parse_file_detailed(path),
+    };
+    match result {
+        Ok(mut parsed) => {
+            log::info!("Loaded {}", parsed.stats);
+            *notes = std::mem::take(&mut parsed.notes);
+            overlay.set_parse_stats(Some(parsed.stats.clone()));
             player.reset();
+            player.set_duration(parsed.stats.duration());
+            banner.clear_error();
+            Some(parsed)
         }
         Err(e) => {
             log::error!("Failed to load MIDI file: {}", e);
+            // The statistics stay with the song still loaded, if any
+            let name = match path {
+                STDIN_PATH => "standard input".to_string(),
+                _ => Path::new(path)
+                    .file_name()
+                    .map_or_else(|| path.to_string(), |name| name.to_string_lossy().into_owned()),
+            };
+            banner.show_error(format!("Failed to load {}: {}", name, e), Instant::now());
+            None
         }
     }
 }
 
+/// Get the playlist entry after `index`, or before it going `backwards`, None at either end
+fn playlist_step(index: usize, len: usize, backwards: bool) -> Option<usize> {
+    if backwards {
+        index.checked_sub(1)
+    } else {
+        Some(index + 1).filter(|&next| next < len)
+    }
+}
+
+/// Get the path notes are exported to: next to the loaded file, or the working directory
+fn export_path(current_file: Option<&str>) -> PathBuf {
+    match current_file {
+        Some(path) if path != STDIN_PATH => {
+            let path = PathBuf::from(path);
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            path.with_file_name(format!("{}.export.mid", stem))
+        }
+        _ => PathBuf::from("export.mid"),
+    }
+}
+
+/// Check whether the main window can't be seen, so frames would be drawn for nobody
+fn is_window_hidden(window: &Window, visibility: &WindowVisibility) -> bool {
+    visibility.is_hidden(window.inner_size(), window.is_minimized().unwrap_or(false))
+}
+
+/// Pass the latest states and statistics to the control window, which redraws when they changed
+fn update_control_window(control: &mut ControlWindow, overlay: &PerformanceOverlay, player: &MidiPlayer, config: &AppConfig) {
+    control.update_states(
+        player.is_playing(),
+        config.performance.slow_mode,
+        config.performance.reduced_effects,
+        overlay.is_visible(),
+    );
+    control.set_lines(overlay.get_lines(), Instant::now());
+}
+
+/// Render the control window
+fn render_control_window(control: &mut ControlWindow, config: &AppConfig) {
+    match control.render(config) {
+        Ok(()) => {}
+        Err(wgpu::SurfaceError::Lost) => control.resize(),
+        Err(e) => log::error!("Control window render error: {:?}", e),
+    }
+}
+
 /// Render a frame
+#[allow(clippy::too_many_arguments)]
 fn render_frame(
     pipeline: &RenderPipeline,
     note_renderer: &mut NoteRenderer,
     piano_renderer: &mut PianoRenderer,
+    minimap: &mut MinimapRenderer,
+    playhead: &mut PlayheadRenderer,
+    trails: &mut TrailRenderer,
+    ambient: &mut AmbientRenderer,
+    gpu_timer: &mut GpuTimer,
     ui_controls: &mut UIControls,
+    text_renderer: &mut TextRenderer,
+    banner: &MessageBanner,
+    lyrics_view: &LyricsView,
+    legend: Option<&ChannelLegend>,
+    density_gauge: &DensityGauge,
+    pitch_histogram: &PitchHistogram,
+    welcome: &WelcomeScreen,
     notes: &[Note],
     active_pitches: &[u8],
+    live: bool,
     player: &MidiPlayer,
     config: &AppConfig,
 ) -> Result<(), wgpu::SurfaceError> {
-    // Update uniforms
-    pipeline.update_uniforms(0.15, player.get_current_time()); // Playhead at 15% from bottom
+    // With no file loaded there is no scene to draw, only the welcome screen; live input
+    // shows the piano while waiting for the first note
+    let empty = notes.is_empty() && !live;
+
+    // Update uniforms, animating the background with its own clock while the scene is idle
+    ambient.update(pipeline, AmbientRenderer::is_idle(!notes.is_empty(), player), config);
+    let time = if ambient.is_active() { ambient.time() } else { player.get_current_time() };
+    pipeline.update_uniforms(config.display.playhead_position(), time);
 
     // Update renderers
-    note_renderer.update(pipeline, notes, player.get_current_time(), config);
-    piano_renderer.update(pipeline, active_pitches);
+    if !empty {
+        update_scene(
+            pipeline,
+            note_renderer,
+            piano_renderer,
+            playhead,
+            notes,
+            active_pitches,
+            player.get_current_time(),
+            config,
+        );
+        minimap.update(pipeline, player.get_current_time());
+        trails.update(pipeline, player.get_current_time(), config);
+    }
     ui_controls.update(pipeline);
+    text_renderer.begin(pipeline.size.width as f32, pipeline.size.height as f32);
+    if empty {
+        welcome.draw(text_renderer);
+    } else {
+        piano_renderer.draw_octave_labels(text_renderer, config);
+        pitch_histogram.draw(text_renderer, config, config.display.piano_top());
+        lyrics_view.draw(text_renderer, player.get_current_time(), config.display.playhead_position());
+        minimap.draw_tempo_labels(text_renderer);
+        density_gauge.draw(text_renderer, notes, player.get_current_time(), config.display.piano_top());
+        // The channel legend comes and goes with the performance overlay
+        if let Some(legend) = legend {
+            legend.draw(text_renderer, config);
+        }
+    }
+    banner.draw(text_renderer);
+    text_renderer.update(pipeline);
 
     // Begin render
     let (output, mut encoder) = pipeline.begin_render(config.display.background_color)?;
-    let view = output
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    // The GPU time spans from the start of the first pass to the end of the last one
+    gpu_timer.begin_frame();
+
+    // Trails draw the notes into their own texture first
+    let trails_active = !empty && trails.is_active();
+    if trails_active {
+        trails.render_notes(&mut encoder, pipeline, note_renderer, gpu_timer.pass_writes(true, false));
+    }
 
     {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Main Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
+                view: &output.view,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: config.display.background_color[0] as f64,
-                        g: config.display.background_color[1] as f64,
-                        b: config.display.background_color[2] as f64,
-                        a: config.display.background_color[3] as f64,
-                    }),
+                    load: wgpu::LoadOp::Clear(pipeline.clear_color(config.display.background_color)),
                     store: wgpu::StoreOp::Store,
                 }
             })],
             depth_stencil_attachment: None,
-            timestamp_writes: None,
+            timestamp_writes: gpu_timer.pass_writes(!trails_active, true),
             occlusion_query_set: None,
         });
 
-        // Render piano first (at the bottom)
-        piano_renderer.render(&mut render_pass, pipeline);
-        
-        // Render notes (falling from top to bottom)
-        note_renderer.render(&mut render_pass, pipeline);
-        
+        // Render the idle animation right over the background
+        ambient.render(&mut render_pass, pipeline);
+
+        if !empty {
+            // Render the piano, the notes falling onto it and the playhead marking where they hit
+            let (note_renderer, trails) = (&*note_renderer, &*trails);
+            draw_scene(&mut render_pass, pipeline, piano_renderer, playhead, |render_pass| {
+                if trails_active {
+                    trails.render(render_pass);
+                } else {
+                    note_renderer.render(render_pass, pipeline);
+                }
+            });
+
+            // Render the song overview along the right edge
+            minimap.render(&mut render_pass, pipeline);
+        }
+
         // Render UI controls (on top)
         ui_controls.render(&mut render_pass, pipeline);
+
+        // Render error messages and prompts over everything
+        text_renderer.render(&mut render_pass, pipeline);
     }
 
     // Submit and present
+    gpu_timer.resolve(&mut encoder);
     pipeline.queue.submit(std::iter::once(encoder.finish()));
+    gpu_timer.end_frame();
     output.present();
 
     Ok(())
@@ -0,0 +1,197 @@
+// src/midi/color_map.rs
+
+use crate::midi::Note;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Ending of the color map file next to a MIDI file: `song.mid` uses `song.colors.json`
+pub const COLOR_MAP_SUFFIX: &str = ".colors.json";
+
+/// One entry of a color map file as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColorEntry {
+    track: Option<u16>,
+    channel: Option<u8>,
+    pitches: Option<[u8; 2]>,
+    color: String,
+}
+
+/// Gives the notes matching every criterion that is set a custom color
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRule {
+    /// Track index, counted from 0 like `Note::track_index`
+    pub track: Option<u16>,
+    /// MIDI channel, 0 to 15
+    pub channel: Option<u8>,
+    /// Lowest and highest key, both included
+    pub pitches: Option<(u8, u8)>,
+    /// Color as [r, g, b, a] (0.0 to 1.0)
+    pub color: [f32; 4],
+}
+
+impl ColorRule {
+    pub fn matches(&self, note: &Note) -> bool {
+        self.track.is_none_or(|track| note.track_index == track)
+            && self.channel.is_none_or(|channel| note.channel == channel)
+            && self.pitches.is_none_or(|(low, high)| (low..=high).contains(&note.pitch))
+    }
+
+    /// Check an entry of the file, describing what is wrong with it
+    fn from_entry(entry: ColorEntry) -> Result<Self, String> {
+        let color = parse_hex_color(&entry.color)
+            .ok_or_else(|| format!("\"{}\" is not a color like \"#FFD700\" or \"#FFD70080\"", entry.color))?;
+        if entry.channel.is_some_and(|channel| channel > 15) {
+            return Err("channel must be 0 to 15".to_string());
+        }
+        if let Some([low, high]) = entry.pitches {
+            if low > high || high > 127 {
+                return Err(format!("pitches [{}, {}] are not a key range within 0 to 127", low, high));
+            }
+        }
+        if entry.track.is_none() && entry.channel.is_none() && entry.pitches.is_none() {
+            return Err("no track, channel or pitches to match".to_string());
+        }
+        Ok(ColorRule {
+            track: entry.track,
+            channel: entry.channel,
+            pitches: entry.pitches.map(|[low, high]| (low, high)),
+            color,
+        })
+    }
+}
+
+/// Custom note colors replacing the channel colors of some notes, read from a JSON file
+///
+/// The file holds a list of rules; when several match a note, the last one wins:
+///
+/// ```json
+/// [
+///     { "track": 1, "color": "#3070FF" },
+///     { "track": 1, "pitches": [60, 67], "color": "#FFD700" }
+/// ]
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NoteColorMap {
+    rules: Vec<ColorRule>,
+}
+
+impl NoteColorMap {
+    pub fn new(rules: Vec<ColorRule>) -> Self {
+        NoteColorMap { rules }
+    }
+
+    /// Get the color map file belonging to the MIDI file at `midi_path`
+    pub fn sidecar_path(midi_path: &Path) -> PathBuf {
+        let stem = midi_path.file_stem().unwrap_or_default().to_string_lossy();
+        midi_path.with_file_name(format!("{}{}", stem, COLOR_MAP_SUFFIX))
+    }
+
+    /// Load the color map next to the MIDI file at `midi_path`, empty when there is none
+    ///
+    /// A file that can't be read or parsed is logged and ignored, as are its invalid entries.
+    pub fn load_for(midi_path: &str) -> Self {
+        let path = Self::sidecar_path(Path::new(midi_path));
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                log::warn!("Failed to read color map {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+        match Self::from_json(&json) {
+            Ok(map) => {
+                log::info!("Loaded {} note color rules from {}", map.rules.len(), path.display());
+                map
+            }
+            Err(e) => {
+                log::warn!("Failed to parse color map {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse a color map, skipping and logging the entries that are invalid
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+        let rules = entries
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, value)| {
+                let rule = serde_json::from_value::<ColorEntry>(value)
+                    .map_err(|e| e.to_string())
+                    .and_then(ColorRule::from_entry);
+                match rule {
+                    Ok(rule) => Some(rule),
+                    Err(e) => {
+                        log::warn!("Ignoring color map entry {}: {}", index + 1, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Ok(NoteColorMap { rules })
+    }
+
+    pub fn rules(&self) -> &[ColorRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Get the custom color of a note, None to keep its channel color
+    pub fn color_of(&self, note: &Note) -> Option<[f32; 4]> {
+        self.rules.iter().rev().find(|rule| rule.matches(note)).map(|rule| rule.color)
+    }
+}
+
+/// Parse a `#RRGGBB` or `#RRGGBBAA` hex color
+fn parse_hex_color(text: &str) -> Option<[f32; 4]> {
+    let hex = text.strip_prefix('#')?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return None;
+    }
+    let mut color = [1.0; 4];
+    for (i, channel) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()? as f32 / 255.0;
+    }
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_rules() {
+        let json = r##"[
+            { "track": 1, "color": "#0000FF" },
+            { "track": 1, "pitches": [60, 67], "color": "#FFD70080" },
+            { "channel": 16, "color": "#FFFFFF" },
+            { "pitches": [70, 60], "color": "#FFFFFF" },
+            { "color": "#FFFFFF" },
+            { "channel": 2, "color": "gold" },
+            { "channel": 2, "colour": "#FFFFFF" }
+        ]"##;
+        let map = NoteColorMap::from_json(json).unwrap();
+        // Only the first two entries are valid
+        assert_eq!(map.rules().len(), 2);
+
+        let mut note = Note::new(64, 100, 0.0, 1.0, 0);
+        assert_eq!(map.color_of(&note), None);
+        note.track_index = 1;
+        // The later, more specific rule wins
+        assert_eq!(map.color_of(&note), Some([1.0, 215.0 / 255.0, 0.0, 128.0 / 255.0]));
+        note.pitch = 72;
+        assert_eq!(map.color_of(&note), Some([0.0, 0.0, 1.0, 1.0]));
+
+        assert!(NoteColorMap::from_json("{}").is_err());
+        assert_eq!(
+            NoteColorMap::sidecar_path(Path::new("songs/piece.mid")),
+            PathBuf::from("songs/piece.colors.json")
+        );
+    }
+}
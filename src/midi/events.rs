@@ -0,0 +1,397 @@
+// src/midi/events.rs
+
+use crate::midi::Note;
+use std::sync::mpsc;
+
+/// Largest playback step treated as continuous, in seconds of real time; bigger jumps are
+/// handled as a seek. At other playback speeds the step in song time scales with the speed.
+const MAX_CONTINUOUS_STEP: f32 = 1.0;
+
+/// Whether a note started or stopped sounding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteEventKind {
+    On,
+    Off,
+}
+
+/// A note starting or stopping as playback crosses its boundaries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteEvent {
+    pub pitch: u8,
+    pub velocity: u8,
+    pub channel: u8,
+    pub kind: NoteEventKind,
+    /// Song time of the event in seconds
+    pub time: f32,
+}
+
+impl NoteEvent {
+    fn new(note: &Note, kind: NoteEventKind, time: f32) -> Self {
+        NoteEvent {
+            pitch: note.pitch,
+            velocity: note.velocity,
+            channel: note.channel,
+            kind,
+            time,
+        }
+    }
+}
+
+/// Callback invoked for every note event
+pub type NoteEventListener = Box<dyn FnMut(&NoteEvent) + Send>;
+
+/// Emits note on/off events as playback moves through a song
+///
+/// Call [`NoteEventEmitter::update`] once per frame with the notes (sorted by start time)
+/// and the playback position. Seeking emits note-offs for everything that was sounding
+/// and note-ons for the notes sounding at the new position. Small steps backward, as when
+/// rewinding, play the song in reverse: notes turn off as playback moves back past their
+/// start and on again as it reaches their end. Call
+/// [`NoteEventEmitter::reset`] whenever a different set of notes is loaded, and
+/// [`NoteEventEmitter::set_speed`] when playback runs faster than real time.
+#[derive(Default)]
+pub struct NoteEventEmitter {
+    /// Playback position of the previous update
+    last_time: Option<f32>,
+    /// Playback speed, for telling steps from seeks; 0 until set, which counts as 1
+    speed: f32,
+    /// Duration of the longest note, taken from the notes at the first update after a reset
+    max_duration: Option<f32>,
+    /// Indices of the notes currently sounding
+    active: Vec<usize>,
+    /// Indices of the notes that stopped sounding during the latest update's step
+    ended: Vec<usize>,
+    /// Events of the latest update
+    events: Vec<NoteEvent>,
+    listeners: Vec<NoteEventListener>,
+}
+
+impl NoteEventEmitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a callback that receives every event
+    pub fn add_listener(&mut self, listener: NoteEventListener) {
+        self.listeners.push(listener);
+    }
+
+    /// Get a channel receiving every event, e.g. for another thread driving external hardware
+    pub fn subscribe(&mut self) -> mpsc::Receiver<NoteEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.add_listener(Box::new(move |event| {
+            let _ = sender.send(*event);
+        }));
+        receiver
+    }
+
+    /// Set the speed playback currently runs at, e.g. `MidiPlayer::get_effective_speed`
+    ///
+    /// Faster playback takes larger steps every frame, which are still not seeks.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Get the largest step in song time that is not a seek
+    fn max_step(&self) -> f32 {
+        MAX_CONTINUOUS_STEP * self.speed.max(1.0)
+    }
+
+    /// Forget the playback state, e.g. after loading a different file
+    pub fn reset(&mut self) {
+        self.last_time = None;
+        self.max_duration = None;
+        self.active.clear();
+        self.ended.clear();
+        self.events.clear();
+    }
+
+    /// Get the index of the first note that can still sound at `time`; no note starting
+    /// earlier lasts long enough
+    fn first_reaching(&self, notes: &[Note], time: f32) -> usize {
+        let max_duration = self.max_duration.unwrap_or(f32::INFINITY);
+        notes.partition_point(|note| note.start_time < time - max_duration)
+    }
+
+    /// Advance to `current_time`, notify listeners and return the events in time order
+    pub fn update(&mut self, notes: &[Note], current_time: f32) -> &[NoteEvent] {
+        self.events.clear();
+        self.ended.clear();
+
+        if self.active.iter().any(|&index| index >= notes.len()) {
+            // The notes changed without a reset
+            self.active.clear();
+            self.last_time = None;
+            self.max_duration = None;
+        }
+        if self.max_duration.is_none() {
+            self.max_duration = Some(notes.iter().map(|note| note.duration).fold(0.0, f32::max));
+        }
+
+        let mut backward = false;
+        let max_step = self.max_step();
+        match self.last_time {
+            Some(last) if current_time >= last && current_time - last <= max_step => {
+                self.advance(notes, last, current_time);
+            }
+            Some(last) if current_time < last && last - current_time <= max_step => {
+                self.retreat(notes, last, current_time);
+                backward = true;
+            }
+            _ => self.jump(notes, current_time),
+        }
+        self.last_time = Some(current_time);
+
+        // In the order playback passes them, with note-offs first when events coincide so a
+        // repeated key turns off before turning on again
+        self.events.sort_by(|a, b| {
+            let order = if backward { b.time.total_cmp(&a.time) } else { a.time.total_cmp(&b.time) };
+            order.then_with(|| (a.kind == NoteEventKind::On).cmp(&(b.kind == NoteEventKind::On)))
+        });
+        for listener in &mut self.listeners {
+            for event in &self.events {
+                listener(event);
+            }
+        }
+        &self.events
+    }
+
+    /// Get the notes sounding at the last update
+    pub fn active_notes<'a>(&'a self, notes: &'a [Note]) -> impl Iterator<Item = &'a Note> {
+        self.active.iter().filter_map(|&index| notes.get(index))
+    }
+
+    /// Get the notes that sounded at any point between the previous update and the last one
+    ///
+    /// Unlike [`NoteEventEmitter::active_notes`] this includes notes shorter than a frame,
+    /// which start and end between two updates. Seeking skips over notes, so after a seek
+    /// these are the active notes.
+    pub fn sounded_notes<'a>(&'a self, notes: &'a [Note]) -> impl Iterator<Item = &'a Note> {
+        self.active.iter().chain(&self.ended).filter_map(|&index| notes.get(index))
+    }
+
+    /// Emit events for playback moving forward from `last` to `now`
+    fn advance(&mut self, notes: &[Note], last: f32, now: f32) {
+        let events = &mut self.events;
+        let ended = &mut self.ended;
+        self.active.retain(|&index| {
+            let note = &notes[index];
+            if note.end_time() <= now {
+                events.push(NoteEvent::new(note, NoteEventKind::Off, note.end_time()));
+                ended.push(index);
+                false
+            } else {
+                true
+            }
+        });
+
+        let first = notes.partition_point(|note| note.start_time <= last);
+        let end = notes.partition_point(|note| note.start_time <= now);
+        for (index, note) in notes.iter().enumerate().take(end).skip(first) {
+            events.push(NoteEvent::new(note, NoteEventKind::On, note.start_time));
+            if note.end_time() <= now {
+                // Started and ended within this step
+                events.push(NoteEvent::new(note, NoteEventKind::Off, note.end_time()));
+                self.ended.push(index);
+            } else {
+                self.active.push(index);
+            }
+        }
+    }
+
+    /// Emit events for playback moving backward from `last` to `now`
+    fn retreat(&mut self, notes: &[Note], last: f32, now: f32) {
+        let start = self.first_reaching(notes, now);
+        let events = &mut self.events;
+        let ended = &mut self.ended;
+        self.active.retain(|&index| {
+            let note = &notes[index];
+            if note.start_time > now {
+                events.push(NoteEvent::new(note, NoteEventKind::Off, note.start_time));
+                ended.push(index);
+                false
+            } else {
+                true
+            }
+        });
+
+        // Notes that ended during the step sound again: the ones started by `now` stay on
+        let first = notes.partition_point(|note| note.start_time <= now);
+        let end = notes.partition_point(|note| note.start_time <= last);
+        for (index, note) in notes.iter().enumerate().take(end).skip(start) {
+            if note.end_time() <= now || note.end_time() > last {
+                continue;
+            }
+            events.push(NoteEvent::new(note, NoteEventKind::On, note.end_time()));
+            if index < first {
+                self.active.push(index);
+            } else {
+                // Ended and started again within this step
+                events.push(NoteEvent::new(note, NoteEventKind::Off, note.start_time));
+                ended.push(index);
+            }
+        }
+    }
+
+    /// Resynchronize after a seek: stop everything, then start what sounds at `now`
+    fn jump(&mut self, notes: &[Note], now: f32) {
+        for index in self.active.drain(..) {
+            self.events.push(NoteEvent::new(&notes[index], NoteEventKind::Off, now));
+        }
+
+        let end = notes.partition_point(|note| note.start_time <= now);
+        let start = self.first_reaching(notes, now);
+        for (index, note) in notes.iter().enumerate().take(end).skip(start) {
+            if note.end_time() > now {
+                self.events.push(NoteEvent::new(note, NoteEventKind::On, now));
+                self.active.push(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(events: &[NoteEvent]) -> Vec<(u8, NoteEventKind)> {
+        events.iter().map(|event| (event.pitch, event.kind)).collect()
+    }
+
+    #[test]
+    fn test_events_follow_playback() {
+        let notes = vec![
+            Note::new(60, 100, 0.0, 0.5, 0),
+            Note::new(62, 100, 0.2, 0.05, 0),
+            Note::new(64, 100, 0.4, 1.0, 1),
+        ];
+        let mut emitter = NoteEventEmitter::new();
+
+        assert_eq!(kinds(emitter.update(&notes, 0.0)), vec![(60, NoteEventKind::On)]);
+        // The short note starts and ends within one step
+        assert_eq!(
+            kinds(emitter.update(&notes, 0.3)),
+            vec![(62, NoteEventKind::On), (62, NoteEventKind::Off)]
+        );
+        assert_eq!(
+            kinds(emitter.update(&notes, 0.6)),
+            vec![(64, NoteEventKind::On), (60, NoteEventKind::Off)]
+        );
+        assert_eq!(emitter.active_notes(&notes).map(|n| n.pitch).collect::<Vec<_>>(), vec![64]);
+        assert!(emitter.update(&notes, 0.6).is_empty());
+    }
+
+    #[test]
+    fn test_seek_resynchronizes() {
+        let notes = vec![Note::new(60, 100, 0.0, 0.5, 0), Note::new(64, 100, 5.0, 1.0, 0)];
+        let mut emitter = NoteEventEmitter::new();
+        let receiver = emitter.subscribe();
+
+        emitter.update(&notes, 0.1);
+        emitter.update(&notes, 5.5);
+        let received: Vec<NoteEvent> = receiver.try_iter().collect();
+        assert_eq!(
+            kinds(&received),
+            vec![(60, NoteEventKind::On), (60, NoteEventKind::Off), (64, NoteEventKind::On)]
+        );
+    }
+
+    #[test]
+    fn test_fast_playback_steps_are_not_seeks() {
+        let notes = vec![Note::new(60, 100, 0.0, 0.5, 0), Note::new(64, 100, 1.0, 0.5, 0)];
+        let mut emitter = NoteEventEmitter::new();
+        // 4x speed at 3 FPS moves 1.33 seconds per frame, playing the notes on the way
+        emitter.set_speed(4.0);
+        emitter.update(&notes, 0.1);
+        assert_eq!(
+            kinds(emitter.update(&notes, 0.1 + 4.0 / 3.0)),
+            vec![(60, NoteEventKind::Off), (64, NoteEventKind::On)]
+        );
+        // Jumps beyond a second of real time are still seeks, skipping the notes in between
+        assert_eq!(kinds(emitter.update(&notes, 20.0)), vec![(64, NoteEventKind::Off)]);
+        emitter.update(&notes, 0.1);
+        emitter.set_speed(1.0);
+        assert_eq!(kinds(emitter.update(&notes, 1.6)), vec![(60, NoteEventKind::Off)]);
+    }
+
+    #[test]
+    fn test_notes_between_updates_sounded() {
+        let notes = vec![
+            Note::new(60, 100, 0.0, 0.02, 0),
+            Note::new(62, 100, 0.02, 0.005, 0),
+            Note::new(64, 100, 0.03, 1.0, 0),
+        ];
+        let mut emitter = NoteEventEmitter::new();
+        let pitches = |emitter: &NoteEventEmitter| {
+            let mut pitches: Vec<u8> = emitter.sounded_notes(&notes).map(|n| n.pitch).collect();
+            pitches.sort_unstable();
+            pitches
+        };
+
+        emitter.update(&notes, 0.0);
+        assert_eq!(pitches(&emitter), vec![60]);
+        // Two 60 FPS frames later: the first note ended and the second started and ended within the step
+        emitter.update(&notes, 1.0 / 60.0 * 2.0);
+        assert_eq!(emitter.active_notes(&notes).map(|n| n.pitch).collect::<Vec<_>>(), vec![64]);
+        assert_eq!(pitches(&emitter), vec![60, 62, 64]);
+        // Only for the frame they were played in
+        emitter.update(&notes, 0.05);
+        assert_eq!(pitches(&emitter), vec![64]);
+    }
+
+    #[test]
+    fn test_rewinding_plays_backward() {
+        let notes = vec![
+            Note::new(60, 100, 0.0, 0.5, 0),
+            Note::new(64, 100, 0.2, 1.0, 1),
+            Note::new(62, 100, 0.6, 0.05, 0),
+        ];
+        let mut emitter = NoteEventEmitter::new();
+        emitter.update(&notes, 1.0);
+        let receiver = emitter.subscribe();
+        let active = |emitter: &NoteEventEmitter| emitter.active_notes(&notes).map(|n| n.pitch).collect::<Vec<_>>();
+
+        // Moving back past the short note plays it in reverse, the long one keeps sounding
+        assert_eq!(
+            kinds(emitter.update(&notes, 0.55)),
+            vec![(62, NoteEventKind::On), (62, NoteEventKind::Off)]
+        );
+        assert_eq!(active(&emitter), vec![64]);
+        let pitches: Vec<u8> = emitter.sounded_notes(&notes).map(|n| n.pitch).collect();
+        assert_eq!(pitches, vec![64, 62]);
+
+        // Back past the start of one note and the end of another
+        emitter.update(&notes, 0.1);
+        assert_eq!(active(&emitter), vec![60]);
+        let received: Vec<NoteEvent> = receiver.try_iter().collect();
+        assert_eq!(
+            kinds(&received[2..]),
+            vec![(60, NoteEventKind::On), (64, NoteEventKind::Off)]
+        );
+        // In the order playback passes them, without retriggering held notes
+        assert_eq!(received[2].time, 0.5);
+        assert!(emitter.update(&notes, 0.05).is_empty());
+        assert_eq!(active(&emitter), vec![60]);
+    }
+
+    #[test]
+    fn test_rewinding_reaches_long_notes() {
+        // A long note early on among many short ones; rewinding only looks back as far as
+        // the longest note lasts, which still covers it
+        let mut notes = vec![Note::new(40, 100, 0.0, 9.5, 0)];
+        notes.extend((0..90).map(|i| Note::new(60 + (i % 12) as u8, 100, i as f32 * 0.1, 0.05, 1)));
+        notes.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        let mut emitter = NoteEventEmitter::new();
+        emitter.update(&notes, 9.6);
+        assert!(emitter.active_notes(&notes).next().is_none());
+
+        // Back into the end of the long note
+        assert_eq!(kinds(emitter.update(&notes, 9.42)), vec![(40, NoteEventKind::On)]);
+        // And on into the last short note, which started last at 8.9
+        assert_eq!(kinds(emitter.update(&notes, 8.92)), vec![(65, NoteEventKind::On)]);
+
+        // A seek back also finds it
+        emitter.update(&notes, 20.0);
+        assert!(kinds(emitter.update(&notes, 5.0)).contains(&(40, NoteEventKind::On)));
+    }
+}
@@ -0,0 +1,156 @@
+// src/midi/export.rs
+
+use crate::midi::note::Note;
+use midly::num::{u15, u24, u28, u4, u7};
+use midly::{Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind};
+use std::io;
+use std::path::Path;
+
+/// Resolution of exported files in ticks per beat
+const EXPORT_TICKS_PER_BEAT: u16 = 960;
+/// Tempo of exported files: 120 BPM in microseconds per beat
+const EXPORT_TEMPO: u32 = 500_000;
+/// Longest delta a MIDI file can hold (28 bits), over 38 hours at the export resolution
+const EXPORT_MAX_DELTA: u64 = (1 << 28) - 1;
+
+/// Write notes to a standard MIDI file at `path`
+pub fn export_notes<P: AsRef<Path>>(notes: &[Note], path: P) -> io::Result<()> {
+    std::fs::write(path, export_bytes(notes))
+}
+
+/// Serialize notes to standard MIDI file data
+///
+/// The file uses a fixed tempo of 120 BPM with one track per channel, so note times
+/// round-trip to within a tick (about 0.5ms). Overlapping notes of the same key and
+/// channel are shortened to end where the next one starts, since MIDI can't represent
+/// them and they would otherwise be merged on import. Controller data such as
+/// pitch bend is not exported.
+pub fn export_bytes(notes: &[Note]) -> Vec<u8> {
+    let ticks_per_second = EXPORT_TICKS_PER_BEAT as f64 * 1_000_000.0 / EXPORT_TEMPO as f64;
+    let to_ticks = |seconds: f32| (seconds.max(0.0) as f64 * ticks_per_second).round() as u64;
+
+    // Tempo track
+    let mut tracks = vec![vec![
+        TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::Tempo(u24::new(EXPORT_TEMPO))),
+        },
+        TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        },
+    ]];
+
+    for channel in 0..16u8 {
+        // (start tick, end tick, pitch, velocity), ordered by key then start
+        let mut spans: Vec<(u64, u64, u8, u8)> = notes
+            .iter()
+            .filter(|note| note.channel == channel)
+            .map(|note| {
+                let start = to_ticks(note.start_time);
+                let end = to_ticks(note.end_time()).max(start + 1);
+                (start, end, note.pitch.min(127), note.velocity.clamp(1, 127))
+            })
+            .collect();
+        if spans.is_empty() {
+            continue;
+        }
+        spans.sort_by_key(|&(start, _, pitch, _)| (pitch, start));
+
+        // Cut each note off where the next note on the same key begins
+        for i in 1..spans.len() {
+            let (next_start, _, next_pitch, _) = spans[i];
+            let previous = &mut spans[i - 1];
+            if previous.2 == next_pitch && previous.1 > next_start {
+                previous.1 = next_start;
+            }
+        }
+
+        // (tick, is_note_on, pitch, velocity); note-offs sort before note-ons at the same tick
+        let mut events: Vec<(u64, bool, u8, u8)> = spans
+            .iter()
+            .filter(|&&(start, end, _, _)| end > start)
+            .flat_map(|&(start, end, pitch, velocity)| [(start, true, pitch, velocity), (end, false, pitch, 0)])
+            .collect();
+        events.sort_unstable();
+
+        let mut track = Vec::with_capacity(events.len() + 1);
+        let mut last_tick = 0u64;
+        for (tick, is_note_on, pitch, velocity) in events {
+            let key = u7::new(pitch);
+            let message = if is_note_on {
+                MidiMessage::NoteOn { key, vel: u7::new(velocity) }
+            } else {
+                MidiMessage::NoteOff { key, vel: u7::new(0) }
+            };
+            track.push(TrackEvent {
+                delta: u28::new((tick - last_tick).min(EXPORT_MAX_DELTA) as u32),
+                kind: TrackEventKind::Midi { channel: u4::new(channel), message },
+            });
+            last_tick = tick;
+        }
+        track.push(TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
+        tracks.push(track);
+    }
+
+    let mut smf = Smf::new(Header::new(
+        Format::Parallel,
+        Timing::Metrical(u15::new(EXPORT_TICKS_PER_BEAT)),
+    ));
+    smf.tracks = tracks;
+
+    let mut data = Vec::new();
+    smf.write_std(&mut data).expect("writing to a Vec cannot fail");
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::MidiParser;
+
+    #[test]
+    fn test_export_round_trip() {
+        let notes = vec![
+            Note::new(60, 100, 0.0, 0.5, 0),
+            Note::new(64, 80, 0.25, 1.0, 0),
+            Note::new(36, 127, 1.333, 0.1, 9),
+        ];
+
+        let data = export_bytes(&notes);
+        let parsed = MidiParser::new().parse_bytes(&data).unwrap();
+        assert_eq!(parsed.len(), notes.len());
+        for (original, parsed) in notes.iter().zip(&parsed) {
+            assert_eq!(
+                (parsed.pitch, parsed.velocity, parsed.channel),
+                (original.pitch, original.velocity, original.channel)
+            );
+            assert!((parsed.start_time - original.start_time).abs() < 1e-3);
+            assert!((parsed.duration - original.duration).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_export_long_silence() {
+        // A channel that stays silent for an hour keeps its timing
+        let notes = vec![Note::new(60, 100, 0.0, 0.5, 3), Note::new(62, 100, 3600.0, 0.5, 3)];
+
+        let (parsed, stats) = MidiParser::new().parse_bytes_with_stats(&export_bytes(&notes)).unwrap();
+        assert_eq!(stats.clamped_deltas, 0);
+        assert!((parsed[1].start_time - 3600.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_export_overlapping_notes() {
+        // The second note starts before the first one ends on the same key
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(60, 100, 0.5, 1.0, 0)];
+
+        let parsed = MidiParser::new().parse_bytes(&export_bytes(&notes)).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert!((parsed[0].duration - 0.5).abs() < 1e-3);
+        assert!((parsed[1].start_time - 0.5).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,204 @@
+// src/midi/expression.rs
+
+use std::collections::HashMap;
+
+/// Step-wise controller values over time for each of the 16 MIDI channels
+#[derive(Debug, Clone, Default)]
+pub struct ChannelTimeline {
+    /// (time in seconds, value) pairs per channel, sorted by time
+    events: [Vec<(f32, f32)>; 16],
+}
+
+impl ChannelTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a value change; call [`ChannelTimeline::sort`] after adding out-of-order events
+    pub fn push(&mut self, channel: u8, time: f32, value: f32) {
+        self.events[channel as usize & 0x0F].push((time, value));
+    }
+
+    /// Sort every channel's events by time, keeping the file order of simultaneous events
+    pub fn sort(&mut self) {
+        for events in &mut self.events {
+            events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+    }
+
+    /// Check if the timeline has no events on any channel
+    pub fn is_empty(&self) -> bool {
+        self.events.iter().all(Vec::is_empty)
+    }
+
+    /// Get the number of events on a channel
+    pub fn event_count(&self, channel: u8) -> usize {
+        self.events[channel as usize & 0x0F].len()
+    }
+
+    /// Get the value in effect on `channel` at `time`, or `default` before the first event
+    pub fn value_at(&self, channel: u8, time: f32, default: f32) -> f32 {
+        self.get(channel, time).unwrap_or(default)
+    }
+
+    /// Get the value in effect on `channel` at `time`, if any event precedes it
+    pub fn get(&self, channel: u8, time: f32) -> Option<f32> {
+        value_at(&self.events[channel as usize & 0x0F], time)
+    }
+
+    /// Check if the value on `channel` changes after `start` and before `end`
+    pub fn changes_between(&self, channel: u8, start: f32, end: f32) -> bool {
+        let events = &self.events[channel as usize & 0x0F];
+        let first = events.partition_point(|&(t, _)| t <= start);
+        events.get(first).is_some_and(|&(t, _)| t < end)
+    }
+}
+
+/// Step-wise controller values over time for individual keys, such as polyphonic aftertouch
+#[derive(Debug, Clone, Default)]
+pub struct KeyTimeline {
+    /// (time in seconds, value) pairs per (channel, key), sorted by time
+    events: HashMap<(u8, u8), Vec<(f32, f32)>>,
+}
+
+impl KeyTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a value change; call [`KeyTimeline::sort`] after adding out-of-order events
+    pub fn push(&mut self, channel: u8, key: u8, time: f32, value: f32) {
+        self.events.entry((channel, key)).or_default().push((time, value));
+    }
+
+    /// Sort every key's events by time, keeping the file order of simultaneous events
+    pub fn sort(&mut self) {
+        for events in self.events.values_mut() {
+            events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Get the value in effect for `key` on `channel` at `time`, if any event precedes it
+    pub fn value_at(&self, channel: u8, key: u8, time: f32) -> Option<f32> {
+        self.events.get(&(channel, key)).and_then(|events| value_at(events, time))
+    }
+}
+
+/// Get the value of the last event at or before `time` in a sorted event list
+fn value_at(events: &[(f32, f32)], time: f32) -> Option<f32> {
+    match events.partition_point(|&(t, _)| t <= time) {
+        0 => None,
+        index => Some(events[index - 1].1),
+    }
+}
+
+/// Expressive controller data captured alongside the notes of a file
+#[derive(Debug, Clone, Default)]
+pub struct Expression {
+    /// Pitch bend per channel, from -1.0 (full down) to 1.0 (full up)
+    pub pitch_bends: ChannelTimeline,
+    /// Channel aftertouch (pressure) per channel, from 0.0 to 1.0
+    pub channel_pressure: ChannelTimeline,
+    /// Polyphonic aftertouch per key, from 0.0 to 1.0
+    pub key_pressure: KeyTimeline,
+    /// Channel volume (CC7) per channel, from 0.0 to 1.0
+    pub volume: ChannelTimeline,
+    /// Expression (CC11) per channel, from 0.0 to 1.0, a fraction of the channel volume
+    pub expression: ChannelTimeline,
+}
+
+impl Expression {
+    /// Sort all timelines after parsing
+    pub fn sort(&mut self) {
+        self.pitch_bends.sort();
+        self.channel_pressure.sort();
+        self.key_pressure.sort();
+        self.volume.sort();
+        self.expression.sort();
+    }
+
+    /// Check if any aftertouch was recorded
+    pub fn has_pressure(&self) -> bool {
+        !self.channel_pressure.is_empty() || !self.key_pressure.is_empty()
+    }
+
+    /// Get the aftertouch applied to `key` on `channel` at `time`
+    /// Polyphonic aftertouch takes precedence over channel aftertouch
+    pub fn pressure_at(&self, channel: u8, key: u8, time: f32) -> Option<f32> {
+        self.key_pressure
+            .value_at(channel, key, time)
+            .or_else(|| self.channel_pressure.get(channel, time))
+    }
+
+    /// Check if any channel volume or expression was recorded
+    pub fn has_volume(&self) -> bool {
+        !self.volume.is_empty() || !self.expression.is_empty()
+    }
+
+    /// Get the loudness of `channel` at `time` from its volume and expression, 1.0 before
+    /// either is set
+    pub fn volume_at(&self, channel: u8, time: f32) -> f32 {
+        self.volume.value_at(channel, time, 1.0) * self.expression.value_at(channel, time, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_timeline() {
+        let mut timeline = ChannelTimeline::new();
+        timeline.push(0, 2.0, -0.5);
+        timeline.push(0, 1.0, 0.5);
+        timeline.push(3, 0.0, 1.0);
+        timeline.sort();
+
+        assert_eq!(timeline.value_at(0, 0.5, 0.0), 0.0);
+        assert_eq!(timeline.value_at(0, 1.0, 0.0), 0.5);
+        assert_eq!(timeline.value_at(0, 5.0, 0.0), -0.5);
+        assert_eq!(timeline.value_at(3, 5.0, 0.0), 1.0);
+        assert_eq!(timeline.value_at(1, 5.0, 0.25), 0.25);
+
+        assert!(timeline.changes_between(0, 0.0, 1.5));
+        assert!(!timeline.changes_between(0, 1.0, 2.0));
+        assert!(!timeline.changes_between(3, 0.0, 10.0));
+    }
+
+    #[test]
+    fn test_pressure_precedence() {
+        let mut expression = Expression::default();
+        assert!(!expression.has_pressure());
+        expression.channel_pressure.push(0, 1.0, 0.25);
+        expression.key_pressure.push(0, 60, 2.0, 0.75);
+        expression.sort();
+
+        assert!(expression.has_pressure());
+        assert_eq!(expression.pressure_at(0, 60, 0.5), None);
+        assert_eq!(expression.pressure_at(0, 60, 1.5), Some(0.25));
+        assert_eq!(expression.pressure_at(0, 60, 2.5), Some(0.75));
+        assert_eq!(expression.pressure_at(0, 62, 2.5), Some(0.25));
+        assert_eq!(expression.pressure_at(1, 60, 2.5), None);
+    }
+
+    #[test]
+    fn test_volume_and_expression() {
+        let mut expression = Expression::default();
+        assert!(!expression.has_volume());
+        assert_eq!(expression.volume_at(0, 1.0), 1.0);
+        expression.volume.push(0, 1.0, 0.5);
+        expression.expression.push(0, 2.0, 0.5);
+        expression.sort();
+
+        assert!(expression.has_volume());
+        assert_eq!(expression.volume_at(0, 0.5), 1.0);
+        assert_eq!(expression.volume_at(0, 1.5), 0.5);
+        // Expression scales the channel volume
+        assert_eq!(expression.volume_at(0, 2.5), 0.25);
+        assert_eq!(expression.volume_at(1, 2.5), 1.0);
+    }
+}
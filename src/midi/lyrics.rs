@@ -0,0 +1,214 @@
+// src/midi/lyrics.rs
+
+/// How long before a line starts it is shown as the upcoming line (in seconds)
+const LINE_LEAD: f32 = 2.0;
+/// How long a line stays after its last syllable when the next one is far away (in seconds)
+const LINE_HOLD: f32 = 4.0;
+
+/// A piece of a lyric line and when it is sung
+#[derive(Debug, Clone, PartialEq)]
+pub struct Syllable {
+    /// Time in seconds
+    pub time: f32,
+    pub text: String,
+}
+
+/// A line of lyrics made of timed syllables
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LyricLine {
+    pub syllables: Vec<Syllable>,
+}
+
+impl LyricLine {
+    /// Get the time the first syllable is sung
+    pub fn start_time(&self) -> f32 {
+        self.syllables.first().map_or(0.0, |syllable| syllable.time)
+    }
+
+    /// Get the time the last syllable is sung
+    pub fn end_time(&self) -> f32 {
+        self.syllables.last().map_or(0.0, |syllable| syllable.time)
+    }
+
+    /// Get the whole line as text
+    pub fn text(&self) -> String {
+        self.syllables.iter().map(|syllable| syllable.text.as_str()).collect()
+    }
+
+    /// Get how many characters of the line have been sung by `time`
+    pub fn sung_chars(&self, time: f32) -> usize {
+        self.syllables
+            .iter()
+            .take_while(|syllable| syllable.time <= time)
+            .map(|syllable| syllable.text.chars().count())
+            .sum()
+    }
+}
+
+/// Synced lyrics from lyric meta events, or from text events in karaoke (.kar) files
+#[derive(Debug, Clone, Default)]
+pub struct Lyrics {
+    /// Lines in the order they are sung
+    lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    pub fn lines(&self) -> &[LyricLine] {
+        &self.lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Get the line to show at `time`: the line being sung, or the next one shortly before it starts
+    pub fn line_at(&self, time: f32) -> Option<&LyricLine> {
+        let index = self.lines.partition_point(|line| line.start_time() <= time);
+        let upcoming = self
+            .lines
+            .get(index)
+            .filter(|line| line.start_time() - time <= LINE_LEAD);
+        if index == 0 {
+            return upcoming;
+        }
+
+        let current = &self.lines[index - 1];
+        if time - current.end_time() > LINE_HOLD {
+            upcoming
+        } else {
+            Some(current)
+        }
+    }
+
+    /// Split timed text events into lines
+    ///
+    /// A syllable starting with `/` or `\` begins a new line (the karaoke convention) and a
+    /// syllable ending with a carriage return or newline ends one (the lyric event convention).
+    fn from_syllables(events: impl IntoIterator<Item = (f32, String)>) -> Self {
+        let mut lines = Vec::new();
+        let mut line = LyricLine::default();
+
+        for (time, text) in events {
+            let mut text = text.as_str();
+            if let Some(rest) = text.strip_prefix(['/', '\\']) {
+                if !line.syllables.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                text = rest;
+            }
+            let ends_line = text.ends_with(['\r', '\n']);
+            let text = text.trim_end_matches(['\r', '\n']);
+
+            if !text.is_empty() {
+                line.syllables.push(Syllable {
+                    time,
+                    text: text.to_string(),
+                });
+            }
+            if ends_line && !line.syllables.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+        }
+        if !line.syllables.is_empty() {
+            lines.push(line);
+        }
+
+        Lyrics { lines }
+    }
+}
+
+/// Lyric and text meta events collected while parsing, turned into [`Lyrics`] once all tracks are read
+#[derive(Debug, Default)]
+pub(crate) struct LyricEvents {
+    lyrics: Vec<(f32, String)>,
+    texts: Vec<(f32, String)>,
+}
+
+impl LyricEvents {
+    pub(crate) fn push_lyric(&mut self, time: f32, data: &[u8]) {
+        self.lyrics.push((time, decode(data)));
+    }
+
+    pub(crate) fn push_text(&mut self, time: f32, data: &[u8]) {
+        self.texts.push((time, decode(data)));
+    }
+
+    /// Build the lyrics, preferring lyric events and falling back to karaoke text events
+    pub(crate) fn into_lyrics(self) -> Lyrics {
+        let mut events = if self.lyrics.is_empty() {
+            // Karaoke files put their header in text events starting with '@'
+            self.texts
+                .into_iter()
+                .filter(|(_, text)| !text.starts_with('@'))
+                .collect()
+        } else {
+            self.lyrics
+        };
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Lyrics::from_syllables(events)
+    }
+}
+
+/// Decode meta event text as UTF-8, falling back to Latin-1 which older karaoke files use
+pub(crate) fn decode(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(text) => text.to_string(),
+        Err(_) => data.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lyrics: &Lyrics) -> Vec<String> {
+        lyrics.lines().iter().map(LyricLine::text).collect()
+    }
+
+    #[test]
+    fn test_karaoke_text_events() {
+        let mut events = LyricEvents::default();
+        events.push_text(0.0, b"@TSong title");
+        events.push_text(1.0, b"\\Hel");
+        events.push_text(1.5, b"lo ");
+        events.push_text(2.0, b"world");
+        events.push_text(3.0, b"/Se");
+        events.push_text(3.5, b"cond");
+
+        let lyrics = events.into_lyrics();
+        assert_eq!(texts(&lyrics), vec!["Hello world", "Second"]);
+        assert_eq!(lyrics.lines()[0].sung_chars(1.7), 6);
+        assert_eq!(lyrics.lines()[1].start_time(), 3.0);
+    }
+
+    #[test]
+    fn test_lyric_events_take_precedence() {
+        let mut events = LyricEvents::default();
+        events.push_text(0.0, b"Copyright notice");
+        events.push_lyric(2.0, b"line\r");
+        events.push_lyric(1.0, b"First ");
+        events.push_lyric(3.0, b"two");
+        events.push_lyric(3.2, &[0xE9]); // Latin-1 e acute
+
+        assert_eq!(texts(&events.into_lyrics()), vec!["First line", "two\u{e9}"]);
+    }
+
+    #[test]
+    fn test_line_at() {
+        let mut events = LyricEvents::default();
+        events.push_lyric(5.0, b"one\r");
+        events.push_lyric(6.0, b"two\r");
+        events.push_lyric(20.0, b"three\r");
+        let lyrics = events.into_lyrics();
+        let line_at = |time| lyrics.line_at(time).map(LyricLine::text);
+
+        assert_eq!(line_at(1.0), None);
+        // The first line shows up shortly before it is sung
+        assert_eq!(line_at(4.0).as_deref(), Some("one"));
+        assert_eq!(line_at(5.5).as_deref(), Some("one"));
+        assert_eq!(line_at(7.0).as_deref(), Some("two"));
+        // A long instrumental gap clears the screen until the next line approaches
+        assert_eq!(line_at(15.0), None);
+        assert_eq!(line_at(18.5).as_deref(), Some("three"));
+    }
+}
@@ -0,0 +1,78 @@
+// src/midi/markers.rs
+
+/// Markers within this many seconds before the playback position are skipped when
+/// stepping back, so pressing "previous" repeatedly keeps moving backwards
+const PREVIOUS_MARKER_GRACE: f32 = 1.0;
+/// Markers this close after the playback position count as already reached
+const NEXT_MARKER_EPSILON: f32 = 0.01;
+
+/// A named point in the song, such as "Verse" or "Chorus"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    /// Time in seconds
+    pub time: f32,
+    pub name: String,
+}
+
+/// Marker meta events of a file, sorted by time
+#[derive(Debug, Clone, Default)]
+pub struct Markers {
+    markers: Vec<Marker>,
+}
+
+impl Markers {
+    /// Create a marker list, sorting the markers by time
+    pub fn new(mut markers: Vec<Marker>) -> Self {
+        markers.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Markers { markers }
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+
+    /// Get the first marker after `time`
+    pub fn next_after(&self, time: f32) -> Option<&Marker> {
+        let index = self.markers.partition_point(|marker| marker.time <= time + NEXT_MARKER_EPSILON);
+        self.markers.get(index)
+    }
+
+    /// Get the marker to step back to from `time`
+    pub fn previous_before(&self, time: f32) -> Option<&Marker> {
+        let index = self
+            .markers
+            .partition_point(|marker| marker.time < time - PREVIOUS_MARKER_GRACE);
+        index.checked_sub(1).map(|index| &self.markers[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(time: f32, name: &str) -> Marker {
+        Marker {
+            time,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_marker_navigation() {
+        let markers = Markers::new(vec![marker(30.0, "Chorus"), marker(0.0, "Intro"), marker(10.0, "Verse")]);
+        let name = |marker: Option<&Marker>| marker.map(|m| m.name.clone());
+
+        assert_eq!(name(markers.next_after(0.0)).as_deref(), Some("Verse"));
+        assert_eq!(name(markers.next_after(10.0)).as_deref(), Some("Chorus"));
+        assert_eq!(markers.next_after(30.0), None);
+
+        assert_eq!(name(markers.previous_before(20.0)).as_deref(), Some("Verse"));
+        // Right after a marker, stepping back goes to the one before it
+        assert_eq!(name(markers.previous_before(10.5)).as_deref(), Some("Intro"));
+        assert_eq!(markers.previous_before(0.5), None);
+    }
+}
@@ -1,9 +1,27 @@
 // src/midi/mod.rs
 
+pub mod color_map;
+pub mod events;
+pub mod export;
+pub mod expression;
+pub mod lyrics;
+pub mod markers;
 pub mod parser;
 pub mod player;
 pub mod note;
+pub mod tempo;
+pub mod note_set;
+pub mod voices;
 
-pub use parser::MidiParser;
-pub use player::MidiPlayer;
-pub use note::Note;
\ No newline at end of file
+pub use color_map::{ColorRule, NoteColorMap};
+pub use events::{NoteEvent, NoteEventEmitter, NoteEventKind};
+pub use export::{export_bytes, export_notes};
+pub use expression::{ChannelTimeline, Expression, KeyTimeline};
+pub use lyrics::{LyricLine, Lyrics, Syllable};
+pub use markers::{Marker, Markers};
+pub use parser::{MidiParser, ParseError, ParseStats, ParsedMidi, TrackInfo};
+pub use player::{format_time, MidiPlayer};
+pub use note::Note;
+pub use tempo::{TempoChange, TempoMap};
+pub use note_set::NoteSet;
+pub use voices::steal_voices;
\ No newline at end of file
@@ -1,8 +1,16 @@
 // src/midi/note.rs
 
+use crate::config::HsvParams;
 use bytemuck::{Pod, Zeroable};
 
+/// Part of the time window of `Note::is_visible` that lies before the current time
+pub const VISIBLE_PAST_FRACTION: f32 = 0.15;
+
 /// Represents a MIDI note with timing and channel information
+///
+/// Times are f32 to keep Black MIDI files with millions of notes small. That still resolves
+/// a millisecond up to 8192 seconds (over two hours) into a song; the parser computes them
+/// in f64 and the player's clock runs in f64, so no error builds up on the way.
 #[derive(Debug, Clone, Copy)]
 pub struct Note {
     pub pitch: u8,
@@ -10,6 +18,8 @@ pub struct Note {
     pub start_time: f32,
     pub duration: f32,
     pub channel: u8,
+    /// Index of the track the note was read from
+    pub track_index: u16,
 }
 
 impl Note {
@@ -21,6 +31,7 @@ impl Note {
             start_time,
             duration,
             channel,
+            track_index: 0,
         }
     }
 
@@ -33,22 +44,45 @@ impl Note {
     /// For vertical rendering: notes are visible from above the playhead down to below it
     pub fn is_visible(&self, current_time: f32, time_window: f32) -> bool {
         // Notes visible from 15% below current time to 85% above current time
-        let window_start = current_time - time_window * 0.15;
-        let window_end = current_time + time_window * 0.85;
+        let window_start = current_time - time_window * VISIBLE_PAST_FRACTION;
+        let window_end = current_time + time_window * (1.0 - VISIBLE_PAST_FRACTION);
         self.start_time <= window_end && self.end_time() >= window_start
     }
 
     /// Get color as [r, g, b, a] based on the channel using HSV to RGB conversion
     pub fn get_color(&self) -> [f32; 4] {
+        self.get_color_with_intensity(self.velocity as f32 / 127.0)
+    }
+
+    /// Get color as [r, g, b, a] with brightness driven by `intensity` (0.0 to 1.0)
+    /// instead of the velocity, e.g. to follow aftertouch
+    pub fn get_color_with_intensity(&self, intensity: f32) -> [f32; 4] {
+        self.get_color_with_params(intensity, &HsvParams::default())
+    }
+
+    /// Get color as [r, g, b, a] with brightness driven by `intensity` (0.0 to 1.0),
+    /// using the saturation and brightness from `params`
+    pub fn get_color_with_params(&self, intensity: f32, params: &HsvParams) -> [f32; 4] {
         // Use channel to create distinct colors (16 channels -> 16 different hues)
         let hue = (self.channel as f32 / 16.0) * 360.0;
-        let saturation = 0.8;
-        let value = 0.5 + (self.velocity as f32 / 127.0) * 0.5; // Velocity affects brightness
-        
+        let saturation = params.saturation.clamp(0.0, 1.0);
+        // Intensity affects brightness
+        let value = params.value(intensity);
+
         let (r, g, b) = hsv_to_rgb(hue, saturation, value);
         [r, g, b, 1.0]
     }
 
+    /// Get the strength (0.0 to 1.0) of the flash shown when the note reaches the playhead
+    /// The flash starts at full strength on note-on and fades out over `flash_duration` seconds
+    pub fn flash_intensity(&self, current_time: f32, flash_duration: f32) -> f32 {
+        let elapsed = current_time - self.start_time;
+        if flash_duration <= 0.0 || elapsed < 0.0 || elapsed >= flash_duration {
+            return 0.0;
+        }
+        1.0 - elapsed / flash_duration
+    }
+
     /// Get x position for screen rendering (0.0 to 1.0 normalized based on pitch)
     /// Maps the pitch to a horizontal position like a piano keyboard
     pub fn get_x_position_from_pitch(&self) -> f32 {
@@ -72,6 +106,38 @@ impl Note {
         (self.duration / time_window) * 0.85
     }
 
+    /// Generate a deterministic set of notes for stress testing and benchmarks
+    ///
+    /// Notes are spread over `duration` seconds in dense chord-like bursts across
+    /// the whole keyboard and are returned sorted by start time.
+    pub fn generate_stress_test(count: usize, duration: f32) -> Vec<Note> {
+        // xorshift32 keeps the output reproducible without a random number dependency
+        let mut state: u32 = 0x9E37_79B9;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let mut notes: Vec<Note> = (0..count)
+            .map(|i| {
+                // Group notes into bursts so the density varies like real Black MIDI
+                let burst = (i / 64) as f32;
+                let bursts = count.div_ceil(64).max(1) as f32;
+                let start_time = (burst / bursts) * duration + (next() % 1000) as f32 / 1000.0 * 0.05;
+                let note_length = 0.02 + (next() % 500) as f32 / 1000.0;
+                let pitch = (next() % 128) as u8;
+                let velocity = 1 + (next() % 127) as u8;
+                let channel = (next() % 16) as u8;
+                Note::new(pitch, velocity, start_time, note_length.min(duration - start_time).max(0.001), channel)
+            })
+            .collect();
+
+        notes.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+        notes
+    }
+
     /// Get the width of the note based on pitch (for piano-style rendering)
     pub fn get_width_from_pitch(&self) -> f32 {
         // Fixed width for each key - 88 keys on a piano (A0 to C8, MIDI 21-108)
@@ -81,9 +147,12 @@ impl Note {
 }
 
 /// Convert HSV color to RGB
+/// Hues outside 0-360 degrees wrap around, so 360 is red like 0
 fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
     let c = v * s;
-    let h_prime = h / 60.0;
+    // rem_euclid can round up to exactly 360 for tiny negative hues, wrap that to 0 as well
+    let h = h.rem_euclid(360.0);
+    let h_prime = if h < 360.0 { h / 60.0 } else { 0.0 };
     let x = c * (1.0 - ((h_prime % 2.0) - 1.0).abs());
     let m = v - c;
     
@@ -174,4 +243,68 @@ mod tests {
         // Note (5.0-6.0) is NOT within (18.5-28.5)
         assert!(!note.is_visible(20.0, 10.0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generate_stress_test() {
+        let notes = Note::generate_stress_test(10_000, 30.0);
+        assert_eq!(notes.len(), 10_000);
+        assert!(notes.windows(2).all(|w| w[0].start_time <= w[1].start_time));
+        assert!(notes.iter().all(|n| n.start_time >= 0.0 && n.end_time() <= 30.0 + 0.001));
+        assert!(notes.iter().all(|n| n.pitch < 128 && n.channel < 16 && n.velocity > 0));
+
+        // Generation is deterministic
+        let again = Note::generate_stress_test(10_000, 30.0);
+        assert!(notes.iter().zip(&again).all(|(a, b)| a.start_time == b.start_time && a.pitch == b.pitch));
+    }
+
+    #[test]
+    fn test_flash_intensity() {
+        let note = Note::new(60, 100, 2.0, 1.0, 0);
+        assert_eq!(note.flash_intensity(1.9, 0.2), 0.0);
+        assert_eq!(note.flash_intensity(2.0, 0.2), 1.0);
+        assert!((note.flash_intensity(2.1, 0.2) - 0.5).abs() < 1e-5);
+        assert_eq!(note.flash_intensity(2.2, 0.2), 0.0);
+        assert_eq!(note.flash_intensity(2.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_color_params() {
+        let note = Note::new(60, 127, 0.0, 1.0, 0);
+        // The defaults keep the original palette
+        assert_eq!(note.get_color_with_params(1.0, &HsvParams::default()), note.get_color());
+
+        // Channel 0 is red: value is the brightest component, value * (1 - saturation) the others
+        let pastel = HsvParams { saturation: 0.3, base_value: 0.6, velocity_weight: 0.2 };
+        let [r, g, b, _] = note.get_color_with_params(0.5, &pastel);
+        assert!((r - 0.7).abs() < 1e-5);
+        assert!((g - 0.49).abs() < 1e-5 && (b - 0.49).abs() < 1e-5);
+
+        // Brightness is capped
+        let loud = HsvParams { saturation: 0.0, base_value: 0.8, velocity_weight: 1.0 };
+        assert_eq!(note.get_color_with_params(1.0, &loud), [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_hsv_sector_boundaries() {
+        let close = |(r, g, b): (f32, f32, f32), expected: [f32; 3]| {
+            (r - expected[0]).abs() < 1e-5 && (g - expected[1]).abs() < 1e-5 && (b - expected[2]).abs() < 1e-5
+        };
+        // Each sector starts at a primary or secondary color
+        let sectors = [
+            (0.0, [1.0, 0.0, 0.0]),
+            (60.0, [1.0, 1.0, 0.0]),
+            (120.0, [0.0, 1.0, 0.0]),
+            (180.0, [0.0, 1.0, 1.0]),
+            (240.0, [0.0, 0.0, 1.0]),
+            (300.0, [1.0, 0.0, 1.0]),
+            (360.0, [1.0, 0.0, 0.0]),
+        ];
+        for (hue, expected) in sectors {
+            assert!(close(hsv_to_rgb(hue, 1.0, 1.0), expected), "hue {}", hue);
+        }
+        // Hues outside 0-360 wrap around
+        assert!(close(hsv_to_rgb(420.0, 1.0, 1.0), [1.0, 1.0, 0.0]));
+        assert!(close(hsv_to_rgb(-120.0, 1.0, 1.0), [0.0, 0.0, 1.0]));
+        assert!(close(hsv_to_rgb(-1e-9, 1.0, 1.0), [1.0, 0.0, 0.0]));
+    }
+}
@@ -0,0 +1,129 @@
+// src/midi/note_set.rs
+
+use crate::midi::note::VISIBLE_PAST_FRACTION;
+use crate::midi::Note;
+use std::ops::RangeInclusive;
+
+/// A collection of notes sorted by start time, with queries for playback and analysis
+#[derive(Debug, Clone, Default)]
+pub struct NoteSet {
+    notes: Vec<Note>,
+}
+
+impl NoteSet {
+    /// Create a note set, sorting the notes by start time
+    pub fn new(mut notes: Vec<Note>) -> Self {
+        notes.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        NoteSet { notes }
+    }
+
+    /// Get all notes in start time order
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    /// Consume the set and return the sorted notes
+    pub fn into_vec(self) -> Vec<Note> {
+        self.notes
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Note> {
+        self.notes.iter()
+    }
+
+    /// Get the time at which the last note ends
+    pub fn duration(&self) -> f32 {
+        self.notes.iter().map(Note::end_time).fold(0.0, f32::max)
+    }
+
+    /// Notes sounding at `time` (started at or before it and not yet ended)
+    pub fn active_at(&self, time: f32) -> impl Iterator<Item = &Note> {
+        self.started_by(time).iter().filter(move |note| note.end_time() > time)
+    }
+
+    /// Notes visible at `time` with a `time_window` seconds tall view, see [`Note::is_visible`]
+    pub fn in_window(&self, time: f32, time_window: f32) -> impl Iterator<Item = &Note> {
+        self.started_by(time + time_window * (1.0 - VISIBLE_PAST_FRACTION))
+            .iter()
+            .filter(move |note| note.is_visible(time, time_window))
+    }
+
+    /// Notes on the given MIDI channel (0-15)
+    pub fn by_channel(&self, channel: u8) -> impl Iterator<Item = &Note> {
+        self.notes.iter().filter(move |note| note.channel == channel)
+    }
+
+    /// Notes whose pitch lies within `pitches`
+    pub fn in_pitch_range(&self, pitches: RangeInclusive<u8>) -> impl Iterator<Item = &Note> {
+        self.notes.iter().filter(move |note| pitches.contains(&note.pitch))
+    }
+
+    /// The notes starting at or before `time`
+    fn started_by(&self, time: f32) -> &[Note] {
+        let end = self.notes.partition_point(|note| note.start_time <= time);
+        &self.notes[..end]
+    }
+}
+
+impl From<Vec<Note>> for NoteSet {
+    fn from(notes: Vec<Note>) -> Self {
+        Self::new(notes)
+    }
+}
+
+impl<'a> IntoIterator for &'a NoteSet {
+    type Item = &'a Note;
+    type IntoIter = std::slice::Iter<'a, Note>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.notes.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_set() -> NoteSet {
+        NoteSet::new(vec![
+            Note::new(72, 100, 2.0, 1.0, 1),
+            Note::new(60, 100, 0.0, 1.0, 0),
+            Note::new(64, 100, 0.5, 2.0, 0),
+            Note::new(36, 100, 10.0, 0.5, 9),
+        ])
+    }
+
+    #[test]
+    fn test_sorted_by_start_time() {
+        let set = sample_set();
+        let starts: Vec<f32> = set.iter().map(|n| n.start_time).collect();
+        assert_eq!(starts, vec![0.0, 0.5, 2.0, 10.0]);
+        assert_eq!(set.duration(), 10.5);
+    }
+
+    #[test]
+    fn test_queries() {
+        let set = sample_set();
+
+        let active: Vec<u8> = set.active_at(0.75).map(|n| n.pitch).collect();
+        assert_eq!(active, vec![60, 64]);
+        // A note is no longer active at its exact end time
+        let active: Vec<u8> = set.active_at(1.0).map(|n| n.pitch).collect();
+        assert_eq!(active, vec![64]);
+
+        let visible: Vec<u8> = set.in_window(0.0, 5.0).map(|n| n.pitch).collect();
+        assert_eq!(visible, vec![60, 64, 72]);
+
+        assert_eq!(set.by_channel(0).count(), 2);
+        assert_eq!(set.by_channel(9).next().unwrap().pitch, 36);
+        assert_eq!(set.in_pitch_range(60..=64).count(), 2);
+    }
+}
@@ -1,10 +1,15 @@
 // src/midi/parser.rs
 
+use crate::midi::expression::Expression;
+use crate::midi::lyrics::{self, LyricEvents, Lyrics};
+use crate::midi::markers::{Marker, Markers};
 use crate::midi::note::Note;
+use crate::midi::player::format_time;
+use crate::midi::tempo::{TempoChange, TempoMap};
 use midly::{MidiMessage, Smf, TrackEventKind};
 use std::collections::HashMap;
 use std::fs;
-use std::io;
+use std::io::{self, Read};
 
 /// Error types for MIDI parsing
 #[derive(Debug)]
@@ -38,16 +43,152 @@ impl From<midly::Error> for ParseError {
     }
 }
 
+/// Statistics collected while parsing a MIDI file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseStats {
+    /// Total number of notes kept after filtering
+    pub total_notes: usize,
+    /// Number of notes kept in each track, indexed by track
+    pub track_note_counts: Vec<usize>,
+    /// Number of notes dropped for being shorter than `min_note_duration`
+    pub filtered_notes: usize,
+    /// Number of notes without a note-off that were given a default duration
+    pub unclosed_notes: usize,
+    /// Number of oversized delta times that were clamped
+    pub clamped_deltas: usize,
+    /// Number of notes dropped or shortened by the maximum song duration
+    pub capped_notes: usize,
+    /// When the last note ends, in seconds
+    pub notes_end: f32,
+    /// When the longest track ends at its end-of-track event, in seconds; later than
+    /// `notes_end` for files with a deliberate silent tail
+    pub track_end: f32,
+}
+
+impl ParseStats {
+    /// Get the number of tracks in the parsed file
+    pub fn track_count(&self) -> usize {
+        self.track_note_counts.len()
+    }
+
+    /// Get the length of the song: until the last note or track ends, whichever is later
+    pub fn duration(&self) -> f32 {
+        self.notes_end.max(self.track_end)
+    }
+
+    /// Get how long the song stays silent after its last note
+    pub fn silent_tail(&self) -> f32 {
+        (self.track_end - self.notes_end).max(0.0)
+    }
+}
+
+impl std::fmt::Display for ParseStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} notes in {} tracks ({} filtered, {} unclosed)",
+            self.total_notes,
+            self.track_count(),
+            self.filtered_notes,
+            self.unclosed_notes
+        )?;
+        if self.clamped_deltas > 0 {
+            write!(f, ", {} oversized deltas clamped", self.clamped_deltas)?;
+        }
+        if self.capped_notes > 0 {
+            write!(f, ", {} notes past the maximum duration", self.capped_notes)?;
+        }
+        // Tails shorter than the displayed precision aren't worth mentioning
+        if self.silent_tail() >= 1.0 {
+            write!(
+                f,
+                ", notes end at {}, track ends at {}",
+                format_time(self.notes_end),
+                format_time(self.track_end)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Names a track gives itself in its meta events
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackInfo {
+    /// Track name, e.g. "Violin I"
+    pub name: Option<String>,
+    /// Instrument name, e.g. "Strings"
+    pub instrument: Option<String>,
+}
+
+impl TrackInfo {
+    /// Read the first track and instrument name events of a track
+    fn from_track(track: &[midly::TrackEvent]) -> Self {
+        let mut info = TrackInfo::default();
+        let text = |data: &[u8]| Some(lyrics::decode(data).trim().to_string()).filter(|text| !text.is_empty());
+        for event in track {
+            match event.kind {
+                TrackEventKind::Meta(midly::MetaMessage::TrackName(name)) if info.name.is_none() => {
+                    info.name = text(name);
+                }
+                TrackEventKind::Meta(midly::MetaMessage::InstrumentName(name)) if info.instrument.is_none() => {
+                    info.instrument = text(name);
+                }
+                _ => {}
+            }
+        }
+        info
+    }
+
+    /// Get the name to show for the track: its own name, or else its instrument
+    pub fn label(&self) -> Option<&str> {
+        self.name.as_deref().or(self.instrument.as_deref())
+    }
+}
+
+/// Everything extracted from a MIDI file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedMidi {
+    /// All notes, sorted by start time
+    pub notes: Vec<Note>,
+    pub stats: ParseStats,
+    /// Pitch bend and other controller timelines
+    pub expression: Expression,
+    /// Synced lyrics, empty when the file has none
+    pub lyrics: Lyrics,
+    /// Named section markers such as "Verse" or "Chorus"
+    pub markers: Markers,
+    /// Tempo changes, empty when the whole song plays at the default tempo
+    pub tempo_map: TempoMap,
+    /// Names of the tracks, indexed like `Note::track_index`
+    pub tracks: Vec<TrackInfo>,
+}
+
+/// Longest silence accepted between two events, in seconds
+/// No piece rests for hours; longer deltas only occur in corrupt files and are shortened to this
+const MAX_DELTA_SECONDS: f64 = 3.0 * 60.0 * 60.0;
+
 /// MIDI file parser with support for Black MIDI files
 pub struct MidiParser {
     /// Minimum note duration in seconds (to filter out zero-length notes)
     min_note_duration: f32,
+    /// Maximum song duration in seconds; later notes are dropped
+    max_duration: Option<f32>,
+    /// Duration of notes still held at the end of their track when they start on its last tick
+    unclosed_duration: f32,
+    /// Ticks per beat used instead of the header's, for files with a broken header
+    forced_ticks_per_beat: Option<u16>,
+    /// Tempo in BPM used for the whole song instead of the file's tempo events
+    forced_tempo: Option<f32>,
 }
 
 impl MidiParser {
     pub fn new() -> Self {
         MidiParser {
             min_note_duration: 0.001, // 1ms minimum
+            max_duration: None,
+            unclosed_duration: 0.1,
+            forced_ticks_per_beat: None,
+            forced_tempo: None,
         }
     }
 
@@ -57,55 +198,212 @@ impl MidiParser {
         self
     }
 
+    /// Set the maximum song duration; notes past it are dropped or shortened
+    pub fn with_max_duration(mut self, duration: Option<f32>) -> Self {
+        self.max_duration = duration;
+        self
+    }
+
+    /// Set the duration given to notes that are still held on the last tick of their track
+    ///
+    /// Notes held earlier last until the end of the track instead.
+    pub fn with_unclosed_duration(mut self, duration: f32) -> Self {
+        self.unclosed_duration = duration;
+        self
+    }
+
+    /// Override the ticks per beat of the file header; None or 0 keeps the file's
+    pub fn with_ticks_per_beat(mut self, ticks_per_beat: Option<u16>) -> Self {
+        self.forced_ticks_per_beat = ticks_per_beat.filter(|&ticks| ticks > 0);
+        self
+    }
+
+    /// Play the whole song at `bpm`, ignoring the file's tempo events; None keeps them
+    pub fn with_tempo(mut self, bpm: Option<f32>) -> Self {
+        self.forced_tempo = bpm.filter(|bpm| bpm.is_finite() && *bpm > 0.0);
+        self
+    }
+
     /// Parse a MIDI file and return all notes with proper timing
     pub fn parse_file(&self, path: &str) -> Result<Vec<Note>, ParseError> {
+        self.parse_file_with_stats(path).map(|(notes, _)| notes)
+    }
+
+    /// Parse a MIDI file and return all notes along with parse statistics
+    pub fn parse_file_with_stats(&self, path: &str) -> Result<(Vec<Note>, ParseStats), ParseError> {
+        self.parse_file_detailed(path).map(|parsed| (parsed.notes, parsed.stats))
+    }
+
+    /// Parse a MIDI file into notes, statistics and controller timelines
+    pub fn parse_file_detailed(&self, path: &str) -> Result<ParsedMidi, ParseError> {
         log::info!("Parsing MIDI file: {}", path);
         
         let data = fs::read(path)?;
-        self.parse_bytes(&data)
+        self.parse_bytes_detailed(&data)
+    }
+
+    /// Parse MIDI data from any reader, such as a network stream or an archive entry
+    ///
+    /// The whole file is read into memory first, since an SMF can only be parsed in one piece.
+    pub fn parse_reader<R: Read>(&self, reader: R) -> Result<Vec<Note>, ParseError> {
+        self.parse_reader_with_stats(reader).map(|(notes, _)| notes)
+    }
+
+    /// Parse MIDI data from a reader and return all notes along with parse statistics
+    pub fn parse_reader_with_stats<R: Read>(&self, reader: R) -> Result<(Vec<Note>, ParseStats), ParseError> {
+        self.parse_reader_detailed(reader).map(|parsed| (parsed.notes, parsed.stats))
+    }
+
+    /// Parse MIDI data from a reader into notes, statistics and controller timelines
+    pub fn parse_reader_detailed<R: Read>(&self, mut reader: R) -> Result<ParsedMidi, ParseError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        self.parse_bytes_detailed(&data)
     }
 
     /// Parse MIDI data from bytes
     pub fn parse_bytes(&self, data: &[u8]) -> Result<Vec<Note>, ParseError> {
+        self.parse_bytes_with_stats(data).map(|(notes, _)| notes)
+    }
+
+    /// Parse MIDI data from bytes and return all notes along with parse statistics
+    pub fn parse_bytes_with_stats(&self, data: &[u8]) -> Result<(Vec<Note>, ParseStats), ParseError> {
+        self.parse_bytes_detailed(data).map(|parsed| (parsed.notes, parsed.stats))
+    }
+
+    /// Parse MIDI data from bytes into notes, statistics and controller timelines
+    pub fn parse_bytes_detailed(&self, data: &[u8]) -> Result<ParsedMidi, ParseError> {
         let smf = Smf::parse(data)?;
         
         // Get ticks per beat from the header
-        let ticks_per_beat = match smf.header.timing {
+        let mut ticks_per_beat = match smf.header.timing {
             midly::Timing::Metrical(tpb) => tpb.as_int() as f64,
             midly::Timing::Timecode(fps, subframe) => {
                 // For SMPTE timing, approximate
                 (fps.as_f32() * subframe as f32) as f64
             }
         };
+        if let Some(forced) = self.forced_ticks_per_beat {
+            log::info!("Ticks per beat override: {} replaces {} from the file", forced, ticks_per_beat);
+            ticks_per_beat = forced as f64;
+        }
+
+        if ticks_per_beat <= 0.0 {
+            return Err(ParseError::InvalidFile(format!(
+                "invalid timing: {} ticks per beat",
+                ticks_per_beat
+            )));
+        }
 
         log::info!("Ticks per beat: {}", ticks_per_beat);
         log::info!("Number of tracks: {}", smf.tracks.len());
 
         let mut all_notes = Vec::new();
+        let mut stats = ParseStats::default();
+        let mut expression = Expression::default();
+        let mut lyric_events = LyricEvents::default();
+        let mut markers = Vec::new();
+        let mut tempo_changes = Vec::new();
+        let mut tracks = Vec::with_capacity(smf.tracks.len());
         
         // Default tempo: 120 BPM = 500,000 microseconds per beat
-        let default_tempo = 500_000.0;
+        let default_tempo = match self.forced_tempo {
+            Some(bpm) => {
+                log::info!("Tempo override: {} BPM replaces the tempo of the file", bpm);
+                60_000_000.0 / bpm as f64
+            }
+            None => 500_000.0,
+        };
 
         for (track_idx, track) in smf.tracks.iter().enumerate() {
-            let mut notes = self.parse_track(track, ticks_per_beat, default_tempo)?;
+            let clamped_before = stats.clamped_deltas;
+            let (mut notes, track_end) = self.parse_track(
+                track,
+                ticks_per_beat,
+                default_tempo,
+                &mut stats,
+                &mut expression,
+                &mut lyric_events,
+                &mut markers,
+                &mut tempo_changes,
+            )?;
             log::debug!("Track {} has {} notes", track_idx, notes.len());
+            for note in &mut notes {
+                note.track_index = track_idx as u16;
+            }
+            if stats.clamped_deltas > clamped_before {
+                log::warn!(
+                    "Track {}: clamped {} oversized delta time(s) to {} hours",
+                    track_idx,
+                    stats.clamped_deltas - clamped_before,
+                    MAX_DELTA_SECONDS / 3600.0
+                );
+            }
+            stats.track_note_counts.push(notes.len());
+            stats.track_end = stats.track_end.max(track_end);
+            tracks.push(TrackInfo::from_track(track));
             all_notes.append(&mut notes);
         }
 
         // Sort notes by start time for efficient rendering
         all_notes.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
 
+        // Drop or shorten notes past the maximum song duration
+        if let Some(max_duration) = self.max_duration {
+            let before = all_notes.len();
+            all_notes.retain(|note| note.start_time < max_duration);
+            stats.capped_notes = before - all_notes.len();
+            for note in &mut all_notes {
+                if note.end_time() > max_duration {
+                    note.duration = max_duration - note.start_time;
+                    stats.capped_notes += 1;
+                }
+            }
+            if stats.capped_notes > 0 {
+                log::warn!(
+                    "{} notes dropped or shortened to the {:.0}s maximum duration",
+                    stats.capped_notes,
+                    max_duration
+                );
+            }
+            stats.track_end = stats.track_end.min(max_duration);
+        }
+        stats.notes_end = Self::get_duration(&all_notes);
+
+        expression.sort();
+        let lyrics = lyric_events.into_lyrics();
+        let markers = Markers::new(markers);
+        let tempo_map = TempoMap::new(tempo_changes);
+        if !lyrics.is_empty() {
+            log::info!("Lyrics: {} lines", lyrics.lines().len());
+        }
+
+        stats.total_notes = all_notes.len();
         log::info!("Total notes parsed: {}", all_notes.len());
-        Ok(all_notes)
+        Ok(ParsedMidi {
+            notes: all_notes,
+            stats,
+            expression,
+            lyrics,
+            markers,
+            tempo_map,
+            tracks,
+        })
     }
 
-    /// Parse a single MIDI track
+    /// Parse a single MIDI track, returning its notes and when it ends in seconds
+    #[allow(clippy::too_many_arguments)]
     fn parse_track(
         &self,
         track: &[midly::TrackEvent],
         ticks_per_beat: f64,
         default_tempo: f64,
-    ) -> Result<Vec<Note>, ParseError> {
+        stats: &mut ParseStats,
+        expression: &mut Expression,
+        lyric_events: &mut LyricEvents,
+        markers: &mut Vec<Marker>,
+        tempo_changes: &mut Vec<TempoChange>,
+    ) -> Result<(Vec<Note>, f32), ParseError> {
         let mut notes = Vec::new();
         
         // Track active notes: (pitch, channel) -> (start_tick, velocity)
@@ -117,13 +415,17 @@ impl MidiParser {
         
         for event in track {
             tick += event.delta.as_int() as u64;
-            if let TrackEventKind::Meta(midly::MetaMessage::Tempo(tempo)) = event.kind {
-                tempo_map.push((tick, tempo.as_int() as f64));
+            match event.kind {
+                // A forced tempo replaces all tempo events
+                TrackEventKind::Meta(midly::MetaMessage::Tempo(tempo)) if self.forced_tempo.is_none() => {
+                    tempo_map.push((tick, tempo.as_int() as f64));
+                }
+                _ => {}
             }
         }
 
         // Helper function to convert ticks to seconds using tempo map
-        let ticks_to_seconds = |tick: u64| -> f32 {
+        let file_seconds = |tick: u64| -> f64 {
             let mut seconds = 0.0;
             let mut last_tick = 0u64;
             let mut last_tempo = default_tempo;
@@ -143,11 +445,48 @@ impl MidiParser {
             let delta_ticks = tick - last_tick;
             seconds += (delta_ticks as f64 / ticks_per_beat) * (last_tempo / 1_000_000.0);
             
-            seconds as f32
+            seconds
+        };
+
+        // Corrupt deltas are shortened to MAX_DELTA_SECONDS: (tick ending the delta, seconds cut
+        // from it and all earlier ones). Deltas shorter than this many ticks can't be that long
+        // even at the fastest tempo of the track.
+        let fastest_tempo = tempo_map.iter().map(|&(_, tempo)| tempo).fold(f64::INFINITY, f64::min);
+        let min_oversized_ticks = MAX_DELTA_SECONDS / (fastest_tempo / 1_000_000.0) * ticks_per_beat;
+        let mut cuts: Vec<(u64, f64)> = Vec::new();
+        let mut tick = 0u64;
+        for event in track {
+            let delta = event.delta.as_int() as u64;
+            let start = tick;
+            tick += delta;
+            if delta as f64 >= min_oversized_ticks {
+                let excess = file_seconds(tick) - file_seconds(start) - MAX_DELTA_SECONDS;
+                if excess > 0.0 {
+                    let total = cuts.last().map_or(0.0, |&(_, cut)| cut) + excess;
+                    cuts.push((tick, total));
+                    stats.clamped_deltas += 1;
+                }
+            }
+        }
+        let precise_seconds = |tick: u64| -> f64 {
+            let cut = match cuts.partition_point(|&(end, _)| end <= tick) {
+                0 => 0.0,
+                index => cuts[index - 1].1,
+            };
+            file_seconds(tick) - cut
         };
+        let ticks_to_seconds = |tick: u64| precise_seconds(tick) as f32;
+        // Durations are taken before rounding to f32, which would cost them precision late in long songs
+        let note_duration = |start_tick: u64, end_tick: u64| (precise_seconds(end_tick) - precise_seconds(start_tick)) as f32;
+        tempo_changes.extend(
+            tempo_map[1..]
+                .iter()
+                .map(|&(tick, tempo)| TempoChange::from_micros_per_beat(ticks_to_seconds(tick), tempo)),
+        );
 
         // Second pass: process note events
         let mut current_tick: u64 = 0;
+        let mut end_tick = None;
         
         for event in track {
             current_tick += event.delta.as_int() as u64;
@@ -156,6 +495,21 @@ impl MidiParser {
                 TrackEventKind::Meta(midly::MetaMessage::Tempo(_)) => {
                     // Tempo changes are already processed in the tempo map
                 }
+                TrackEventKind::Meta(midly::MetaMessage::EndOfTrack) => {
+                    end_tick.get_or_insert(current_tick);
+                }
+                TrackEventKind::Meta(midly::MetaMessage::Lyric(text)) => {
+                    lyric_events.push_lyric(ticks_to_seconds(current_tick), text);
+                }
+                TrackEventKind::Meta(midly::MetaMessage::Text(text)) => {
+                    lyric_events.push_text(ticks_to_seconds(current_tick), text);
+                }
+                TrackEventKind::Meta(midly::MetaMessage::Marker(name)) => {
+                    markers.push(Marker {
+                        time: ticks_to_seconds(current_tick),
+                        name: lyrics::decode(name).trim().to_string(),
+                    });
+                }
                 TrackEventKind::Midi { channel, message } => {
                     let channel = channel.as_int();
                     
@@ -171,11 +525,12 @@ impl MidiParser {
                                 // Note off (velocity 0)
                                 if let Some((start_tick, vel)) = active_notes.remove(&(pitch, channel)) {
                                     let start_time = ticks_to_seconds(start_tick);
-                                    let end_time = ticks_to_seconds(current_tick);
-                                    let duration = end_time - start_time;
+                                    let duration = note_duration(start_tick, current_tick);
                                     
                                     if duration >= self.min_note_duration {
                                         notes.push(Note::new(pitch, vel, start_time, duration, channel));
+                                    } else {
+                                        stats.filtered_notes += 1;
                                     }
                                 }
                             }
@@ -185,14 +540,36 @@ impl MidiParser {
                             
                             if let Some((start_tick, vel)) = active_notes.remove(&(pitch, channel)) {
                                 let start_time = ticks_to_seconds(start_tick);
-                                let end_time = ticks_to_seconds(current_tick);
-                                let duration = end_time - start_time;
+                                let duration = note_duration(start_tick, current_tick);
                                 
                                 if duration >= self.min_note_duration {
                                     notes.push(Note::new(pitch, vel, start_time, duration, channel));
+                                } else {
+                                    stats.filtered_notes += 1;
                                 }
                             }
                         }
+                        MidiMessage::PitchBend { bend } => {
+                            expression.pitch_bends.push(channel, ticks_to_seconds(current_tick), bend.as_f32());
+                        }
+                        MidiMessage::ChannelAftertouch { vel } => {
+                            let pressure = vel.as_int() as f32 / 127.0;
+                            expression.channel_pressure.push(channel, ticks_to_seconds(current_tick), pressure);
+                        }
+                        MidiMessage::Aftertouch { key, vel } => {
+                            let pressure = vel.as_int() as f32 / 127.0;
+                            expression
+                                .key_pressure
+                                .push(channel, key.as_int(), ticks_to_seconds(current_tick), pressure);
+                        }
+                        MidiMessage::Controller { controller, value } => {
+                            let value = value.as_int() as f32 / 127.0;
+                            match controller.as_int() {
+                                7 => expression.volume.push(channel, ticks_to_seconds(current_tick), value),
+                                11 => expression.expression.push(channel, ticks_to_seconds(current_tick), value),
+                                _ => {}
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -200,17 +577,26 @@ impl MidiParser {
             }
         }
 
-        // Handle notes that were never closed (give them a default duration)
+        // Notes that were never closed sustain to the end of the track, or get the
+        // default duration when they start on its last tick
         for ((pitch, channel), (start_tick, velocity)) in active_notes {
             let start_time = ticks_to_seconds(start_tick);
-            let duration = 0.1; // Default 100ms for unclosed notes
+            let duration = if current_tick > start_tick {
+                note_duration(start_tick, current_tick)
+            } else {
+                self.unclosed_duration
+            };
             notes.push(Note::new(pitch, velocity, start_time, duration, channel));
+            stats.unclosed_notes += 1;
         }
 
-        Ok(notes)
+        // Tracks missing the end-of-track event end at their last event
+        Ok((notes, ticks_to_seconds(end_tick.unwrap_or(current_tick))))
     }
 
-    /// Get the duration of the MIDI file in seconds
+    /// Get when the last of `notes` ends in seconds
+    ///
+    /// The song can last longer, see [`ParseStats::duration`].
     pub fn get_duration(notes: &[Note]) -> f32 {
         notes
             .iter()
@@ -228,6 +614,51 @@ impl Default for MidiParser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use midly::num::{u24, u28, u4, u7};
+    use midly::{Format, Header, MetaMessage, PitchBend, Timing, TrackEvent};
+
+    /// Build a single-track SMF (480 ticks per beat) from (delta, message) pairs on channel 0
+    fn build_smf(events: &[(u32, MidiMessage)]) -> Vec<u8> {
+        let track: Vec<(u32, TrackEventKind)> =
+            events.iter().map(|&(delta, message)| (delta, midi(0, message))).collect();
+        build_smf_tracks(&[track])
+    }
+
+    /// Build an SMF (480 ticks per beat) with one track per list of (delta, event) pairs
+    fn build_smf_tracks(tracks: &[Vec<(u32, TrackEventKind)>]) -> Vec<u8> {
+        let format = if tracks.len() == 1 { Format::SingleTrack } else { Format::Parallel };
+        let mut smf = Smf::new(Header::new(format, Timing::Metrical(480.into())));
+        for events in tracks {
+            let mut track: Vec<TrackEvent> = events
+                .iter()
+                .map(|&(delta, kind)| TrackEvent { delta: u28::new(delta), kind })
+                .collect();
+            track.push(TrackEvent {
+                delta: u28::new(0),
+                kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+            });
+            smf.tracks.push(track);
+        }
+        let mut data = Vec::new();
+        smf.write_std(&mut data).unwrap();
+        data
+    }
+
+    fn midi(channel: u8, message: MidiMessage) -> TrackEventKind<'static> {
+        TrackEventKind::Midi { channel: u4::new(channel), message }
+    }
+
+    fn tempo(microseconds_per_beat: u32) -> TrackEventKind<'static> {
+        TrackEventKind::Meta(MetaMessage::Tempo(u24::new(microseconds_per_beat)))
+    }
+
+    fn note_on(key: u8, vel: u8) -> MidiMessage {
+        MidiMessage::NoteOn { key: u7::new(key), vel: u7::new(vel) }
+    }
+
+    fn note_off(key: u8) -> MidiMessage {
+        MidiMessage::NoteOff { key: u7::new(key), vel: u7::new(0) }
+    }
 
     #[test]
     fn test_parser_creation() {
@@ -240,4 +671,362 @@ mod tests {
         let parser = MidiParser::new().with_min_duration(0.01);
         assert_eq!(parser.min_note_duration, 0.01);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_stats() {
+        let data = build_smf(&[
+            // Regular note lasting one beat
+            (0, note_on(60, 100)),
+            (480, note_off(60)),
+            // Zero-length note, dropped by the duration filter
+            (0, note_on(62, 100)),
+            (0, note_off(62)),
+            // Note that is never closed
+            (0, note_on(64, 100)),
+        ]);
+
+        let (notes, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(stats.total_notes, 2);
+        assert_eq!(stats.track_count(), 1);
+        assert_eq!(stats.track_note_counts, vec![2]);
+        assert_eq!(stats.filtered_notes, 1);
+        assert_eq!(stats.unclosed_notes, 1);
+    }
+
+    #[test]
+    fn test_oversized_delta_is_clamped() {
+        let data = build_smf(&[
+            (0, note_on(60, 100)),
+            (480, note_off(60)),
+            // A corrupt delta of ~2^28 ticks would place this note days into the song
+            (0x0FFF_FFFF, note_on(62, 100)),
+            (480, note_off(62)),
+            // A long rest of 300 seconds at 120 BPM is kept
+            (288_000, note_on(64, 100)),
+            (480, note_off(64)),
+        ]);
+
+        let (notes, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(notes.len(), 3);
+        assert_eq!(stats.clamped_deltas, 1);
+        // Three hours after the first note ends at 0.5s
+        assert!((notes[1].start_time - 10_800.5).abs() < 1e-2);
+        assert!((notes[1].duration - 0.5).abs() < 1e-3);
+        assert!((notes[2].start_time - notes[1].end_time() - 300.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_max_duration() {
+        let data = build_smf(&[
+            (0, note_on(60, 100)),
+            (960, note_off(60)),
+            (960, note_on(62, 100)),
+            (480, note_off(62)),
+        ]);
+
+        let parser = MidiParser::new().with_max_duration(Some(0.5));
+        let (notes, stats) = parser.parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert!((notes[0].duration - 0.5).abs() < 1e-6);
+        assert_eq!(stats.capped_notes, 2);
+    }
+
+    #[test]
+    fn test_pitch_bends_are_captured() {
+        let data = build_smf(&[
+            (0, note_on(60, 100)),
+            (240, MidiMessage::PitchBend { bend: PitchBend::from_f32(0.5) }),
+            (240, note_off(60)),
+            (0, MidiMessage::PitchBend { bend: PitchBend::mid_raw_value() }),
+        ]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let bends = &parsed.expression.pitch_bends;
+        assert_eq!(bends.event_count(0), 2);
+        assert_eq!(bends.value_at(0, 0.1, 0.0), 0.0);
+        assert!((bends.value_at(0, 0.3, 0.0) - 0.5).abs() < 1e-3);
+        assert_eq!(bends.value_at(0, 1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_aftertouch_is_captured() {
+        let data = build_smf(&[
+            (0, note_on(60, 100)),
+            (240, MidiMessage::ChannelAftertouch { vel: u7::new(127) }),
+            (0, MidiMessage::Aftertouch { key: u7::new(60), vel: u7::new(0) }),
+            (240, note_off(60)),
+        ]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let expression = &parsed.expression;
+        assert_eq!(expression.channel_pressure.value_at(0, 0.3, 0.0), 1.0);
+        assert_eq!(expression.pressure_at(0, 60, 0.3), Some(0.0));
+        assert_eq!(expression.pressure_at(0, 61, 0.3), Some(1.0));
+    }
+
+    #[test]
+    fn test_volume_is_captured() {
+        let data = build_smf(&[
+            (0, MidiMessage::Controller { controller: u7::new(7), value: u7::new(127) }),
+            (0, note_on(60, 100)),
+            (240, MidiMessage::Controller { controller: u7::new(11), value: u7::new(0) }),
+            (0, MidiMessage::Controller { controller: u7::new(64), value: u7::new(127) }),
+            (240, note_off(60)),
+        ]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let expression = &parsed.expression;
+        assert_eq!(expression.volume.event_count(0), 1);
+        assert_eq!(expression.expression.event_count(0), 1);
+        assert_eq!(expression.volume_at(0, 0.1), 1.0);
+        assert_eq!(expression.volume_at(0, 0.3), 0.0);
+    }
+
+    #[test]
+    fn test_velocity_zero_note_on_ends_note() {
+        let data = build_smf(&[(0, note_on(60, 100)), (480, note_on(60, 0))]);
+
+        let notes = MidiParser::new().parse_bytes(&data).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].velocity, 100);
+        assert!((notes[0].duration - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_running_status() {
+        // Hand-written track relying on running status for every event after the first
+        #[rustfmt::skip]
+        let data: Vec<u8> = [
+            b"MThd".as_slice(), &[0, 0, 0, 6, 0, 0, 0, 1, 0x01, 0xE0],
+            b"MTrk".as_slice(), &[0, 0, 0, 17],
+            &[0x00, 0x90, 60, 100],  // note on 60
+            &[0x83, 0x60, 60, 0],    // one beat later, running status note on with velocity 0
+            &[0x00, 62, 100],        // running status note on 62
+            &[0x83, 0x60, 62, 0],    // one beat later, running status note off
+            &[0x00, 0xFF, 0x2F, 0],  // end of track
+        ]
+        .concat();
+
+        let (notes, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(stats.unclosed_notes, 0);
+        assert_eq!((notes[0].pitch, notes[0].start_time, notes[0].duration), (60, 0.0, 0.5));
+        assert_eq!((notes[1].pitch, notes[1].start_time, notes[1].duration), (62, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_interleaved_channels() {
+        // The same key on two channels overlaps without one closing the other
+        let data = build_smf_tracks(&[vec![
+            (0, midi(0, note_on(60, 100))),
+            (240, midi(1, note_on(60, 50))),
+            (240, midi(0, note_off(60))),
+            (480, midi(1, note_off(60))),
+        ]]);
+
+        let notes = MidiParser::new().parse_bytes(&data).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!((notes[0].channel, notes[0].velocity, notes[0].duration), (0, 100, 0.5));
+        assert_eq!((notes[1].channel, notes[1].velocity, notes[1].duration), (1, 50, 0.75));
+        assert_eq!(notes[1].start_time, 0.25);
+    }
+
+    #[test]
+    fn test_tempo_changes() {
+        // One beat at 120 BPM, then the tempo halves to 60 BPM
+        let data = build_smf_tracks(&[vec![
+            (0, tempo(500_000)),
+            (0, midi(0, note_on(60, 100))),
+            (480, midi(0, note_off(60))),
+            (0, tempo(1_000_000)),
+            (0, midi(0, note_on(62, 100))),
+            (480, midi(0, note_off(62))),
+            (480, midi(0, note_on(64, 100))),
+            (240, midi(0, note_off(64))),
+        ]]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let timings: Vec<(u8, f32, f32)> = parsed.notes.iter().map(|n| (n.pitch, n.start_time, n.duration)).collect();
+        assert_eq!(timings, vec![(60, 0.0, 0.5), (62, 0.5, 1.0), (64, 2.5, 0.5)]);
+        // Restating the default tempo is no change
+        assert_eq!(parsed.tempo_map.changes(), &[TempoChange { time: 0.5, bpm: 60.0 }]);
+    }
+
+    #[test]
+    fn test_unclosed_notes_sustain_to_track_end() {
+        let data = build_smf(&[(0, note_on(60, 100)), (480, note_on(64, 90))]);
+
+        let (notes, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(stats.unclosed_notes, 2);
+        let timings: Vec<(u8, f32, f32)> = notes.iter().map(|n| (n.pitch, n.start_time, n.duration)).collect();
+        // The first note holds until the last event, the one starting there gets the default
+        assert_eq!(timings, vec![(60, 0.0, 0.5), (64, 0.5, 0.1)]);
+
+        let notes = MidiParser::new().with_unclosed_duration(0.25).parse_bytes(&data).unwrap();
+        assert_eq!(notes[1].duration, 0.25);
+    }
+
+    #[test]
+    fn test_empty_tracks() {
+        let data = build_smf_tracks(&[
+            vec![],
+            vec![(0, midi(0, note_on(60, 100))), (480, midi(0, note_off(60)))],
+            vec![],
+        ]);
+
+        let (notes, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(stats.track_note_counts, vec![0, 1, 0]);
+
+        // A file with nothing but empty tracks parses to no notes
+        let data = build_smf_tracks(&[vec![], vec![]]);
+        let (notes, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert!(notes.is_empty());
+        assert_eq!(stats.track_count(), 2);
+        assert_eq!(MidiParser::get_duration(&notes), 0.0);
+    }
+
+    #[test]
+    fn test_lyrics_are_captured() {
+        let data = build_smf_tracks(&[
+            vec![(0, midi(0, note_on(60, 100))), (960, midi(0, note_off(60)))],
+            vec![
+                (0, TrackEventKind::Meta(MetaMessage::Lyric(b"Hel"))),
+                (240, TrackEventKind::Meta(MetaMessage::Lyric(b"lo\r"))),
+                (480, TrackEventKind::Meta(MetaMessage::Lyric(b"again"))),
+            ],
+        ]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let lines = parsed.lyrics.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text(), "Hello");
+        assert_eq!(lines[0].syllables[1].time, 0.25);
+        assert_eq!(lines[1].start_time(), 0.75);
+    }
+
+    #[test]
+    fn test_markers_are_captured() {
+        let data = build_smf_tracks(&[vec![
+            (0, TrackEventKind::Meta(MetaMessage::Marker(b"Intro"))),
+            (0, midi(0, note_on(60, 100))),
+            (960, TrackEventKind::Meta(MetaMessage::Marker(b""))),
+            (960, TrackEventKind::Meta(MetaMessage::Marker(b"Chorus "))),
+            (0, midi(0, note_off(60))),
+        ]]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let markers = parsed.markers.markers();
+        assert_eq!(markers.len(), 3);
+        // Unnamed markers still mark a point in the song
+        assert_eq!((markers[1].time, markers[1].name.as_str()), (1.0, ""));
+        assert_eq!((markers[2].time, markers[2].name.as_str()), (2.0, "Chorus"));
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let data = build_smf(&[(0, note_on(60, 100)), (480, note_off(60))]);
+        let from_reader = MidiParser::new().parse_reader(std::io::Cursor::new(&data)).unwrap();
+        let from_bytes = MidiParser::new().parse_bytes(&data).unwrap();
+        assert_eq!(from_reader.len(), 1);
+        assert_eq!(from_reader[0].pitch, from_bytes[0].pitch);
+        assert_eq!(from_reader[0].duration, from_bytes[0].duration);
+
+        // Read errors are reported as IO errors
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+        }
+        assert!(matches!(MidiParser::new().parse_reader(Failing), Err(ParseError::IoError(_))));
+    }
+
+    #[test]
+    fn test_track_names() {
+        let data = build_smf_tracks(&[
+            vec![(0, TrackEventKind::Meta(MetaMessage::TrackName(b" Piano ")))],
+            vec![
+                (0, TrackEventKind::Meta(MetaMessage::InstrumentName(b"Strings"))),
+                (0, midi(1, note_on(60, 100))),
+                (480, midi(1, note_off(60))),
+            ],
+            vec![],
+        ]);
+
+        let parsed = MidiParser::new().parse_bytes_detailed(&data).unwrap();
+        let labels: Vec<Option<&str>> = parsed.tracks.iter().map(TrackInfo::label).collect();
+        assert_eq!(labels, vec![Some("Piano"), Some("Strings"), None]);
+    }
+
+    #[test]
+    fn test_timing_overrides() {
+        // Half a beat at 480 ticks per beat, then a tempo change to 60 BPM
+        let data = build_smf_tracks(&[vec![
+            (240, midi(0, note_on(60, 100))),
+            (0, tempo(1_000_000)),
+            (480, midi(0, note_off(60))),
+        ]]);
+        let note = |parser: MidiParser| {
+            let notes = parser.parse_bytes(&data).unwrap();
+            (notes[0].start_time, notes[0].duration)
+        };
+        assert_eq!(note(MidiParser::new()), (0.25, 1.0));
+
+        // Fewer ticks per beat make every tick longer
+        assert_eq!(note(MidiParser::new().with_ticks_per_beat(Some(240))), (0.5, 2.0));
+        // A forced tempo ignores the tempo change
+        assert_eq!(note(MidiParser::new().with_tempo(Some(240.0))), (0.125, 0.25));
+        assert_eq!(note(MidiParser::new().with_ticks_per_beat(Some(0)).with_tempo(Some(-1.0))), (0.25, 1.0));
+
+        let parsed = MidiParser::new().with_tempo(Some(240.0)).parse_bytes_detailed(&data).unwrap();
+        assert!(parsed.tempo_map.changes().is_empty());
+    }
+
+    #[test]
+    fn test_long_song_keeps_millisecond_precision() {
+        // 6000 seconds of silence in deltas below the clamp, then two notes a tick (~1 ms) apart
+        let mut events: Vec<(u32, TrackEventKind)> = (0..5).map(|_| (1_152_000, tempo(500_000))).collect();
+        events.extend([
+            (0, midi(0, note_on(60, 100))),
+            (1, midi(0, note_on(62, 100))),
+            (1, midi(0, note_off(60))),
+            (1, midi(0, note_off(62))),
+        ]);
+        let notes = MidiParser::new().with_min_duration(0.0).parse_bytes(&build_smf_tracks(&[events])).unwrap();
+        assert_eq!(notes.len(), 2);
+
+        let tick = 1.0 / 960.0;
+        assert!((notes[0].start_time - 6000.0).abs() < 5e-4);
+        assert!((notes[1].start_time - notes[0].start_time - tick).abs() < 5e-4);
+        // Durations come out exact, not rounded to the coarse steps of the start times
+        assert!((notes[0].duration - 2.0 * tick).abs() < 1e-6);
+        assert!((notes[1].duration - 2.0 * tick).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_silent_tail_extends_duration() {
+        // The notes end after a beat, a second track keeps going for ten beats
+        let data = build_smf_tracks(&[
+            vec![(0, midi(0, note_on(60, 100))), (480, midi(0, note_off(60)))],
+            vec![(4800, TrackEventKind::Meta(MetaMessage::Text(b"fade")))],
+        ]);
+        let (_, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(stats.notes_end, 0.5);
+        assert_eq!(stats.track_end, 5.0);
+        assert_eq!(stats.duration(), 5.0);
+        assert_eq!(stats.silent_tail(), 4.5);
+        assert!(stats.to_string().ends_with("notes end at 0:00, track ends at 0:05"), "{}", stats);
+
+        // The maximum duration cuts the tail too
+        let (_, stats) = MidiParser::new().with_max_duration(Some(2.0)).parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(stats.duration(), 2.0);
+
+        // Without a tail the song ends with its last note
+        let data = build_smf(&[(0, note_on(60, 100)), (480, note_off(60))]);
+        let (_, stats) = MidiParser::new().parse_bytes_with_stats(&data).unwrap();
+        assert_eq!(stats.duration(), 0.5);
+        assert_eq!(stats.silent_tail(), 0.0);
+    }
+}
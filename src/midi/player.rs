@@ -1,9 +1,37 @@
 // src/midi/player.rs
 
+/// Format a time in seconds as m:ss, or h:mm:ss for an hour or longer
+pub fn format_time(seconds: f32) -> String {
+    let total = seconds.max(0.0) as u64;
+    let (hours, minutes, secs) = (total / 3600, (total / 60) % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
 pub struct MidiPlayer {
-    current_time: f32,
+    /// Playback position in seconds, kept in f64 so many small frame steps add up exactly
+    /// even hours into a song, where an f32 would round every step
+    current_time: f64,
     is_playing: bool,
+    /// Speed set by the user
     playback_speed: f32,
+    /// Speed playback currently runs at, ramping towards `playback_speed`
+    effective_speed: f32,
+    /// Seconds a speed change takes to take full effect, 0.0 for instant changes
+    speed_ramp: f32,
+    /// Seconds left until the effective speed reaches the set speed
+    ramp_remaining: f32,
+    /// Length of the loaded song (in seconds)
+    duration: f32,
+    /// Start over at the beginning when the end is reached instead of stopping
+    looping: bool,
+    /// Move backward instead of forward, paused or not, e.g. while the rewind key is held
+    rewinding: bool,
+    /// The latest update reached the end of the song and stopped playback there
+    just_finished: bool,
 }
 
 impl MidiPlayer {
@@ -12,10 +40,21 @@ impl MidiPlayer {
             current_time: 0.0,
             is_playing: false,
             playback_speed: 1.0,
+            effective_speed: 1.0,
+            speed_ramp: 0.0,
+            ramp_remaining: 0.0,
+            duration: 0.0,
+            looping: false,
+            rewinding: false,
+            just_finished: false,
         }
     }
 
+    /// Start playback, from the beginning when the song has finished
     pub fn play(&mut self) {
+        if self.is_finished() {
+            self.current_time = 0.0;
+        }
         self.is_playing = true;
     }
 
@@ -24,35 +63,94 @@ impl MidiPlayer {
     }
 
     pub fn toggle_playback(&mut self) {
-        self.is_playing = !self.is_playing;
+        if self.is_playing {
+            self.pause();
+        } else {
+            self.play();
+        }
     }
 
     pub fn is_playing(&self) -> bool {
         self.is_playing
     }
 
+    /// Get the playback position, precise to the millisecond for over two hours
     pub fn get_current_time(&self) -> f32 {
+        self.current_time as f32
+    }
+
+    /// Get the playback position at full precision
+    pub fn get_precise_time(&self) -> f64 {
         self.current_time
     }
 
+    pub fn get_duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn set_duration(&mut self, duration: f32) {
+        self.duration = duration.max(0.0);
+    }
+
+    /// Check whether playback has reached the end of the song
+    /// Without a duration (nothing loaded, or live input) playback never finishes
+    pub fn is_finished(&self) -> bool {
+        self.duration > 0.0 && self.current_time >= self.duration as f64
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Set whether playback starts over at the end instead of stopping there
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Get the playback position as "elapsed / total" text, e.g. "1:23 / 4:56"
+    pub fn get_time_text(&self) -> String {
+        format!("{} / {}", format_time(self.get_current_time()), format_time(self.duration))
+    }
+
+    /// Get the speed set by the user, which playback may still be ramping towards
     pub fn get_playback_speed(&self) -> f32 {
         self.playback_speed
     }
 
+    /// Get the speed playback runs at right now
+    pub fn get_effective_speed(&self) -> f32 {
+        self.effective_speed
+    }
+
+    /// Set the playback speed, reached over the speed ramp time
     pub fn set_playback_speed(&mut self, speed: f32) {
         self.playback_speed = speed.clamp(0.5, 2.0);
+        self.ramp_remaining = self.speed_ramp;
+        if self.ramp_remaining <= 0.0 {
+            self.effective_speed = self.playback_speed;
+        }
+    }
+
+    /// Set how many seconds speed changes take to take full effect, 0.0 for instant changes
+    pub fn set_speed_ramp(&mut self, seconds: f32) {
+        self.speed_ramp = seconds.max(0.0);
     }
 
-    pub fn increase_speed(&mut self) {
-        self.set_playback_speed(self.playback_speed + 0.1);
+    pub fn increase_speed(&mut self, step: f32) {
+        self.step_speed(step);
     }
 
-    pub fn decrease_speed(&mut self) {
-        self.set_playback_speed(self.playback_speed - 0.1);
+    pub fn decrease_speed(&mut self, step: f32) {
+        self.step_speed(-step);
+    }
+
+    /// Change the speed by `delta`, rounded to hundredths so repeated steps don't drift
+    fn step_speed(&mut self, delta: f32) {
+        self.set_playback_speed(((self.playback_speed + delta) * 100.0).round() / 100.0);
     }
 
     pub fn seek(&mut self, time: f32) {
-        self.current_time = time.max(0.0);
+        self.current_time = time.max(0.0) as f64;
     }
 
     pub fn reset(&mut self) {
@@ -60,15 +158,213 @@ impl MidiPlayer {
         self.is_playing = false;
     }
 
+    pub fn is_rewinding(&self) -> bool {
+        self.rewinding
+    }
+
+    /// Start or stop rewinding; playback continues as before once stopped
+    pub fn set_rewinding(&mut self, rewinding: bool) {
+        self.rewinding = rewinding;
+    }
+
+    /// Advance playback, stopping at the end of the song or wrapping around when looping
+    ///
+    /// While rewinding, time runs backward at the playback speed until the start of the song.
     pub fn update(&mut self, delta_time: f32) {
-        if self.is_playing {
-            self.current_time += delta_time * self.playback_speed;
+        self.just_finished = false;
+        // Ramp the speed linearly so it arrives at the set speed after the ramp time;
+        // while paused there is no motion to smooth
+        if self.is_playing && self.ramp_remaining > delta_time {
+            self.effective_speed += (self.playback_speed - self.effective_speed) * delta_time / self.ramp_remaining;
+            self.ramp_remaining -= delta_time;
+        } else {
+            self.effective_speed = self.playback_speed;
+            self.ramp_remaining = 0.0;
+        }
+
+        if self.rewinding {
+            self.current_time = (self.current_time - delta_time as f64 * self.effective_speed as f64).max(0.0);
+            return;
+        }
+        if !self.is_playing {
+            return;
+        }
+        self.current_time += delta_time as f64 * self.effective_speed as f64;
+        if self.is_finished() {
+            if self.looping {
+                self.current_time %= self.duration as f64;
+            } else {
+                self.current_time = self.duration as f64;
+                self.is_playing = false;
+                self.just_finished = true;
+            }
         }
     }
+
+    /// Check if the latest update played to the end of the song and stopped there, e.g. to
+    /// move on to the next song and keep playing
+    pub fn has_just_finished(&self) -> bool {
+        self.just_finished
+    }
 }
 
 impl Default for MidiPlayer {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0.0), "0:00");
+        assert_eq!(format_time(83.9), "1:23");
+        assert_eq!(format_time(296.0), "4:56");
+        assert_eq!(format_time(3723.0), "1:02:03");
+        assert_eq!(format_time(-5.0), "0:00");
+    }
+
+    #[test]
+    fn test_time_text() {
+        let mut player = MidiPlayer::new();
+        player.set_duration(296.0);
+        player.seek(83.0);
+        assert_eq!(player.get_time_text(), "1:23 / 4:56");
+    }
+
+    #[test]
+    fn test_speed_ramp() {
+        let mut player = MidiPlayer::new();
+        player.set_speed_ramp(0.1);
+        player.play();
+        player.set_playback_speed(2.0);
+        // The set speed is reported right away, the effective one follows
+        assert_eq!(player.get_playback_speed(), 2.0);
+        assert_eq!(player.get_effective_speed(), 1.0);
+
+        let mut last = player.get_effective_speed();
+        for _ in 0..4 {
+            player.update(0.02);
+            let speed = player.get_effective_speed();
+            assert!(speed > last && speed < 2.0);
+            last = speed;
+        }
+        player.update(0.02);
+        assert_eq!(player.get_effective_speed(), 2.0);
+        // Playback moved at the ramping speeds, not the target from the start
+        assert!(player.get_current_time() < 0.1 * 2.0);
+
+        // Without a ramp, and while paused, changes are instant
+        player.set_speed_ramp(0.0);
+        player.set_playback_speed(0.5);
+        assert_eq!(player.get_effective_speed(), 0.5);
+        player.set_speed_ramp(0.1);
+        player.pause();
+        player.set_playback_speed(1.0);
+        player.update(0.01);
+        assert_eq!(player.get_effective_speed(), 1.0);
+    }
+
+    #[test]
+    fn test_reaching_the_end() {
+        let mut player = MidiPlayer::new();
+        // Without a song playback runs on
+        player.play();
+        player.update(5.0);
+        assert!(!player.is_finished() && player.is_playing());
+
+        player.reset();
+        player.set_duration(10.0);
+        player.play();
+        player.update(9.0);
+        assert!(!player.is_finished());
+        // Advancing past the end stops there and pauses
+        player.update(2.0);
+        assert!(player.is_finished());
+        assert!(!player.is_playing());
+        assert_eq!(player.get_current_time(), 10.0);
+
+        // Playing again starts over
+        player.toggle_playback();
+        assert!(player.is_playing());
+        assert_eq!(player.get_current_time(), 0.0);
+
+        // Looping wraps around and keeps playing
+        player.set_looping(true);
+        player.seek(9.5);
+        player.update(1.0);
+        assert!(player.is_playing());
+        assert!((player.get_current_time() - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut player = MidiPlayer::new();
+        player.set_duration(10.0);
+        player.seek(10.0);
+        player.set_playback_speed(2.0);
+
+        // Rewinding works while paused, even from the end of the song, at the playback speed
+        player.set_rewinding(true);
+        player.update(1.0);
+        assert_eq!(player.get_current_time(), 8.0);
+        assert!(!player.is_playing() && !player.is_finished());
+        // ...and stops at the start
+        player.update(5.0);
+        assert_eq!(player.get_current_time(), 0.0);
+
+        // Playback continues forward once released
+        player.seek(4.0);
+        player.play();
+        player.update(0.5);
+        assert_eq!(player.get_current_time(), 3.0);
+        player.set_rewinding(false);
+        player.update(0.5);
+        assert_eq!(player.get_current_time(), 4.0);
+        assert!(player.is_playing());
+    }
+
+    #[test]
+    fn test_long_song_keeps_precision() {
+        let mut player = MidiPlayer::new();
+        player.set_duration(7200.0);
+        player.seek(6000.0);
+        player.play();
+        // An hour and a half in, ten seconds of 144 Hz frames still add up to ten seconds
+        for _ in 0..144 * 10 {
+            player.update(1.0 / 144.0);
+        }
+        assert!((player.get_precise_time() - 6010.0).abs() < 1e-6);
+        assert!((player.get_current_time() - 6010.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_finishing_stops_once() {
+        let mut player = MidiPlayer::new();
+        player.set_duration(1.0);
+        player.play();
+        player.update(0.5);
+        assert!(!player.has_just_finished());
+        player.update(0.6);
+        assert!(player.has_just_finished() && player.is_finished() && !player.is_playing());
+        // Only the update reaching the end reports it
+        player.update(0.1);
+        assert!(!player.has_just_finished());
+
+        // The next song picks up playback where the first one stopped it
+        player.update(0.6);
+        player.reset();
+        player.set_duration(2.0);
+        player.play();
+        player.update(0.5);
+        assert!(player.is_playing() && !player.has_just_finished());
+
+        // Looping never finishes
+        player.set_looping(true);
+        player.update(2.0);
+        assert!(player.is_playing() && !player.has_just_finished());
+    }
+}
@@ -0,0 +1,97 @@
+// src/midi/tempo.rs
+
+/// Tempo assumed before the first tempo event, as the MIDI standard specifies
+pub const DEFAULT_BPM: f32 = 120.0;
+/// Changes smaller than this (in BPM) are rounding noise and don't count as a change
+const BPM_EPSILON: f32 = 0.01;
+
+/// A point where the tempo of the song changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoChange {
+    /// Time in seconds
+    pub time: f32,
+    /// Tempo from this point on, in beats per minute
+    pub bpm: f32,
+}
+
+impl TempoChange {
+    /// Create a tempo change from a tempo event's microseconds per beat
+    pub fn from_micros_per_beat(time: f32, micros_per_beat: f64) -> Self {
+        TempoChange {
+            time,
+            bpm: (60_000_000.0 / micros_per_beat) as f32,
+        }
+    }
+}
+
+/// Tempo events of a file, sorted by time
+///
+/// Events that don't change the tempo are left out.
+#[derive(Debug, Clone, Default)]
+pub struct TempoMap {
+    changes: Vec<TempoChange>,
+}
+
+impl TempoMap {
+    /// Create a tempo map, sorting the changes by time
+    /// Of several changes at the same time the last one wins, as in playback
+    pub fn new(mut changes: Vec<TempoChange>) -> Self {
+        changes.retain(|change| change.bpm.is_finite() && change.bpm > 0.0);
+        changes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut kept: Vec<TempoChange> = Vec::with_capacity(changes.len());
+        for change in changes {
+            if kept.last().is_some_and(|last| last.time == change.time) {
+                kept.pop();
+            }
+            let previous = kept.last().map_or(DEFAULT_BPM, |last| last.bpm);
+            if (change.bpm - previous).abs() >= BPM_EPSILON {
+                kept.push(change);
+            }
+        }
+        TempoMap { changes: kept }
+    }
+
+    pub fn changes(&self) -> &[TempoChange] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Get the tempo in effect at `time`, in beats per minute
+    pub fn bpm_at(&self, time: f32) -> f32 {
+        let index = self.changes.partition_point(|change| change.time <= time);
+        index.checked_sub(1).map_or(DEFAULT_BPM, |index| self.changes[index].bpm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(time: f32, bpm: f32) -> TempoChange {
+        TempoChange { time, bpm }
+    }
+
+    #[test]
+    fn test_tempo_map() {
+        let map = TempoMap::new(vec![
+            change(4.0, 90.0),
+            change(0.0, 100.0),
+            change(2.0, 100.0),
+            change(4.0, 60.0),
+            change(6.0, 0.0),
+        ]);
+        // The repeated tempo and the invalid one are dropped, the later change at 4s wins
+        assert_eq!(map.changes(), &[change(0.0, 100.0), change(4.0, 60.0)]);
+
+        assert_eq!(map.bpm_at(3.9), 100.0);
+        assert_eq!(map.bpm_at(4.0), 60.0);
+        assert_eq!(TempoMap::default().bpm_at(1.0), DEFAULT_BPM);
+        // Restating the default tempo at the start is no change
+        assert!(TempoMap::new(vec![change(0.0, 120.0)]).is_empty());
+        assert_eq!(TempoChange::from_micros_per_beat(1.0, 400_000.0).bpm, 150.0);
+    }
+}
@@ -0,0 +1,75 @@
+// src/midi/voices.rs
+
+use crate::config::VoiceStealing;
+use crate::midi::Note;
+
+/// Simulate a synth with `max_voices` voices per channel and find the notes it would cut off
+///
+/// Returns one entry per note: the time its voice was stolen by a later note on the same
+/// channel before it ended, None for notes that keep their voice. Notes are processed in
+/// start time order whatever the order of the slice.
+pub fn steal_voices(notes: &[Note], max_voices: usize, policy: VoiceStealing) -> Vec<Option<f32>> {
+    let max_voices = max_voices.max(1);
+    let mut stolen = vec![None; notes.len()];
+
+    let mut order: Vec<usize> = (0..notes.len()).collect();
+    order.sort_by(|&a, &b| notes[a].start_time.total_cmp(&notes[b].start_time));
+
+    // Indices of the notes holding a voice, per channel, in start order
+    let mut voices: [Vec<usize>; 16] = Default::default();
+
+    for index in order {
+        let note = &notes[index];
+        let active = &mut voices[note.channel as usize & 0x0F];
+        active.retain(|&held| notes[held].end_time() > note.start_time);
+
+        if active.len() >= max_voices {
+            let victim = match policy {
+                VoiceStealing::Oldest => 0,
+                // `min_by_key` keeps the first of equal velocities, which is the oldest
+                VoiceStealing::Quietest => (0..active.len())
+                    .min_by_key(|&slot| notes[active[slot]].velocity)
+                    .unwrap_or(0),
+            };
+            stolen[active.remove(victim)] = Some(note.start_time);
+        }
+        active.push(index);
+    }
+
+    stolen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_voice_is_stolen() {
+        let notes = vec![
+            Note::new(60, 100, 0.0, 2.0, 0),
+            Note::new(62, 40, 0.5, 2.0, 0),
+            Note::new(64, 100, 1.0, 2.0, 0),
+            // Another channel has its own voices
+            Note::new(48, 100, 1.0, 2.0, 1),
+        ];
+
+        // The voice is taken when the third note starts
+        assert_eq!(steal_voices(&notes, 2, VoiceStealing::Oldest), vec![Some(1.0), None, None, None]);
+        assert_eq!(steal_voices(&notes, 2, VoiceStealing::Quietest), vec![None, Some(1.0), None, None]);
+        assert_eq!(steal_voices(&notes, 3, VoiceStealing::Oldest), vec![None; 4]);
+    }
+
+    #[test]
+    fn test_finished_notes_free_their_voice() {
+        let notes = vec![
+            Note::new(60, 100, 0.0, 1.0, 0),
+            Note::new(62, 100, 1.0, 1.0, 0),
+            Note::new(64, 100, 2.0, 1.0, 0),
+        ];
+        assert_eq!(steal_voices(&notes, 1, VoiceStealing::Oldest), vec![None; 3]);
+
+        // A limit of zero still leaves one voice and out of order input is handled
+        let overlapping = vec![Note::new(64, 100, 0.5, 1.0, 0), Note::new(60, 100, 0.0, 1.0, 0)];
+        assert_eq!(steal_voices(&overlapping, 0, VoiceStealing::Oldest), vec![None, Some(0.5)]);
+    }
+}
@@ -1,12 +1,27 @@
 // src/performance.rs
 
+use crate::midi::{MidiPlayer, Note};
+use crate::renderer::NoteRenderer;
+use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
+/// Time constant of the smoothed FPS (in seconds): frames this long ago weigh about a third
+const FPS_SMOOTHING: f32 = 0.5;
+/// Gaps between frames longer than this (in seconds) are pauses, not slow frames
+const MAX_FRAME_INTERVAL: f32 = 0.5;
+
 pub struct PerformanceMonitor {
     frame_count: u32,
     last_time: Instant,
+    /// Frames counted over the last full second
     fps: f32,
+    /// When the previous frame was rendered
+    last_frame: Option<Instant>,
+    /// Exponential moving average of the time between frames (in seconds)
+    smoothed_interval: Option<f32>,
     render_time: Duration,
+    /// GPU execution time of a recent frame, None without timestamp query support
+    gpu_time: Option<Duration>,
 }
 
 impl PerformanceMonitor {
@@ -15,28 +30,366 @@ impl PerformanceMonitor {
             frame_count: 0,
             last_time: Instant::now(),
             fps: 0.0,
+            last_frame: None,
+            smoothed_interval: None,
             render_time: Duration::new(0, 0),
+            gpu_time: None,
         }
     }
 
-    pub fn frame_rendered(&mut self, render_duration: Duration) {
+    /// Record a rendered frame, returning true when a new FPS sample was taken
+    pub fn frame_rendered(&mut self, render_duration: Duration) -> bool {
+        self.frame_rendered_at(render_duration, Instant::now())
+    }
+
+    /// Record a frame rendered at `now`, returning true when a new one-second FPS sample was taken
+    pub fn frame_rendered_at(&mut self, render_duration: Duration, now: Instant) -> bool {
         self.frame_count += 1;
         self.render_time = render_duration;
 
-        if self.last_time.elapsed().as_secs() >= 1 {
+        if let Some(last) = self.last_frame {
+            let interval = now.duration_since(last).as_secs_f32();
+            // Frames after a pause would drag the average down for seconds, so start over
+            if interval > MAX_FRAME_INTERVAL {
+                self.smoothed_interval = None;
+            } else if interval > 0.0 {
+                // Averaging the intervals rather than their inverse keeps the mean frame rate exact
+                let average = self.smoothed_interval.get_or_insert(interval);
+                let weight = 1.0 - (-interval / FPS_SMOOTHING).exp();
+                *average += (interval - *average) * weight;
+            }
+        }
+        self.last_frame = Some(now);
+
+        if now.duration_since(self.last_time).as_secs() >= 1 {
             self.fps = self.frame_count as f32;
             self.frame_count = 0;
-            self.last_time = Instant::now();
+            self.last_time = now;
+            return true;
         }
+        false
     }
 
+    /// Get the number of frames rendered during the last full second
     pub fn get_fps(&self) -> f32 {
         self.fps
     }
 
+    /// Get the frame rate averaged over the last half second or so, updated every frame
+    /// Falls back to the one-second count until two frames came close enough together
+    pub fn get_smoothed_fps(&self) -> f32 {
+        match self.smoothed_interval {
+            Some(interval) if interval > 0.0 => 1.0 / interval,
+            _ => self.fps,
+        }
+    }
+
+    /// Get the CPU time spent encoding and submitting the last frame
     pub fn get_render_time(&self) -> Duration {
         self.render_time
     }
 
+    /// Record the GPU execution time of a frame, measured with timestamp queries
+    pub fn gpu_frame_measured(&mut self, gpu_time: Duration) {
+        self.gpu_time = Some(gpu_time);
+    }
+
+    /// Get the GPU execution time of a recent frame, None when it can't be measured
+    pub fn get_gpu_time(&self) -> Option<Duration> {
+        self.gpu_time
+    }
+
     // Here you can add more methods to fetch system stats if needed.
-}
\ No newline at end of file
+}
+
+impl Default for PerformanceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Snapshot of rendering and playback statistics, e.g. for telemetry or quality reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderStats {
+    pub fps: f32,
+    /// Render time of the last frame in milliseconds
+    pub frame_time_ms: f32,
+    /// GPU execution time of a recent frame in milliseconds, when the GPU can measure it
+    pub gpu_time_ms: Option<f32>,
+    /// Notes drawn in the last frame
+    pub visible_notes: u32,
+    /// Notes sounding at the current time
+    pub polyphony: usize,
+    /// Notes started during the last second of playback
+    pub notes_per_second: usize,
+    /// Playback position in seconds
+    pub current_time: f32,
+    /// Song length in seconds
+    pub duration: f32,
+}
+
+impl RenderStats {
+    /// Gather statistics from the running application state
+    /// `notes` must be sorted by start time; polyphony scans every note started so far
+    pub fn capture(
+        monitor: &PerformanceMonitor,
+        note_renderer: &NoteRenderer,
+        player: &MidiPlayer,
+        notes: &[Note],
+    ) -> Self {
+        let current_time = player.get_current_time();
+        let started = notes.partition_point(|note| note.start_time <= current_time);
+
+        RenderStats {
+            fps: monitor.get_fps(),
+            frame_time_ms: monitor.get_render_time().as_secs_f32() * 1000.0,
+            gpu_time_ms: monitor.get_gpu_time().map(|time| time.as_secs_f32() * 1000.0),
+            visible_notes: note_renderer.visible_count(),
+            polyphony: notes[..started].iter().filter(|note| note.end_time() > current_time).count(),
+            notes_per_second: notes_per_second(notes, current_time),
+            current_time,
+            duration: player.get_duration(),
+        }
+    }
+}
+
+/// Count the notes that started during the second up to `time`
+/// `notes` must be sorted by start time
+pub fn notes_per_second(notes: &[Note], time: f32) -> usize {
+    let started = notes.partition_point(|note| note.start_time <= time);
+    let second_ago = notes.partition_point(|note| note.start_time <= time - 1.0);
+    started - second_ago
+}
+
+/// Paces rendering to the FPS cap and decides how far playback advances each frame
+///
+/// Capped, every frame advances playback by one frame budget and the time left over carries
+/// to the next redraw. After a hitch, the time owed is limited to `1 / min_fps` worth of
+/// frames (at least one budget); the rest is skipped instead of being caught up frame by
+/// frame, which on a slow GPU would keep every following frame late.
+#[derive(Debug, Default)]
+pub struct FrameLimiter {
+    /// Time since the last rendered frame not yet played back
+    accumulator: Duration,
+}
+
+impl FrameLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render the next frame right away without counting the time before it, e.g. after idling
+    pub fn restart(&mut self, target: Option<Duration>) {
+        self.accumulator = target.unwrap_or(Duration::ZERO);
+    }
+
+    /// Add the `delta` since the last redraw and get the playback step of the frame to render,
+    /// or None while the frame budget `target` hasn't passed yet; uncapped (`target` None)
+    /// every redraw renders and steps by the real elapsed time, up to `1 / min_fps`
+    pub fn advance(&mut self, delta: Duration, target: Option<Duration>, min_fps: u32) -> Option<Duration> {
+        let max_backlog = Duration::from_secs_f64(1.0 / min_fps.max(1) as f64);
+        match target {
+            Some(target) => {
+                self.accumulator = (self.accumulator + delta).min(max_backlog.max(target));
+                (self.accumulator >= target).then(|| {
+                    self.accumulator -= target;
+                    target
+                })
+            }
+            None => Some((std::mem::take(&mut self.accumulator) + delta).min(max_backlog)),
+        }
+    }
+}
+
+/// Lowest note budget adaptive quality will reduce to
+const ADAPTIVE_MIN_NOTES: u32 = 10_000;
+/// Fraction of the target FPS below which a sample counts as slow
+const ADAPTIVE_LOW_RATIO: f32 = 0.8;
+/// Fraction of the target FPS above which a sample counts as healthy
+const ADAPTIVE_HIGH_RATIO: f32 = 0.95;
+/// Consecutive slow samples before the note budget is reduced
+const ADAPTIVE_LOW_SAMPLES: u32 = 2;
+/// Consecutive healthy samples before the note budget is restored
+const ADAPTIVE_HIGH_SAMPLES: u32 = 5;
+
+/// Adjusts the rendered note budget based on the sustained frame rate
+pub struct AdaptiveQuality {
+    /// Configured note budget, never exceeded
+    max_notes: u32,
+    /// Currently applied note budget
+    note_limit: u32,
+    /// Consecutive FPS samples below the low threshold
+    slow_samples: u32,
+    /// Consecutive FPS samples above the high threshold
+    healthy_samples: u32,
+}
+
+impl AdaptiveQuality {
+    pub fn new(max_notes: u32) -> Self {
+        AdaptiveQuality {
+            max_notes,
+            note_limit: max_notes,
+            slow_samples: 0,
+            healthy_samples: 0,
+        }
+    }
+
+    /// Get the currently applied note budget
+    pub fn note_limit(&self) -> u32 {
+        self.note_limit
+    }
+
+    /// Feed a new FPS sample; returns the new note budget if it changed
+    pub fn update(&mut self, fps: f32, target_fps: f32) -> Option<u32> {
+        if fps < target_fps * ADAPTIVE_LOW_RATIO {
+            self.slow_samples += 1;
+            self.healthy_samples = 0;
+        } else if fps >= target_fps * ADAPTIVE_HIGH_RATIO {
+            self.healthy_samples += 1;
+            self.slow_samples = 0;
+        } else {
+            self.slow_samples = 0;
+            self.healthy_samples = 0;
+        }
+
+        let new_limit = if self.slow_samples >= ADAPTIVE_LOW_SAMPLES {
+            self.slow_samples = 0;
+            (self.note_limit / 2).max(ADAPTIVE_MIN_NOTES.min(self.max_notes))
+        } else if self.healthy_samples >= ADAPTIVE_HIGH_SAMPLES {
+            self.healthy_samples = 0;
+            self.note_limit.saturating_add(self.note_limit / 2).min(self.max_notes)
+        } else {
+            self.note_limit
+        };
+
+        if new_limit == self.note_limit {
+            return None;
+        }
+
+        if new_limit < self.note_limit {
+            log::info!(
+                "Adaptive quality: FPS {:.0} below target {:.0}, reducing note limit to {}",
+                fps, target_fps, new_limit
+            );
+        } else {
+            log::info!("Adaptive quality: FPS recovered, raising note limit to {}", new_limit);
+        }
+        self.note_limit = new_limit;
+        Some(new_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_reduces_on_sustained_low_fps() {
+        let mut adaptive = AdaptiveQuality::new(1_000_000);
+        assert_eq!(adaptive.update(20.0, 60.0), None);
+        assert_eq!(adaptive.update(20.0, 60.0), Some(500_000));
+        assert_eq!(adaptive.note_limit(), 500_000);
+    }
+
+    #[test]
+    fn test_adaptive_restores_on_recovery() {
+        let mut adaptive = AdaptiveQuality::new(1_000_000);
+        adaptive.update(20.0, 60.0);
+        adaptive.update(20.0, 60.0);
+        for _ in 0..4 {
+            assert_eq!(adaptive.update(60.0, 60.0), None);
+        }
+        assert_eq!(adaptive.update(60.0, 60.0), Some(750_000));
+        for _ in 0..5 {
+            adaptive.update(60.0, 60.0);
+        }
+        assert_eq!(adaptive.note_limit(), 1_000_000);
+    }
+
+    #[test]
+    fn test_adaptive_respects_floor() {
+        let mut adaptive = AdaptiveQuality::new(15_000);
+        for _ in 0..10 {
+            adaptive.update(1.0, 60.0);
+        }
+        assert_eq!(adaptive.note_limit(), ADAPTIVE_MIN_NOTES);
+    }
+
+    #[test]
+    fn test_render_stats_capture() {
+        let monitor = PerformanceMonitor::new();
+        let note_renderer = NoteRenderer::new(&crate::config::AppConfig::default());
+        let mut player = MidiPlayer::new();
+        let notes: Vec<Note> = (0..40).map(|i| Note::new(60, 100, i as f32 * 0.25, 1.0, 0)).collect();
+        player.set_duration(10.75);
+        player.seek(5.1);
+
+        let stats = RenderStats::capture(&monitor, &note_renderer, &player, &notes);
+        // Notes starting at 4.25, 4.5, 4.75 and 5.0 are sounding
+        assert_eq!(stats.polyphony, 4);
+        assert_eq!(stats.notes_per_second, 4);
+        assert_eq!(stats.duration, 10.75);
+
+        let json = serde_json::to_string(&stats).unwrap();
+        assert_eq!(serde_json::from_str::<RenderStats>(&json).unwrap(), stats);
+    }
+
+    #[test]
+    fn test_smoothed_fps() {
+        let mut monitor = PerformanceMonitor::new();
+        let start = monitor.last_time;
+        let frame = Duration::from_micros(6944);
+        assert_eq!(monitor.get_smoothed_fps(), 0.0);
+
+        // Steady 144 Hz frames average to 144 FPS long before the one-second count
+        for i in 0..100 {
+            monitor.frame_rendered_at(Duration::ZERO, start + frame * i);
+        }
+        assert!((monitor.get_smoothed_fps() - 144.0).abs() < 0.1);
+        assert_eq!(monitor.get_fps(), 0.0);
+
+        // One slow frame moves the average a little instead of jumping to its rate
+        let slow = start + frame * 99 + Duration::from_millis(20);
+        monitor.frame_rendered_at(Duration::ZERO, slow);
+        let fps = monitor.get_smoothed_fps();
+        assert!(fps < 144.0 && fps > 130.0, "{}", fps);
+
+        // The raw count is still sampled once a second
+        assert!(monitor.frame_rendered_at(Duration::ZERO, start + Duration::from_millis(1001)));
+        assert_eq!(monitor.get_fps(), 102.0);
+
+        // A pause starts the average over
+        monitor.frame_rendered_at(Duration::ZERO, start + Duration::from_secs(5));
+        assert_eq!(monitor.get_smoothed_fps(), monitor.get_fps());
+    }
+
+    #[test]
+    fn test_frame_limiter_skips_after_hitch() {
+        let ms = Duration::from_millis;
+        let target = Some(ms(10));
+        let mut limiter = FrameLimiter::new();
+        // Frames wait for their budget and the leftover time carries over
+        assert_eq!(limiter.advance(ms(6), target, 20), None);
+        assert_eq!(limiter.advance(ms(6), target, 20), Some(ms(10)));
+        assert_eq!(limiter.advance(ms(8), target, 20), Some(ms(10)));
+
+        // After a one second hitch only 50 ms are owed at a 20 FPS floor, so a few frames
+        // render right away and then the pace is back to normal
+        let catch_up = std::iter::once(ms(1000))
+            .chain(std::iter::repeat(Duration::ZERO))
+            .take(20)
+            .filter(|&delta| limiter.advance(delta, target, 20).is_some())
+            .count();
+        assert_eq!(catch_up, 5);
+        assert_eq!(limiter.advance(ms(4), target, 20), None);
+
+        // Uncapped frames step by the elapsed time, up to the floor
+        assert_eq!(limiter.advance(ms(7), None, 20), Some(ms(11)));
+        assert_eq!(limiter.advance(ms(1000), None, 20), Some(ms(50)));
+
+        // After idling the next frame renders at once without stepping playback further
+        limiter.restart(target);
+        assert_eq!(limiter.advance(Duration::ZERO, target, 20), Some(ms(10)));
+        assert_eq!(limiter.advance(ms(5), target, 20), None);
+    }
+}
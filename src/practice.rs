@@ -0,0 +1,211 @@
+// src/practice.rs
+
+use crate::config::ChannelConfig;
+use crate::midi::{MidiPlayer, Note};
+
+/// Notes starting within this many seconds of the first one are played together as a chord
+const CHORD_WINDOW: f32 = 0.03;
+/// Largest playback step treated as continuous; bigger jumps are seeks and skip no chord
+const MAX_CONTINUOUS_STEP: f32 = 1.0;
+
+/// A chord playback is stopped at until its keys are played
+#[derive(Debug, Clone, PartialEq)]
+struct Chord {
+    /// Start time of the chord's first note, where playback waits
+    time: f32,
+    /// Start time of the chord's last note, after which the next chord begins
+    last_start: f32,
+    /// Keys to play, sorted and without duplicates
+    pitches: Vec<u8>,
+}
+
+/// Practice mode: playback waits at every note or chord until its keys are played on a
+/// connected MIDI keyboard
+///
+/// Call [`PracticeMode::update`] every frame after the player advanced, with the keys pressed
+/// since the previous frame. Notes on muted channels are not waited for, so muting the left
+/// hand practices the right one. Resuming playback while waiting skips the chord.
+#[derive(Debug, Default)]
+pub struct PracticeMode {
+    /// Chord being waited for
+    waiting: Option<Chord>,
+    /// Playback time of the previous update; chords starting after it come up next
+    last_time: Option<f32>,
+    /// Keys of the current or last chord that were played
+    correct: Vec<u8>,
+    /// Keys played that are not in the current chord
+    wrong: Vec<u8>,
+}
+
+impl PracticeMode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the practice state, e.g. after loading a different file
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Check whether playback is stopped waiting for keys to be played
+    pub fn is_waiting(&self) -> bool {
+        self.waiting.is_some()
+    }
+
+    /// Get the keys of the chord being waited for
+    pub fn waiting_for(&self) -> Option<&[u8]> {
+        self.waiting.as_ref().map(|chord| chord.pitches.as_slice())
+    }
+
+    /// Get the keys of the current or last chord that were played, shown in green
+    pub fn correct_keys(&self) -> &[u8] {
+        &self.correct
+    }
+
+    /// Get the keys played that are not in the chord being waited for
+    pub fn wrong_keys(&self) -> &[u8] {
+        &self.wrong
+    }
+
+    /// Stop playback at the next chord and continue once its keys were played
+    /// `notes` must be sorted by start time; `pressed` are the keys pressed since the last update
+    pub fn update(&mut self, notes: &[Note], player: &mut MidiPlayer, pressed: &[u8], channels: &ChannelConfig) {
+        let now = player.get_current_time();
+
+        if let Some(chord) = self.waiting.take() {
+            if player.is_playing() || (now - chord.time).abs() > f32::EPSILON {
+                // Resumed or seeked away: skip the chord
+                self.wrong.clear();
+                self.last_time = Some(now.max(chord.last_start));
+                return;
+            }
+            self.play_keys(&chord, pressed);
+            if chord.pitches.iter().all(|pitch| self.correct.contains(pitch)) {
+                self.last_time = Some(chord.last_start);
+                self.wrong.clear();
+                player.play();
+            } else {
+                self.waiting = Some(chord);
+            }
+            return;
+        }
+
+        let last = match self.last_time {
+            // Seeking backward or far forward starts over from the new position
+            Some(last) if now >= last && now - last <= MAX_CONTINUOUS_STEP => last,
+            Some(_) => now,
+            None => f32::NEG_INFINITY,
+        };
+        self.last_time = Some(now);
+
+        if let Some(chord) = Self::next_chord(notes, last, now, channels) {
+            player.seek(chord.time);
+            player.pause();
+            self.correct.clear();
+            self.wrong.clear();
+            self.waiting = Some(chord);
+            // Keys pressed right as the chord comes up count for it
+            self.update(notes, player, pressed, channels);
+        }
+    }
+
+    /// Sort the pressed keys into the chord's and wrong ones
+    fn play_keys(&mut self, chord: &Chord, pressed: &[u8]) {
+        for &pitch in pressed {
+            let keys = if chord.pitches.binary_search(&pitch).is_ok() { &mut self.correct } else { &mut self.wrong };
+            if !keys.contains(&pitch) {
+                keys.push(pitch);
+            }
+        }
+    }
+
+    /// Find the first chord of unmuted notes starting after `last` and up to `now`
+    fn next_chord(notes: &[Note], last: f32, now: f32, channels: &ChannelConfig) -> Option<Chord> {
+        let first = notes.partition_point(|note| note.start_time <= last);
+        let start = notes[first..]
+            .iter()
+            .take_while(|note| note.start_time <= now)
+            .find(|note| !channels.is_muted(note.channel))?
+            .start_time;
+
+        let chord: Vec<&Note> = notes[first..]
+            .iter()
+            .skip_while(|note| note.start_time < start)
+            .take_while(|note| note.start_time < start + CHORD_WINDOW)
+            .filter(|note| !channels.is_muted(note.channel))
+            .collect();
+        let last_start = chord.last().map_or(start, |note| note.start_time);
+        let mut pitches: Vec<u8> = chord.iter().map(|note| note.pitch).collect();
+        pitches.sort_unstable();
+        pitches.dedup();
+        Some(Chord { time: start, last_start, pitches })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waits_for_chords() {
+        let notes = vec![
+            Note::new(60, 100, 0.5, 0.5, 0),
+            Note::new(64, 100, 0.51, 0.5, 0),
+            Note::new(36, 100, 0.5, 1.0, 1),
+            Note::new(67, 100, 1.0, 0.5, 0),
+        ];
+        // The left hand on channel 2 plays along without waiting
+        let channels = ChannelConfig {
+            muted: vec![1],
+            ..Default::default()
+        };
+        let mut player = MidiPlayer::new();
+        player.set_duration(2.0);
+        let mut practice = PracticeMode::new();
+
+        player.play();
+        player.update(0.1);
+        practice.update(&notes, &mut player, &[], &channels);
+        assert!(!practice.is_waiting());
+
+        // Playback stops at the chord, even when the frame stepped past it
+        player.update(0.5);
+        practice.update(&notes, &mut player, &[], &channels);
+        assert_eq!(practice.waiting_for(), Some(&[60, 64][..]));
+        assert!(!player.is_playing());
+        assert_eq!(player.get_current_time(), 0.5);
+
+        practice.update(&notes, &mut player, &[60, 62], &channels);
+        assert_eq!(practice.correct_keys(), &[60]);
+        assert_eq!(practice.wrong_keys(), &[62]);
+        assert!(practice.is_waiting());
+
+        // The last key completes the chord and playback continues to the next note
+        practice.update(&notes, &mut player, &[64], &channels);
+        assert!(!practice.is_waiting() && player.is_playing());
+        assert_eq!(practice.correct_keys(), &[60, 64]);
+        assert!(practice.wrong_keys().is_empty());
+        player.update(0.2);
+        practice.update(&notes, &mut player, &[], &channels);
+        assert!(!practice.is_waiting());
+        player.update(0.4);
+        practice.update(&notes, &mut player, &[], &channels);
+        assert_eq!(practice.waiting_for(), Some(&[67][..]));
+        assert!(practice.correct_keys().is_empty());
+
+        // Resuming playback skips the chord
+        player.play();
+        practice.update(&notes, &mut player, &[], &channels);
+        assert!(!practice.is_waiting());
+        player.update(0.1);
+        practice.update(&notes, &mut player, &[], &channels);
+        assert!(!practice.is_waiting() && player.is_playing());
+
+        // Seeking back waits at the chords from there on
+        player.seek(0.2);
+        practice.update(&notes, &mut player, &[], &channels);
+        player.update(0.4);
+        practice.update(&notes, &mut player, &[], &channels);
+        assert_eq!(practice.waiting_for(), Some(&[60, 64][..]));
+    }
+}
@@ -0,0 +1,8 @@
+// src/prelude.rs
+
+// The types most programs embedding the visualizer need, for `use midi_rs::prelude::*`
+// Everything here is also available from its own module.
+
+pub use crate::config::{AppConfig, DisplayConfig, QualityConfig};
+pub use crate::midi::{Expression, MidiParser, MidiPlayer, Note, ParseError, ParseStats, ParsedMidi};
+pub use crate::renderer::{FrameExporter, PipelineError, RenderPipeline, Uniforms, Visualizer};
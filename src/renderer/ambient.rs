@@ -0,0 +1,152 @@
+// src/renderer/ambient.rs
+
+use crate::config::AppConfig;
+use crate::midi::MidiPlayer;
+use crate::renderer::pipeline::RenderPipeline;
+use std::time::{Duration, Instant};
+
+/// Frame rate of the idle animation; it drifts so slowly that more frames would only cost power
+const AMBIENT_FPS: u64 = 30;
+
+/// Idle background animation: faint glows slowly drifting and changing color
+///
+/// Shown while nothing is loaded or playback is paused at the start of the song, so the
+/// window isn't a still black rectangle. The glows are drawn over the cleared background
+/// with the shared uniforms, whose time is the animation's own clock while it plays,
+/// since the playback time stands still.
+pub struct AmbientRenderer {
+    /// When the animation clock started
+    started: Instant,
+    /// Whether the last frame showed the animation
+    active: bool,
+    /// Created on first use
+    pipeline: Option<wgpu::RenderPipeline>,
+}
+
+impl AmbientRenderer {
+    pub fn new() -> Self {
+        AmbientRenderer {
+            started: Instant::now(),
+            active: false,
+            pipeline: None,
+        }
+    }
+
+    /// Check whether the scene is idle: no notes loaded, or paused at the start of the song
+    pub fn is_idle(notes_loaded: bool, player: &MidiPlayer) -> bool {
+        !notes_loaded || (!player.is_playing() && !player.is_rewinding() && player.get_current_time() <= 0.0)
+    }
+
+    /// Follow the settings and scene state, creating the pipeline when the animation starts
+    pub fn update(&mut self, pipeline: &RenderPipeline, idle: bool, config: &AppConfig) {
+        self.active = idle && config.display.idle_animation;
+        if self.active && self.pipeline.is_none() {
+            self.pipeline = Some(create_ambient_pipeline(pipeline));
+        }
+    }
+
+    /// Check whether the last frame showed the animation
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Get the animation clock in seconds, passed to the shaders as the time uniform
+    pub fn time(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+
+    /// Get when to draw the next frame after one drawn at `last_frame`, None while not animating
+    pub fn next_frame(&self, last_frame: Instant) -> Option<Instant> {
+        self.active.then(|| last_frame + Duration::from_millis(1000 / AMBIENT_FPS))
+    }
+
+    /// Draw the glows over the background
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a RenderPipeline) {
+        if !self.active {
+            return;
+        }
+        if let Some(ambient_pipeline) = &self.pipeline {
+            render_pass.set_pipeline(ambient_pipeline);
+            render_pass.set_bind_group(0, &pipeline.uniform_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+impl Default for AmbientRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create the pipeline drawing one screen-covering triangle with the ambient shader
+fn create_ambient_pipeline(pipeline: &RenderPipeline) -> wgpu::RenderPipeline {
+    let device = &pipeline.device;
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Ambient Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("../../assets/shaders/ambient.wgsl").into()),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Ambient Pipeline Layout"),
+        bind_group_layouts: &[&pipeline.uniform_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Ambient Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: pipeline.config.format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_idle_scene() {
+        let mut player = MidiPlayer::new();
+        player.set_duration(10.0);
+        assert!(AmbientRenderer::is_idle(false, &player));
+        // Paused at the start, the song hasn't begun yet
+        assert!(AmbientRenderer::is_idle(true, &player));
+        player.play();
+        assert!(!AmbientRenderer::is_idle(true, &player));
+        player.update(1.0);
+        player.pause();
+        assert!(!AmbientRenderer::is_idle(true, &player));
+
+        let mut config = AppConfig::default();
+        let size = winit::dpi::PhysicalSize::new(64, 64);
+        let pipeline = require_gpu(RenderPipeline::new_offscreen(size, &config));
+        let mut ambient = AmbientRenderer::new();
+        let now = Instant::now();
+        ambient.update(&pipeline, true, &config);
+        assert!(ambient.is_active() && ambient.pipeline.is_some());
+        assert!(ambient.next_frame(now).is_some_and(|next| next > now));
+
+        config.display.idle_animation = false;
+        ambient.update(&pipeline, true, &config);
+        assert!(!ambient.is_active());
+        assert_eq!(ambient.next_frame(now), None);
+    }
+}
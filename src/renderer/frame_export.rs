@@ -0,0 +1,217 @@
+// src/renderer/frame_export.rs
+
+use crate::config::AppConfig;
+use crate::renderer::pipeline::RenderPipeline;
+use crate::renderer::visualizer::Visualizer;
+
+/// Bytes per pixel of the offscreen target (RGBA8)
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// Renders frames of a song offscreen for image and video export
+///
+/// Frames are drawn `export.supersample` times larger than the exported size and scaled down
+/// with a box filter, which smooths the note edges independently of the interactive window.
+/// Sizes given in pixels, like `display.note_gap`, apply at the supersampled size.
+/// This is library API only: the midi-rs binary has no export command.
+pub struct FrameExporter {
+    pipeline: RenderPipeline,
+    visualizer: Visualizer,
+    /// Size of the exported frames
+    width: u32,
+    height: u32,
+    /// How many rendered pixels make up one exported pixel along each axis
+    supersample: u32,
+    /// Buffer the rendered frame is copied into for reading it back
+    readback: wgpu::Buffer,
+}
+
+impl FrameExporter {
+    /// Create an exporter for `width` x `height` frames
+    ///
+    /// The supersampling factor is lowered when the enlarged frame would exceed the maximum
+    /// texture size of the device. Returns None when no adapter or device is available.
+    pub async fn new(width: u32, height: u32, config: &AppConfig) -> Option<Self> {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut pipeline = RenderPipeline::new_offscreen(winit::dpi::PhysicalSize::new(width, height), config).await?;
+        let max_dimension = pipeline.device.limits().max_texture_dimension_2d;
+        let supersample = supersample_factor(width, height, config.export.supersample(), max_dimension);
+        if supersample < config.export.supersample() {
+            log::warn!(
+                "Export supersampling lowered to {}x, {}x{} at {}x exceeds the maximum texture size",
+                supersample,
+                width,
+                height,
+                config.export.supersample()
+            );
+        }
+
+        let size = winit::dpi::PhysicalSize::new(width * supersample, height * supersample);
+        pipeline.resize(size);
+        let readback = pipeline.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Export Readback Buffer"),
+            size: padded_row_bytes(size.width) as u64 * size.height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(FrameExporter {
+            pipeline,
+            visualizer: Visualizer::new(config),
+            width,
+            height,
+            supersample,
+            readback,
+        })
+    }
+
+    /// Get the size of the exported frames
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get the supersampling factor actually used
+    pub fn supersample(&self) -> u32 {
+        self.supersample
+    }
+
+    /// Get the visualizer drawing the frames, to load a song into it
+    pub fn visualizer_mut(&mut self) -> &mut Visualizer {
+        &mut self.visualizer
+    }
+
+    /// Render the frame at `current_time` and return its RGBA8 pixels, row by row from the top
+    pub fn render_frame(&mut self, current_time: f32, config: &AppConfig) -> Vec<u8> {
+        let Some(texture) = self.pipeline.offscreen_texture() else {
+            return Vec::new();
+        };
+        let (render_width, render_height) = (texture.width(), texture.height());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self.pipeline.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Export Encoder"),
+        });
+        self.visualizer.encode(&self.pipeline, &mut encoder, &view, current_time, config);
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes(render_width)),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: render_width,
+                height: render_height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.pipeline.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            if let Err(e) = result {
+                log::error!("Failed to read back the exported frame: {}", e);
+            }
+        });
+        self.pipeline.device.poll(wgpu::Maintain::Wait);
+        let pixels = downsample(
+            &slice.get_mapped_range(),
+            padded_row_bytes(render_width) as usize,
+            self.width as usize,
+            self.height as usize,
+            self.supersample as usize,
+        );
+        self.readback.unmap();
+        pixels
+    }
+}
+
+/// Get the largest factor up to `requested` that keeps the enlarged frame within the
+/// maximum texture size `max_dimension`
+fn supersample_factor(width: u32, height: u32, requested: u32, max_dimension: u32) -> u32 {
+    (1..=requested)
+        .rev()
+        .find(|factor| width * factor <= max_dimension && height * factor <= max_dimension)
+        .unwrap_or(1)
+}
+
+/// Get the bytes of one row in a texture copy, which wgpu aligns to 256 bytes
+fn padded_row_bytes(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * BYTES_PER_PIXEL).div_ceil(align) * align
+}
+
+/// Scale RGBA8 pixels down by `factor`, averaging each `factor` x `factor` block
+/// `stride` is the number of bytes per source row; the result has no row padding
+fn downsample(pixels: &[u8], stride: usize, width: usize, height: usize, factor: usize) -> Vec<u8> {
+    let bytes = BYTES_PER_PIXEL as usize;
+    let samples = (factor * factor) as u32;
+    let mut output = Vec::with_capacity(width * height * bytes);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+            for row in y * factor..(y + 1) * factor {
+                let start = row * stride + x * factor * bytes;
+                for pixel in pixels[start..start + factor * bytes].chunks_exact(bytes) {
+                    for (total, &channel) in sum.iter_mut().zip(pixel) {
+                        *total += channel as u32;
+                    }
+                }
+            }
+            // Round to the nearest value
+            output.extend(sum.map(|total| ((total + samples / 2) / samples) as u8));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+    use crate::midi::Note;
+
+    #[test]
+    fn test_downsample() {
+        // A 4x2 image with 8 bytes of row padding, scaled down to 2x1
+        let mut pixels = Vec::new();
+        for row in [[0u8, 255, 100, 100], [255, 255, 100, 101]] {
+            for value in row {
+                pixels.extend([value, value, 0, 255]);
+            }
+            pixels.extend([7; 8]);
+        }
+        let output = downsample(&pixels, 24, 2, 1, 2);
+        assert_eq!(output, vec![191, 191, 0, 255, 100, 100, 0, 255]);
+        assert_eq!(downsample(&pixels, 24, 4, 2, 1)[..8], pixels[..8]);
+
+        // Oversized frames fall back to a smaller factor
+        assert_eq!(supersample_factor(1920, 1080, 4, 8192), 4);
+        assert_eq!(supersample_factor(3000, 10, 4, 8192), 2);
+        assert_eq!(supersample_factor(10000, 10, 4, 8192), 1);
+        // Smaller devices lower the factor sooner
+        assert_eq!(supersample_factor(1920, 1080, 4, 2048), 1);
+        assert_eq!(supersample_factor(1000, 1000, 4, 2048), 2);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_supersampled_export() {
+        let mut config = AppConfig::default();
+        config.display.background_color = [0.0, 0.0, 0.0, 1.0];
+        config.export.supersample = 4;
+        let mut exporter = require_gpu(FrameExporter::new(50, 30, &config));
+        assert_eq!(exporter.size(), (50, 30));
+        assert_eq!(exporter.supersample(), 4);
+
+        let notes = (0..128u8).map(|pitch| Note::new(pitch, 127, 0.0, 10.0, 0)).collect();
+        exporter.visualizer_mut().set_notes(notes, &config);
+        let frame = exporter.render_frame(1.0, &config);
+        assert_eq!(frame.len(), 50 * 30 * 4);
+        // Notes fill the middle of the frame over the black background
+        let middle = (15 * 50 + 25) * 4;
+        assert!(frame[middle..middle + 3].iter().any(|&channel| channel > 64), "{:?}", &frame[middle..middle + 4]);
+    }
+}
@@ -0,0 +1,261 @@
+// src/renderer/gpu_timer.rs
+
+use crate::renderer::pipeline::RenderPipeline;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Readback buffers in flight; results arrive a frame or two late, so one buffer would stall
+const READBACK_SLOTS: usize = 3;
+/// Size of the two u64 timestamps written per frame (in bytes)
+const TIMESTAMP_BYTES: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+/// States of a readback buffer's mapping, set by the map callback
+const MAP_WAITING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+/// A buffer the timestamps are copied into for reading on the CPU
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    /// Set while the buffer is waiting for (or holding) a result
+    pending: bool,
+    /// `MAP_DONE` once the buffer can be read, `MAP_FAILED` when mapping it failed
+    map_state: Arc<AtomicU8>,
+}
+
+/// GPU resources, created when the device supports timestamp queries
+struct TimerQueries {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    slots: Vec<ReadbackSlot>,
+    /// Slot this frame's timestamps are copied into, None when all of them are busy
+    current: Option<usize>,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    /// Whether a failed readback was logged already, the first one is enough
+    failure_logged: bool,
+}
+
+/// Measures how long the GPU takes to execute a frame with timestamp queries
+///
+/// Frame encoding on the CPU returns long before the GPU is done, so the CPU frame time
+/// misses the actual drawing cost. The first pass of a frame writes a timestamp when it
+/// starts and the last one when it ends; the difference is read back a few frames later.
+/// Devices without `TIMESTAMP_QUERY` support report no GPU time.
+pub struct GpuTimer {
+    queries: Option<TimerQueries>,
+    /// Most recently read GPU frame time
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// Create a GPU timer, inactive when the device lacks timestamp queries
+    pub fn new(pipeline: &RenderPipeline) -> Self {
+        let device = &pipeline.device;
+        let queries = device.features().contains(wgpu::Features::TIMESTAMP_QUERY).then(|| TimerQueries {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Frame Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Resolve Buffer"),
+                size: TIMESTAMP_BYTES,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            slots: (0..READBACK_SLOTS)
+                .map(|_| ReadbackSlot {
+                    buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Timestamp Readback Buffer"),
+                        size: TIMESTAMP_BYTES,
+                        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }),
+                    pending: false,
+                    map_state: Arc::new(AtomicU8::new(MAP_WAITING)),
+                })
+                .collect(),
+            current: None,
+            period: pipeline.queue.get_timestamp_period(),
+            failure_logged: false,
+        });
+        if queries.is_none() {
+            log::info!("GPU timestamp queries are not supported, only CPU frame times are measured");
+        }
+
+        GpuTimer { queries, last: None }
+    }
+
+    /// Check whether the device supports GPU timing
+    pub fn is_supported(&self) -> bool {
+        self.queries.is_some()
+    }
+
+    /// Get the GPU time of a recent frame, None until one was measured or when unsupported
+    pub fn last_frame_time(&self) -> Option<Duration> {
+        self.last
+    }
+
+    /// Start timing a frame, returning false when no readback buffer is free
+    ///
+    /// Call before encoding the frame's passes.
+    pub fn begin_frame(&mut self) -> bool {
+        let Some(queries) = &mut self.queries else {
+            return false;
+        };
+        queries.current = queries.slots.iter().position(|slot| !slot.pending);
+        queries.current.is_some()
+    }
+
+    /// Get the timestamp writes for a pass; `first` and `last` mark the frame's first and
+    /// last pass, which may be the same one
+    pub fn pass_writes(&self, first: bool, last: bool) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let queries = self.queries.as_ref().filter(|queries| queries.current.is_some())?;
+        if !first && !last {
+            return None;
+        }
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &queries.query_set,
+            beginning_of_pass_write_index: first.then_some(0),
+            end_of_pass_write_index: last.then_some(1),
+        })
+    }
+
+    /// Copy the frame's timestamps for reading, after its last pass was encoded
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(queries) = &mut self.queries else {
+            return;
+        };
+        let Some(current) = queries.current else {
+            return;
+        };
+        encoder.resolve_query_set(&queries.query_set, 0..2, &queries.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&queries.resolve_buffer, 0, &queries.slots[current].buffer, 0, TIMESTAMP_BYTES);
+    }
+
+    /// Request the frame's timestamps once its commands were submitted
+    pub fn end_frame(&mut self) {
+        let Some(queries) = &mut self.queries else {
+            return;
+        };
+        let Some(current) = queries.current.take() else {
+            return;
+        };
+        let slot = &mut queries.slots[current];
+        slot.pending = true;
+        let map_state = Arc::clone(&slot.map_state);
+        slot.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            map_state.store(if result.is_ok() { MAP_DONE } else { MAP_FAILED }, Ordering::Release);
+        });
+    }
+
+    /// Read the timestamps of finished frames without waiting for the GPU
+    ///
+    /// Returns the newest GPU frame time, if one became available.
+    pub fn poll(&mut self, device: &wgpu::Device) -> Option<Duration> {
+        let queries = self.queries.as_mut()?;
+        if !queries.slots.iter().any(|slot| slot.pending) {
+            return None;
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut measured = None;
+        for slot in queries.slots.iter_mut().filter(|slot| slot.pending) {
+            match slot.map_state.swap(MAP_WAITING, Ordering::Acquire) {
+                MAP_DONE => {}
+                MAP_FAILED => {
+                    // The frame goes unmeasured, the buffer is free for another one
+                    if !queries.failure_logged {
+                        log::warn!("Failed to read back GPU timestamps, some frames go unmeasured");
+                        queries.failure_logged = true;
+                    }
+                    slot.pending = false;
+                    continue;
+                }
+                _ => continue,
+            }
+            {
+                let data = slot.buffer.slice(..).get_mapped_range();
+                let begin = u64::from_le_bytes(data[0..8].try_into().unwrap());
+                let end = u64::from_le_bytes(data[8..16].try_into().unwrap());
+                let nanos = end.saturating_sub(begin) as f64 * queries.period as f64;
+                measured = Some(Duration::from_nanos(nanos as u64));
+            }
+            slot.buffer.unmap();
+            slot.pending = false;
+        }
+        if measured.is_some() {
+            self.last = measured;
+        }
+        measured
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+    use crate::config::AppConfig;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_gpu_timer_frames() {
+        let config = AppConfig::default();
+        let size = winit::dpi::PhysicalSize::new(64, 32);
+        let pipeline = require_gpu(RenderPipeline::new_offscreen(size, &config));
+        let mut timer = GpuTimer::new(&pipeline);
+        if !timer.is_supported() {
+            // Without timestamp queries the passes are left untimed
+            assert!(!timer.begin_frame());
+            assert!(timer.pass_writes(true, true).is_none());
+            assert_eq!(timer.poll(&pipeline.device), None);
+            return;
+        }
+
+        // More frames than readback buffers, so busy buffers are skipped and then reused
+        for _ in 0..READBACK_SLOTS + 2 {
+            let timed = timer.begin_frame();
+            let (output, mut encoder) = pipeline.begin_render(config.display.background_color).unwrap();
+            {
+                let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Test Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &output.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: timer.pass_writes(true, true),
+                    occlusion_query_set: None,
+                });
+            }
+            assert!(timed || timer.pass_writes(true, true).is_none());
+            timer.resolve(&mut encoder);
+            pipeline.queue.submit(std::iter::once(encoder.finish()));
+            output.present();
+            timer.end_frame();
+        }
+
+        pipeline.device.poll(wgpu::Maintain::Wait);
+        assert!(timer.poll(&pipeline.device).is_some());
+        assert!(timer.last_frame_time().is_some());
+        assert!(timer.begin_frame());
+
+        // Failed readbacks free their buffers instead of keeping them busy for good
+        let queries = timer.queries.as_mut().unwrap();
+        queries.current = None;
+        for slot in &mut queries.slots {
+            slot.pending = true;
+            slot.map_state.store(MAP_FAILED, Ordering::Release);
+        }
+        assert!(!timer.begin_frame());
+        assert_eq!(timer.poll(&pipeline.device), None);
+        assert!(timer.queries.as_ref().unwrap().failure_logged);
+        assert!(timer.begin_frame());
+    }
+}
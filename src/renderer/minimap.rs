@@ -0,0 +1,338 @@
+// src/renderer/minimap.rs
+
+use crate::config::AppConfig;
+use crate::midi::{Markers, Note, TempoChange, TempoMap};
+use crate::renderer::note_renderer::NoteInstance;
+use crate::renderer::pipeline::{cursor_to_normalized, RenderPipeline};
+use crate::renderer::text_renderer::TextRenderer;
+use wgpu::util::DeviceExt;
+
+/// Number of density bins the song is divided into
+const MINIMAP_BINS: usize = 256;
+/// Color of the tempo change ticks and their BPM labels
+const TEMPO_COLOR: [f32; 4] = [0.3, 0.85, 0.8, 0.8];
+
+/// Renderer for a song overview strip along the right edge of the screen
+pub struct MinimapRenderer {
+    /// Instance buffer for minimap data
+    instance_buffer: Option<wgpu::Buffer>,
+    /// Number of minimap instances
+    instance_count: u32,
+    /// Quads of the strip, rebuilt every frame in the same allocation
+    instances: Vec<NoteInstance>,
+    /// Note density per bin, normalized to 0-1
+    density: Vec<f32>,
+    /// Song duration covered by the minimap (in seconds)
+    duration: f32,
+    /// Times of the section markers (in seconds)
+    marker_times: Vec<f32>,
+    /// Tempo changes after the start of the song
+    tempo_changes: Vec<TempoChange>,
+    /// Whether the minimap is shown
+    visible: bool,
+    /// Left edge of the strip (normalized 0-1)
+    x: f32,
+    /// Width of the strip (normalized 0-1)
+    width: f32,
+    /// Bottom edge of the strip, just above the piano (normalized 0-1)
+    bottom: f32,
+    /// Top edge of the strip (normalized 0-1)
+    top: f32,
+    /// Screen size for coordinate conversion
+    screen_width: f32,
+    screen_height: f32,
+}
+
+impl MinimapRenderer {
+    /// Create a new minimap renderer
+    pub fn new(config: &AppConfig) -> Self {
+        let width = 0.015;
+        MinimapRenderer {
+            instance_buffer: None,
+            instance_count: 0,
+            instances: Vec::new(),
+            density: Vec::new(),
+            duration: 0.0,
+            marker_times: Vec::new(),
+            tempo_changes: Vec::new(),
+            visible: config.display.show_minimap,
+            x: 1.0 - width,
+            width,
+            bottom: config.display.piano_top(),
+            top: 1.0,
+            screen_width: config.display.width as f32,
+            screen_height: config.display.height as f32,
+        }
+    }
+
+    /// Compute the density histogram for a newly loaded song lasting `duration` seconds,
+    /// which can run past the last note for files with a silent tail
+    pub fn set_notes(&mut self, notes: &[Note], duration: f32) {
+        self.duration = duration;
+        self.density = vec![0.0; MINIMAP_BINS];
+
+        if self.duration <= 0.0 {
+            self.density.clear();
+            return;
+        }
+
+        for note in notes {
+            let bin = ((note.start_time / self.duration) * MINIMAP_BINS as f32) as usize;
+            self.density[bin.min(MINIMAP_BINS - 1)] += 1.0;
+        }
+
+        let max = self.density.iter().copied().fold(0.0f32, f32::max);
+        if max > 0.0 {
+            for value in &mut self.density {
+                *value /= max;
+            }
+        }
+    }
+
+    /// Set the section markers of the loaded song, drawn as ticks beside the strip
+    pub fn set_markers(&mut self, markers: &Markers) {
+        self.marker_times = markers.markers().iter().map(|marker| marker.time).collect();
+    }
+
+    /// Set the tempo changes of the loaded song, drawn as ticks across the strip
+    /// The tempo the song starts with is no change and gets no tick
+    pub fn set_tempo_map(&mut self, tempo_map: &TempoMap) {
+        self.tempo_changes = tempo_map.changes().iter().copied().filter(|change| change.time > 0.0).collect();
+    }
+
+    /// Update screen size for coordinate conversion, in physical pixels like the cursor
+    pub fn set_screen_size(&mut self, width: f32, height: f32) {
+        self.screen_width = width;
+        self.screen_height = height;
+    }
+
+    /// Move the bottom edge of the strip, to stay just above the piano
+    pub fn set_bottom(&mut self, bottom: f32) {
+        self.bottom = bottom;
+    }
+
+    /// Toggle visibility
+    pub fn toggle_visibility(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Check if visible
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Handle mouse click and return the song time to seek to if the minimap was clicked
+    pub fn handle_mouse_click(&self, x: f32, y: f32) -> Option<f32> {
+        if !self.visible || self.density.is_empty() {
+            return None;
+        }
+
+        let (norm_x, norm_y) = cursor_to_normalized(x, y, self.screen_width, self.screen_height);
+
+        if norm_x < self.x || norm_y < self.bottom || norm_y > self.top {
+            return None;
+        }
+
+        let fraction = (norm_y - self.bottom) / (self.top - self.bottom);
+        Some(fraction * self.duration)
+    }
+
+    /// Update the minimap buffer with the current playback position
+    pub fn update(&mut self, pipeline: &RenderPipeline, current_time: f32) {
+        if !self.visible || self.density.is_empty() {
+            self.instance_count = 0;
+            return;
+        }
+
+        self.collect_instances(current_time);
+        let instances = &self.instances;
+        self.instance_count = instances.len() as u32;
+
+        // Create or update instance buffer
+        let buffer_size = (self.instance_count as usize * std::mem::size_of::<NoteInstance>()) as u64;
+
+        let needs_new_buffer = match &self.instance_buffer {
+            None => true,
+            Some(buffer) => buffer.size() < buffer_size,
+        };
+
+        if needs_new_buffer {
+            self.instance_buffer = Some(pipeline.device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("Minimap Instance Buffer"),
+                    contents: bytemuck::cast_slice(instances),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                },
+            ));
+        } else if let Some(buffer) = &self.instance_buffer {
+            pipeline.queue.write_buffer(buffer, 0, bytemuck::cast_slice(instances));
+        }
+    }
+
+    /// Rebuild the quads of the strip for the playback position `current_time`
+    fn collect_instances(&mut self, current_time: f32) {
+        // Taken out while filling it, the positions come from the other fields
+        let mut instances = std::mem::take(&mut self.instances);
+        instances.clear();
+        let height = self.top - self.bottom;
+        let bin_height = height / MINIMAP_BINS as f32;
+
+        // Background strip
+        instances.push(NoteInstance {
+            position: [self.x, self.bottom],
+            size: [self.width, height],
+            color: [0.1, 0.1, 0.12, 0.8],
+            ..Default::default()
+        });
+
+        // Density bars grow from the right edge, the song runs bottom to top
+        for (i, &density) in self.density.iter().enumerate() {
+            if density <= 0.0 {
+                continue;
+            }
+            let bar_width = self.width * density;
+            instances.push(NoteInstance {
+                position: [1.0 - bar_width, self.bottom + i as f32 * bin_height],
+                size: [bar_width, bin_height],
+                color: [0.3 + density * 0.5, 0.5, 0.9 - density * 0.4, 0.9],
+                ..Default::default()
+            });
+        }
+
+        // Tempo change ticks stay within the strip
+        for change in &self.tempo_changes {
+            instances.push(NoteInstance {
+                position: [self.x, self.tick_y(change.time)],
+                size: [self.width, 0.0015],
+                color: TEMPO_COLOR,
+                ..Default::default()
+            });
+        }
+
+        // Section marker ticks stick out to the left of the strip
+        for &time in &self.marker_times {
+            let fraction = (time / self.duration).clamp(0.0, 1.0);
+            instances.push(NoteInstance {
+                position: [self.x - self.width * 0.5, self.bottom + fraction * height],
+                size: [self.width * 1.5, 0.002],
+                color: [1.0, 0.6, 0.2, 0.9],
+                ..Default::default()
+            });
+        }
+
+        // Current position marker
+        let progress = (current_time / self.duration).clamp(0.0, 1.0);
+        instances.push(NoteInstance {
+            position: [self.x, self.bottom + progress * height],
+            size: [self.width, 0.003],
+            color: [1.0, 1.0, 1.0, 1.0],
+            ..Default::default()
+        });
+        self.instances = instances;
+    }
+
+    /// Queue the BPM of every tempo change left of the strip
+    /// Labels that would overlap the one below them are left out, the ticks still show
+    pub fn draw_tempo_labels(&self, text: &mut TextRenderer) {
+        if !self.visible || self.density.is_empty() {
+            return;
+        }
+
+        let scale = (text.default_scale() * 0.5).round().max(1.0);
+        let mut last_top = f32::NEG_INFINITY;
+        for change in &self.tempo_changes {
+            let label = format!("{:.0} BPM", change.bpm);
+            let size = text.text_size(&label, scale);
+            let y = self.tick_y(change.time);
+            if y - size[1] * 0.5 < last_top {
+                continue;
+            }
+            last_top = y + size[1] * 0.5;
+            // Clear of the section marker ticks
+            let right = self.x - self.width * 0.75;
+            text.draw_text(&label, [right - size[0], last_top], scale, TEMPO_COLOR);
+        }
+    }
+
+    /// Get the height of the tick for `time` on the strip (normalized 0-1)
+    fn tick_y(&self, time: f32) -> f32 {
+        let fraction = (time / self.duration).clamp(0.0, 1.0);
+        self.bottom + fraction * (self.top - self.bottom)
+    }
+
+    /// Render the minimap
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a RenderPipeline) {
+        if !self.visible || self.instance_count == 0 {
+            return;
+        }
+
+        if let Some(instance_buffer) = &self.instance_buffer {
+            render_pass.set_pipeline(&pipeline.note_pipeline);
+            render_pass.set_bind_group(0, &pipeline.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, pipeline.quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.set_index_buffer(pipeline.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..pipeline.quad_index_count(), 0, 0..self.instance_count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::Marker;
+
+    #[test]
+    fn test_density_bins() {
+        let mut minimap = MinimapRenderer::new(&AppConfig::default());
+        // Three notes in the first bin, one halfway and one at the very end
+        let notes = vec![
+            Note::new(60, 100, 0.0, 1.0, 0),
+            Note::new(62, 100, 0.1, 1.0, 0),
+            Note::new(64, 100, 0.2, 1.0, 0),
+            Note::new(60, 100, 50.0, 1.0, 0),
+            Note::new(60, 100, 100.0, 1.0, 0),
+        ];
+        minimap.set_notes(&notes, 100.0);
+        assert_eq!(minimap.density.len(), MINIMAP_BINS);
+        assert_eq!(minimap.density[0], 1.0);
+        assert!((minimap.density[MINIMAP_BINS / 2] - 1.0 / 3.0).abs() < 1e-6);
+        // A note at the end of the song counts in the last bin
+        assert!((minimap.density[MINIMAP_BINS - 1] - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(minimap.density.iter().filter(|&&density| density > 0.0).count(), 3);
+
+        // Empty bins get no bar: the background, three bars and the position marker
+        minimap.collect_instances(0.0);
+        assert_eq!(minimap.instances.len(), 5);
+        let markers = Markers::new(vec![Marker { time: 25.0, name: String::new() }]);
+        minimap.set_markers(&markers);
+        minimap.collect_instances(10.0);
+        assert_eq!(minimap.instances.len(), 6);
+
+        // A song without length has no strip
+        minimap.set_notes(&notes, 0.0);
+        assert!(minimap.density.is_empty());
+    }
+
+    #[test]
+    fn test_click_seeks() {
+        let config = AppConfig::default();
+        let mut minimap = MinimapRenderer::new(&config);
+        minimap.set_screen_size(1000.0, 1000.0);
+        let bottom = config.display.piano_top();
+        assert_eq!(minimap.handle_mouse_click(995.0, 500.0), None);
+
+        minimap.set_notes(&[Note::new(60, 100, 0.0, 1.0, 0)], 200.0);
+        // The song runs from the bottom of the strip to the top of the window
+        let y_at = |fraction: f32| (1.0 - (bottom + fraction * (1.0 - bottom))) * 1000.0;
+        let seek = minimap.handle_mouse_click(995.0, y_at(0.25)).unwrap();
+        assert!((seek - 50.0).abs() < 1e-3, "{}", seek);
+        assert!(minimap.handle_mouse_click(995.0, 0.0).unwrap() >= 199.99);
+        // Clicks beside the strip or on the piano below it are not seeks
+        assert_eq!(minimap.handle_mouse_click(900.0, y_at(0.25)), None);
+        assert_eq!(minimap.handle_mouse_click(995.0, 999.0), None);
+
+        minimap.toggle_visibility();
+        assert_eq!(minimap.handle_mouse_click(995.0, y_at(0.25)), None);
+    }
+}
@@ -1,11 +1,30 @@
 // src/renderer/mod.rs
 
 pub mod pipeline;
+pub mod ambient;
+pub mod gpu_timer;
+pub mod frame_export;
 pub mod note_renderer;
 pub mod overlay;
 pub mod piano_renderer;
+pub mod minimap;
+pub mod playhead;
+pub mod text_renderer;
+pub mod trails;
+pub mod visualizer;
 
-pub use pipeline::RenderPipeline;
+#[cfg(test)]
+mod test_support;
+
+pub use pipeline::{PipelineError, RenderPipeline, Uniforms};
+pub use ambient::AmbientRenderer;
+pub use gpu_timer::GpuTimer;
+pub use frame_export::FrameExporter;
 pub use note_renderer::NoteRenderer;
 pub use overlay::PerformanceOverlay;
-pub use piano_renderer::PianoRenderer;
\ No newline at end of file
+pub use piano_renderer::PianoRenderer;
+pub use minimap::MinimapRenderer;
+pub use playhead::PlayheadRenderer;
+pub use text_renderer::TextRenderer;
+pub use trails::TrailRenderer;
+pub use visualizer::Visualizer;
\ No newline at end of file
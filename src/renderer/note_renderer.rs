@@ -1,13 +1,17 @@
 // src/renderer/note_renderer.rs
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, HandFocus, NoteBlend, NoteDrawOrder, NotePriority, NoteShape};
+use crate::midi::{steal_voices, ChannelTimeline, Expression, NoteColorMap};
+pub use crate::config::GhostMode;
+use crate::midi::note::VISIBLE_PAST_FRACTION;
 use crate::midi::Note;
-use crate::renderer::pipeline::RenderPipeline;
+use crate::renderer::playhead::PLAYHEAD_POSITION;
+use crate::renderer::pipeline::{RenderPipeline, Vertex, QUAD_INDICES, QUAD_VERTICES};
 use wgpu::util::DeviceExt;
 
 /// Instance data for GPU rendering of notes
 #[repr(C)]
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct NoteInstance {
     /// Position (x, y) in normalized screen coordinates
     pub position: [f32; 2],
@@ -15,13 +19,35 @@ pub struct NoteInstance {
     pub size: [f32; 2],
     /// RGBA color
     pub color: [f32; 4],
+    /// Strength of the beveled 3D shading (0.0 = flat fill)
+    pub shading: f32,
+    /// Width in pixels of the darker outline around the quad (0.0 = none)
+    pub outline: f32,
+    /// Radius in pixels of the anti-aliased rounded bottom corners (0.0 = square)
+    pub corner_radius: f32,
+    /// Shape cut out of the quad, see [`shape_id`] (0 = rectangle)
+    pub shape: u32,
+    /// Brightness envelope along the note: strength (0.0 = flat fill), then the bottom edge
+    /// and height of the whole note in normalized screen coordinates, which stay the same
+    /// for every piece of a note split by pitch bends or lanes
+    pub envelope: [f32; 3],
 }
 
 impl NoteInstance {
+    /// Layout for reading note data once per instance
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        Self::layout(wgpu::VertexStepMode::Instance)
+    }
+
+    /// Layout for reading note data once per vertex, used when instancing is disabled
+    pub fn desc_per_vertex() -> wgpu::VertexBufferLayout<'static> {
+        Self::layout(wgpu::VertexStepMode::Vertex)
+    }
+
+    fn layout(step_mode: wgpu::VertexStepMode) -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<NoteInstance>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
+            step_mode,
             attributes: &[
                 // Position
                 wgpu::VertexAttribute {
@@ -41,21 +67,182 @@ impl NoteInstance {
                     shader_location: 4,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                // Shading
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                // Outline
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                // Corner radius
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                // Shape
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 11]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+                // Envelope
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
 }
 
+/// Get the id of a note shape in `NoteInstance::shape`, matching the constants in note.wgsl
+pub fn shape_id(shape: NoteShape) -> u32 {
+    match shape {
+        NoteShape::Rectangle => 0,
+        NoteShape::Rounded => 1,
+        NoteShape::Capsule => 2,
+        NoteShape::Diamond => 3,
+    }
+}
+
+/// Map a linearly scrolled y position to the eased one, for the default playhead position
+///
+/// The distance to the playhead is eased separately above and below it, relative to the space
+/// on that side, so the playhead and both screen edges are reached at the same time as without
+/// easing and nothing enters or leaves the view earlier. The slope at the playhead is
+/// `1.0 - strength`; further away the notes move faster to make up for it.
+fn eased_y(y: f32, strength: f32) -> f32 {
+    let span = if y >= PLAYHEAD_POSITION { 1.0 - PLAYHEAD_POSITION } else { PLAYHEAD_POSITION };
+    let distance = (y - PLAYHEAD_POSITION) / span;
+    PLAYHEAD_POSITION + distance * (1.0 - strength + strength * distance.abs()) * span
+}
+
+/// Get the note capacity to allocate when `needed` notes no longer fit in `current`
+///
+/// Buffers start out holding `batch` notes and at least double when they grow, so dense
+/// passages only reallocate a few times; they grow past `max` only when more are needed.
+fn grown_capacity(current: u32, needed: u32, batch: u32, max: u32) -> u32 {
+    batch.max(current.saturating_mul(2)).min(max).max(needed).max(1)
+}
+
+/// Per-vertex buffers for drawing notes without instancing
+///
+/// Every note is expanded into four corners, each carrying a copy of the note data,
+/// so the same shader can be used with plain vertex stepping.
+struct ExpandedBuffers {
+    /// Quad corner positions, repeated for every note
+    corners: wgpu::Buffer,
+    /// Note data, repeated for every corner
+    notes: wgpu::Buffer,
+    /// Triangle indices for every note
+    indices: wgpu::Buffer,
+    /// Number of notes the buffers can hold
+    capacity: u32,
+}
+
+impl ExpandedBuffers {
+    fn new(device: &wgpu::Device, capacity: u32) -> Self {
+        let corners: Vec<Vertex> = (0..capacity).flat_map(|_| QUAD_VERTICES.iter().copied()).collect();
+        let indices: Vec<u32> = (0..capacity)
+            .flat_map(|note| QUAD_INDICES.iter().map(move |&i| note * 4 + i as u32))
+            .collect();
+
+        ExpandedBuffers {
+            corners: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Expanded Note Corner Buffer"),
+                contents: bytemuck::cast_slice(&corners),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            notes: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Expanded Note Buffer"),
+                size: (capacity as usize * 4 * std::mem::size_of::<NoteInstance>()) as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Expanded Note Index Buffer"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            capacity,
+        }
+    }
+}
+
+/// Smallest rendered note height in pixels, so very short notes don't vanish
+const MIN_NOTE_HEIGHT_PX: f32 = 2.0;
+/// Smallest rendered note width in pixels
+const MIN_NOTE_WIDTH_PX: f32 = 1.0;
+
+/// Longest time window that can be shown, in seconds
+const MAX_TIME_WINDOW: f32 = 60.0;
+
+/// Color of the lines between track lanes
+const LANE_SEPARATOR_COLOR: [f32; 4] = [0.35, 0.35, 0.4, 0.8];
+/// Color of the playhead line drawn in each track lane
+const LANE_PLAYHEAD_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.25];
+
+/// Alpha that played notes fade down to with `quality.played_note_fade`
+const PLAYED_NOTE_ALPHA: f32 = 0.2;
+
+/// Alpha multiplier for notes whose voice was stolen
+const STOLEN_ALPHA: f32 = 0.3;
+
+/// Number of segments a note is split into while its pitch bend changes
+const BEND_SEGMENTS: usize = 12;
+
+/// Fraction of the view height over which notes grow in at the top and fade out at the bottom
+const EDGE_ANIMATION_BAND: f32 = 0.1;
+
 /// Renderer for MIDI notes with instancing support for Black MIDI
 pub struct NoteRenderer {
-    /// Instance buffer for note data
+    /// Instance buffer for note data, grown as more notes are visible
     instance_buffer: Option<wgpu::Buffer>,
+    /// CPU-side instance data, reused every frame
+    instances: Vec<NoteInstance>,
     /// Current number of visible instances
     instance_count: u32,
-    /// Maximum buffer capacity
+    /// Notes drawn in the last frame, however many instances they took
+    drawn_notes: u32,
+    /// Maximum number of notes drawn per frame
     max_instances: u32,
+    /// Notes the buffers are first allocated for, from `quality.instance_batch_size`
+    batch_size: u32,
     /// Time window for visible notes (in seconds)
     time_window: f32,
+    /// How long the note-on flash takes to fade out (in seconds)
+    flash_duration: f32,
+    /// Number of times a note buffer has been allocated
+    buffer_allocations: u32,
+    /// Whether notes are drawn with instancing (`quality.use_instancing`)
+    instanced: bool,
+    /// Buffers for the non-instanced path, created on first use
+    expanded_buffers: Option<ExpandedBuffers>,
+    /// CPU-side per-vertex note data for the non-instanced path
+    expanded: Vec<NoteInstance>,
+    /// Controller timelines of the loaded file
+    expression: Expression,
+    /// Custom colors of the loaded file, replacing the channel colors of the notes they match
+    color_map: NoteColorMap,
+    /// Screen size in pixels, used to convert `display.note_gap`
+    screen_size: [f32; 2],
+    /// Per-note times the notes are cut off by `quality.voice_limit`, empty when disabled
+    stolen: Vec<Option<f32>>,
+    /// Indices of the notes passing culling, reused every frame
+    candidates: Vec<usize>,
+    /// How the notes are blended, from `display.note_blend`
+    blend: NoteBlend,
+    /// Lane of each track index for `display.track_lanes`, tracks without notes have none
+    lanes: Vec<Option<usize>>,
+    /// Number of tracks with notes, each getting a lane
+    lane_count: usize,
 }
 
 impl NoteRenderer {
@@ -63,9 +250,25 @@ impl NoteRenderer {
     pub fn new(config: &AppConfig) -> Self {
         NoteRenderer {
             instance_buffer: None,
+            instances: Vec::new(),
             instance_count: 0,
+            drawn_notes: 0,
             max_instances: config.quality.max_note_count,
+            batch_size: config.quality.instance_batch_size(),
             time_window: 5.0, // 5 seconds visible at once
+            flash_duration: 0.15,
+            buffer_allocations: 0,
+            instanced: config.quality.use_instancing,
+            expanded_buffers: None,
+            expanded: Vec::new(),
+            expression: Expression::default(),
+            color_map: NoteColorMap::default(),
+            screen_size: [config.display.width as f32, config.display.height as f32],
+            stolen: Vec::new(),
+            candidates: Vec::new(),
+            blend: config.display.note_blend,
+            lanes: Vec::new(),
+            lane_count: 0,
         }
     }
 
@@ -77,62 +280,505 @@ impl NoteRenderer {
         current_time: f32,
         config: &AppConfig,
     ) {
+        self.screen_size = [pipeline.size.width.max(1) as f32, pipeline.size.height.max(1) as f32];
+        self.collect_visible(notes, current_time, config);
+        self.instanced = config.quality.use_instancing;
+        self.blend = config.display.note_blend;
+        if self.instanced {
+            self.write_instances(&pipeline.device, &pipeline.queue);
+        } else {
+            self.write_expanded(&pipeline.device, &pipeline.queue);
+        }
+    }
+
+    /// Collect the visible notes into the CPU-side instance list
+    fn collect_visible(&mut self, notes: &[Note], current_time: f32, config: &AppConfig) {
+        let time_window = self.time_window;
+        let flash_duration = if config.quality.note_flash { self.flash_duration } else { 0.0 };
+        let shading = config.quality.note_shading_strength();
+        let outline = config.display.note_outline_px();
+        let shape = shape_id(config.display.note_shape);
+        let envelope = config.display.note_envelope_strength();
+        let easing = config.display.playhead_easing_strength();
+        let played_fade = config.quality.played_note_fade.max(0.0);
+        let opacity = config.display.note_opacity.clamp(0.0, 1.0);
+        let focus_falloff = config.quality.focus_falloff.clamp(0.0, 1.0);
+        let spawn = config.quality.note_spawn_animation;
+        let despawn = config.quality.note_despawn_fade;
+        let channels = &config.channels;
+        let hide_muted = channels.ghost_mode == GhostMode::Hide;
+        let ghost_alpha = channels.ghost_alpha.clamp(0.0, 1.0);
+        let hands = &config.hands;
+        let unfocused_alpha = hands.unfocused_opacity.clamp(0.0, 1.0);
+        let highlight = hands.highlight.clamp(0.0, 1.0);
+
+        let bends = &self.expression.pitch_bends;
+        let bend_range = if config.quality.pitch_bend && !bends.is_empty() {
+            config.midi.pitch_bend_range
+        } else {
+            0.0
+        };
+        let pressure_enabled = config.quality.aftertouch && self.expression.has_pressure();
+        let volume_enabled = config.quality.channel_volume && self.expression.has_volume();
+        let colors = &config.display.note_colors;
+        let note_width = config.display.note_width_fraction();
+        let (low_key, high_key) = config.display.key_bounds();
+        let key_scale = config.display.key_scale();
+        let gap = config.display.note_gap.max(0.0);
+        let pixel = [1.0 / self.screen_size[0], 1.0 / self.screen_size[1]];
+        let max_instances = self.max_instances as usize;
+
+        // Notes are laid out for the default playhead position, then stretched to the actual
+        // one, so the top of the screen stays the same time ahead with any piano height
+        let playhead = config.display.playhead_position();
+        let stretch = (1.0 - playhead) / (1.0 - PLAYHEAD_POSITION);
+        // Notes are visible up to the future part of the window, which must reach the top of
+        // the screen, `time_window` ahead. The past part must reach the bottom, which shows
+        // the past at the same seconds per pixel and so grows with the playhead.
+        let future = 1.0 - VISIBLE_PAST_FRACTION;
+        let past_factor = future * playhead / (VISIBLE_PAST_FRACTION * (1.0 - playhead));
+        let cull_window = time_window / future * past_factor.max(1.0);
+        // Notes just beyond the screen edges are kept too, so they scroll in instead of popping in
+        let cull_window = cull_window * (1.0 + config.quality.cull_margin());
+
         // Collect visible notes with frustum culling
-        let visible_notes: Vec<NoteInstance> = notes
-            .iter()
-            .filter(|note| {
-                if !config.quality.frustum_culling {
-                    return true;
+        self.candidates.clear();
+        self.candidates.extend((0..notes.len()).filter(|&index| {
+            let note = &notes[index];
+            if hide_muted && channels.is_muted(note.channel) {
+                return false;
+            }
+            if !(low_key..=high_key).contains(&note.pitch) {
+                return false;
+            }
+            !config.quality.frustum_culling || note.is_visible(current_time, cull_window)
+        }));
+        if self.candidates.len() > max_instances {
+            let priority = config.quality.note_priority;
+            Self::select_salient(&mut self.candidates, notes, current_time, max_instances, priority);
+        }
+        Self::sort_draw_order(&mut self.candidates, notes, current_time, config.quality.note_draw_order);
+
+        self.instances.clear();
+        let lanes = config.display.track_lanes && self.lane_count > 1;
+        // Lanes fill the space above the piano
+        let lane_height = (1.0 - config.display.piano_top()) / self.lane_count.max(1) as f32;
+        if lanes {
+            Self::push_lane_guides(&mut self.instances, self.lane_count, lane_height, pixel);
+        }
+        // The guides don't take the slots of notes
+        let guides = self.instances.len();
+        let mut drawn_notes = 0;
+
+        for &index in &self.candidates {
+            if self.instances.len() - guides >= max_instances {
+                break;
+            }
+            let note = &notes[index];
+            let lane_bottom = self.lane_bottom(note.track_index, lane_height);
+
+            let pressure = if pressure_enabled {
+                let time = current_time.clamp(note.start_time, note.end_time());
+                self.expression.pressure_at(note.channel, note.pitch, time)
+            } else {
+                None
+            };
+            let velocity = note.velocity as f32 / 127.0;
+            // Aftertouch swells brighten the note on top of its velocity
+            let mut intensity = pressure.map_or(velocity, |pressure| (velocity + pressure) * 0.5);
+            if volume_enabled {
+                // Fades and swells of the channel dim and brighten its notes
+                let time = current_time.clamp(note.start_time, note.end_time());
+                intensity *= self.expression.volume_at(note.channel, time);
+            }
+            let color = match self.color_map.color_of(note) {
+                // Custom colors dim with softer notes like the channel colors
+                Some([r, g, b, a]) => {
+                    let value = colors.value(intensity);
+                    [r * value, g * value, b * value, a]
                 }
-                note.is_visible(current_time, self.time_window)
-            })
-            .take(self.max_instances as usize)
-            .map(|note| self.note_to_instance(note, current_time))
-            .collect();
+                None => note.get_color_with_params(intensity, colors),
+            };
+            let mut instance = Self::note_to_instance(
+                note,
+                current_time,
+                time_window,
+                flash_duration,
+                shading,
+                color,
+                played_fade,
+            );
+            if easing > 0.0 {
+                Self::ease_scroll(&mut instance, easing);
+            }
+            instance.color[3] *= opacity;
+            instance.outline = outline;
+            instance.shape = shape;
+            if key_scale != 1.0 {
+                instance.position[0] = config.display.key_x(instance.position[0]);
+                instance.size[0] *= key_scale;
+            }
+            if focus_falloff > 0.0 {
+                instance.color[3] *= Self::focus_alpha(note.start_time - current_time, time_window, focus_falloff);
+            }
+            if lanes {
+                // The lane is a scaled down copy of the full screen view
+                instance.position[1] = lane_bottom + instance.position[1] * lane_height;
+                instance.size[1] *= lane_height;
+            } else if (playhead - PLAYHEAD_POSITION).abs() > 1e-6 {
+                instance.position[1] = playhead + (instance.position[1] - PLAYHEAD_POSITION) * stretch;
+                instance.size[1] *= stretch;
+            }
+            if note_width < 1.0 {
+                // Narrow the note within its key slot, leaving a gutter on both sides
+                let key_width = instance.size[0];
+                instance.size[0] = key_width * note_width;
+                instance.position[0] += key_width * (1.0 - note_width) * 0.5;
+            }
+            if gap > 0.0 {
+                Self::apply_gap(&mut instance, gap, pixel);
+            }
+            instance.size[0] = instance.size[0].max(MIN_NOTE_WIDTH_PX * pixel[0]);
+            instance.size[1] = instance.size[1].max(MIN_NOTE_HEIGHT_PX * pixel[1]);
+            if envelope > 0.0 {
+                // Louder notes get a stronger attack
+                let strength = envelope * (0.5 + velocity * 0.5);
+                instance.envelope = [strength, instance.position[1], instance.size[1]];
+            }
+            if spawn || despawn {
+                // Lanes are views of their own, animated at their edges
+                let (bottom, top) = if lanes { (lane_bottom, lane_bottom + lane_height) } else { (0.0, 1.0) };
+                Self::animate_edges(&mut instance, bottom, top, spawn, despawn);
+            }
+            let mut stolen_at = None;
+            if channels.is_muted(note.channel) {
+                Self::ghost(&mut instance, ghost_alpha);
+            } else if hands.is_unfocused(note.pitch) {
+                Self::ghost(&mut instance, unfocused_alpha);
+            } else {
+                stolen_at = self.stolen.get(index).copied().flatten();
+                if hands.focus != HandFocus::Both {
+                    Self::highlight(&mut instance, highlight);
+                }
+            }
+            let first = self.instances.len();
+            if bend_range != 0.0 {
+                // Bends move the note by semitones, which are wider with fewer keys shown
+                Self::push_bent(&mut self.instances, instance, note, bends, bend_range * key_scale);
+            } else {
+                self.instances.push(instance);
+            }
+            if let Some(time) = stolen_at {
+                // The note only goes quiet once its voice is taken
+                let split = instance.position[1] + instance.size[1] * (time - note.start_time) / note.duration;
+                Self::dim_above(&mut self.instances, first, split);
+            }
+            if lanes {
+                Self::clip_to_lane(&mut self.instances, first, lane_bottom, lane_bottom + lane_height);
+            }
+            if self.instances.len() > first {
+                drawn_notes += 1;
+            }
+        }
+        self.instances.truncate(guides + max_instances);
 
-        self.instance_count = visible_notes.len() as u32;
+        self.instance_count = self.instances.len() as u32;
+        self.drawn_notes = drawn_notes;
+    }
+
+    /// Upload the collected instances, allocating the buffer only when its capacity is too small
+    fn write_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let instance_size = std::mem::size_of::<NoteInstance>() as u64;
+        let capacity = self.instance_buffer.as_ref().map_or(0, |buffer| (buffer.size() / instance_size) as u32);
+
+        if self.instance_buffer.is_none() || capacity < self.instance_count {
+            let capacity = grown_capacity(capacity, self.instance_count, self.batch_size, self.max_instances);
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Note Instance Buffer"),
+                size: capacity as u64 * instance_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            self.buffer_allocations += 1;
+        }
 
         if self.instance_count == 0 {
             return;
         }
 
-        // Create or update instance buffer
-        let buffer_size = (self.instance_count as usize * std::mem::size_of::<NoteInstance>()) as u64;
-        
-        // Check if we need to recreate the buffer
-        let needs_new_buffer = match &self.instance_buffer {
-            None => true,
-            Some(buffer) => buffer.size() < buffer_size,
-        };
+        if let Some(buffer) = &self.instance_buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.instances));
+        }
+    }
 
-        if needs_new_buffer {
-            // Create a new buffer (create_buffer_init already sizes the buffer appropriately)
-            self.instance_buffer = Some(pipeline.device.create_buffer_init(
-                &wgpu::util::BufferInitDescriptor {
-                    label: Some("Note Instance Buffer"),
-                    contents: bytemuck::cast_slice(&visible_notes),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                },
-            ));
-        } else if let Some(buffer) = &self.instance_buffer {
-            // Update existing buffer
-            pipeline.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&visible_notes));
+    /// Upload the collected notes as per-vertex data for the non-instanced path
+    fn write_expanded(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let capacity = self.expanded_buffers.as_ref().map_or(0, |buffers| buffers.capacity);
+        if capacity < self.instance_count {
+            let capacity = grown_capacity(capacity, self.instance_count, self.batch_size, self.max_instances);
+            self.expanded_buffers = Some(ExpandedBuffers::new(device, capacity));
+            self.buffer_allocations += 1;
+        }
+
+        self.expanded.clear();
+        self.expanded.extend(self.instances.iter().flat_map(|&instance| [instance; 4]));
+
+        if let Some(buffers) = &self.expanded_buffers {
+            queue.write_buffer(&buffers.notes, 0, bytemuck::cast_slice(&self.expanded));
         }
     }
 
-    /// Convert a Note to NoteInstance for GPU rendering (vertical - top to bottom)
-    fn note_to_instance(&self, note: &Note, current_time: f32) -> NoteInstance {
+    /// Convert a Note with its base `color` to NoteInstance for GPU rendering (vertical - top to bottom)
+    fn note_to_instance(
+        note: &Note,
+        current_time: f32,
+        time_window: f32,
+        flash_duration: f32,
+        shading: f32,
+        mut color: [f32; 4],
+        played_fade: f32,
+    ) -> NoteInstance {
         // For vertical rendering: x is based on pitch, y is based on time
         let x = note.get_x_position_from_pitch();
-        let y = note.get_y_position_from_time(current_time, self.time_window);
+        let y = note.get_y_position_from_time(current_time, time_window);
         let width = note.get_width_from_pitch();
-        let height = note.get_height(self.time_window);
-        let color = note.get_color();
+        let height = note.get_height(time_window);
+
+        // Flash towards white as the note is hit
+        let flash = note.flash_intensity(current_time, flash_duration);
+        if flash > 0.0 {
+            for channel in &mut color[..3] {
+                *channel += (1.0 - *channel) * flash * 0.8;
+            }
+        }
+
+        // Fade notes that have been played so the eye stays on the upcoming ones
+        let played_for = current_time - note.end_time();
+        if played_fade > 0.0 && played_for > 0.0 {
+            let fade = (played_for / played_fade).min(1.0);
+            color[3] *= 1.0 - fade * (1.0 - PLAYED_NOTE_ALPHA);
+        }
 
         NoteInstance {
             position: [x, y],
             size: [width, height],
             color,
+            shading,
+            outline: 0.0,
+            corner_radius: 0.0,
+            shape: 0,
+            envelope: [0.0; 3],
+        }
+    }
+
+    /// Move the edges of a note laid out for the default playhead along the eased scroll
+    ///
+    /// Both edges are eased on their own, so notes shorten as they slow toward the playhead
+    /// and stretch as they speed away from it.
+    fn ease_scroll(instance: &mut NoteInstance, strength: f32) {
+        let bottom = eased_y(instance.position[1], strength);
+        let top = eased_y(instance.position[1] + instance.size[1], strength);
+        instance.position[1] = bottom;
+        instance.size[1] = top - bottom;
+    }
+
+    /// Get the alpha multiplier of a note starting `ahead` seconds from now with the focus effect
+    ///
+    /// Notes at or past the playhead stay opaque; further up they fade linearly, down to
+    /// `1.0 - falloff` at the top of the screen.
+    fn focus_alpha(ahead: f32, time_window: f32, falloff: f32) -> f32 {
+        // The upcoming part of the window spans the 85% of the screen above the playhead
+        let distance = (ahead / (time_window * 0.85)).clamp(0.0, 1.0);
+        1.0 - distance * falloff
+    }
+
+    /// Push a note shifted sideways by pitch bend, split into segments where the bend changes
+    fn push_bent(
+        instances: &mut Vec<NoteInstance>,
+        instance: NoteInstance,
+        note: &Note,
+        bends: &ChannelTimeline,
+        bend_range: f32,
+    ) {
+        let offset_at = |time: f32| bends.value_at(note.channel, time, 0.0) * bend_range / 128.0;
+
+        if !bends.changes_between(note.channel, note.start_time, note.end_time()) {
+            let mut shifted = instance;
+            shifted.position[0] += offset_at(note.start_time);
+            instances.push(shifted);
+            return;
+        }
+
+        // Each segment takes the bend in effect at its start
+        let segment_height = instance.size[1] / BEND_SEGMENTS as f32;
+        let segment_duration = note.duration / BEND_SEGMENTS as f32;
+        for i in 0..BEND_SEGMENTS {
+            let mut segment = instance;
+            segment.position[0] += offset_at(note.start_time + segment_duration * i as f32);
+            segment.position[1] += segment_height * i as f32;
+            segment.size[1] = segment_height;
+            instances.push(segment);
+        }
+    }
+
+    /// Dim the parts of the instances from `first` on that lie above `split`, splitting the
+    /// instance that crosses it
+    fn dim_above(instances: &mut Vec<NoteInstance>, first: usize, split: f32) {
+        for i in first..instances.len() {
+            let instance = &mut instances[i];
+            let top = instance.position[1] + instance.size[1];
+            if instance.position[1] >= split {
+                instance.color[3] *= STOLEN_ALPHA;
+            } else if top > split {
+                let mut dimmed = *instance;
+                instance.size[1] = split - instance.position[1];
+                dimmed.position[1] = split;
+                dimmed.size[1] = top - split;
+                dimmed.color[3] *= STOLEN_ALPHA;
+                instances.push(dimmed);
+            }
+        }
+    }
+
+    /// Get the bottom edge of the lane of `track_index`, lanes run from the first track at the top
+    fn lane_bottom(&self, track_index: u16, lane_height: f32) -> f32 {
+        let lane = self.lanes.get(track_index as usize).copied().flatten().unwrap_or(0);
+        1.0 - (lane + 1) as f32 * lane_height
+    }
+
+    /// Push the separator and playhead lines of the track lanes, drawn below the notes
+    fn push_lane_guides(instances: &mut Vec<NoteInstance>, lane_count: usize, lane_height: f32, pixel: [f32; 2]) {
+        for lane in 0..lane_count {
+            let bottom = 1.0 - (lane + 1) as f32 * lane_height;
+            instances.push(NoteInstance {
+                position: [0.0, bottom + PLAYHEAD_POSITION * lane_height],
+                size: [1.0, pixel[1]],
+                color: LANE_PLAYHEAD_COLOR,
+                ..Default::default()
+            });
+            if lane > 0 {
+                instances.push(NoteInstance {
+                    position: [0.0, bottom + lane_height - pixel[1]],
+                    size: [1.0, pixel[1] * 2.0],
+                    color: LANE_SEPARATOR_COLOR,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    /// Cut the instances from `first` on to the lane between `bottom` and `top`, dropping
+    /// the ones entirely outside it so notes don't spill into the neighboring lanes
+    fn clip_to_lane(instances: &mut Vec<NoteInstance>, first: usize, bottom: f32, top: f32) {
+        let mut kept = first;
+        for i in first..instances.len() {
+            let mut instance = instances[i];
+            let low = instance.position[1].max(bottom);
+            let high = (instance.position[1] + instance.size[1]).min(top);
+            if high <= low {
+                continue;
+            }
+            instance.position[1] = low;
+            instance.size[1] = high - low;
+            instances[kept] = instance;
+            kept += 1;
+        }
+        instances.truncate(kept);
+    }
+
+    /// Grow a note in from its center as its start enters the view at `top` and fade it out as
+    /// its end leaves the view at `bottom`, over `EDGE_ANIMATION_BAND` of the view height
+    ///
+    /// Notes kept beyond the top by `quality.cull_margin` stay hidden until they enter.
+    fn animate_edges(instance: &mut NoteInstance, bottom: f32, top: f32, spawn: bool, despawn: bool) {
+        let band = (top - bottom) * EDGE_ANIMATION_BAND;
+        if spawn {
+            let entered = ((top - instance.position[1]) / band).clamp(0.0, 1.0);
+            let width = instance.size[0];
+            instance.size[0] = width * entered;
+            instance.position[0] += width * (1.0 - entered) * 0.5;
+        }
+        if despawn {
+            let remaining = ((instance.position[1] + instance.size[1] - bottom) / band).clamp(0.0, 1.0);
+            instance.color[3] *= remaining;
+        }
+    }
+
+    /// Shrink a note by `gap` pixels at its end and half of that on each side
+    fn apply_gap(instance: &mut NoteInstance, gap: f32, pixel: [f32; 2]) {
+        let [width, height] = instance.size;
+
+        let inset = (gap * pixel[0]).min((width - pixel[0]).max(0.0));
+        instance.size[0] = width - inset;
+        instance.position[0] += inset * 0.5;
+
+        instance.size[1] = height - gap * pixel[1];
+    }
+
+    /// Keep the `max_instances` most salient candidates, in their original (draw) order
+    fn select_salient(
+        candidates: &mut Vec<usize>,
+        notes: &[Note],
+        current_time: f32,
+        max_instances: usize,
+        priority: NotePriority,
+    ) {
+        if priority == NotePriority::FileOrder || max_instances == 0 {
+            candidates.truncate(max_instances);
+            return;
+        }
+
+        let distance = |note: &Note| Self::playhead_distance(note, current_time);
+        // Ties fall back to file order so the same notes win from frame to frame
+        let rank = |&a: &usize, &b: &usize| {
+            let (note_a, note_b) = (&notes[a], &notes[b]);
+            let by_distance = distance(note_a).total_cmp(&distance(note_b));
+            let by_velocity = note_b.velocity.cmp(&note_a.velocity);
+            match priority {
+                NotePriority::Velocity => by_velocity.then(by_distance),
+                _ => by_distance.then(by_velocity),
+            }
+            .then(a.cmp(&b))
+        };
+
+        candidates.select_nth_unstable_by(max_instances - 1, rank);
+        candidates.truncate(max_instances);
+        candidates.sort_unstable();
+    }
+
+    /// Reorder the candidates so the notes that should be on top come last
+    /// The sort is stable, so notes that rank the same stay in file order
+    fn sort_draw_order(candidates: &mut [usize], notes: &[Note], current_time: f32, order: NoteDrawOrder) {
+        match order {
+            NoteDrawOrder::FileOrder => {}
+            NoteDrawOrder::Velocity => candidates.sort_by_key(|&index| notes[index].velocity),
+            NoteDrawOrder::Playhead => candidates.sort_by(|&a, &b| {
+                let distance = |index: usize| Self::playhead_distance(&notes[index], current_time);
+                distance(b).total_cmp(&distance(a))
+            }),
+        }
+    }
+
+    /// Get the seconds between a note and the playhead, zero while it is sounding
+    fn playhead_distance(note: &Note, current_time: f32) -> f32 {
+        (note.start_time - current_time).max(current_time - note.end_time()).max(0.0)
+    }
+
+    /// Turn an instance into a faint gray ghost note
+    fn ghost(instance: &mut NoteInstance, alpha: f32) {
+        let [r, g, b, a] = instance.color;
+        let gray = r * 0.3 + g * 0.59 + b * 0.11;
+        instance.color = [gray, gray, gray, a * alpha];
+    }
+
+    /// Brighten an instance of the hand in focus towards white by `amount`
+    fn highlight(instance: &mut NoteInstance, amount: f32) {
+        for channel in &mut instance.color[..3] {
+            *channel += (1.0 - *channel) * amount;
         }
     }
 
@@ -142,8 +788,26 @@ impl NoteRenderer {
             return;
         }
 
+        if !self.instanced {
+            if let Some(buffers) = &self.expanded_buffers {
+                render_pass.set_pipeline(match self.blend {
+                    NoteBlend::Alpha => &pipeline.note_pipeline_non_instanced,
+                    NoteBlend::Additive => &pipeline.additive_note_pipeline_non_instanced,
+                });
+                render_pass.set_bind_group(0, &pipeline.uniform_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, buffers.corners.slice(..));
+                render_pass.set_vertex_buffer(1, buffers.notes.slice(..));
+                render_pass.set_index_buffer(buffers.indices.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.instance_count * pipeline.quad_index_count(), 0, 0..1);
+            }
+            return;
+        }
+
         if let Some(instance_buffer) = &self.instance_buffer {
-            render_pass.set_pipeline(&pipeline.note_pipeline);
+            render_pass.set_pipeline(match self.blend {
+                NoteBlend::Alpha => &pipeline.note_pipeline,
+                NoteBlend::Additive => &pipeline.additive_note_pipeline,
+            });
             render_pass.set_bind_group(0, &pipeline.uniform_bind_group, &[]);
             render_pass.set_vertex_buffer(0, pipeline.quad_vertex_buffer.slice(..));
             render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
@@ -152,14 +816,85 @@ impl NoteRenderer {
         }
     }
 
-    /// Get the current visible note count
+    /// Set the maximum number of notes rendered per frame
+    pub fn set_max_instances(&mut self, max_instances: u32) {
+        self.max_instances = max_instances;
+    }
+
+    /// Get the maximum number of notes rendered per frame
+    pub fn max_instances(&self) -> u32 {
+        self.max_instances
+    }
+
+    /// Check whether notes are drawn with instancing
+    pub fn is_instanced(&self) -> bool {
+        self.instanced
+    }
+
+    /// Get the number of notes drawn in the last frame; bend segments and lane guides are not counted
     pub fn visible_count(&self) -> u32 {
-        self.instance_count
+        self.drawn_notes
+    }
+
+    /// Get the GPU memory taken by the note buffers in bytes
+    pub fn buffer_bytes(&self) -> u64 {
+        let instances = self.instance_buffer.as_ref().map_or(0, wgpu::Buffer::size);
+        let expanded = self
+            .expanded_buffers
+            .as_ref()
+            .map_or(0, |buffers| buffers.corners.size() + buffers.notes.size() + buffers.indices.size());
+        instances + expanded
+    }
+
+    /// Get how many times a note buffer has been allocated, growing to fit more notes
+    pub fn buffer_allocations(&self) -> u32 {
+        self.buffer_allocations
+    }
+
+    /// Set the controller timelines of the loaded file, used for pitch bends
+    pub fn set_expression(&mut self, expression: Expression) {
+        self.expression = expression;
+    }
+
+    /// Set the custom note colors of the loaded file, e.g. from [`NoteColorMap::load_for`]
+    pub fn set_color_map(&mut self, color_map: NoteColorMap) {
+        self.color_map = color_map;
+    }
+
+    /// Simulate `quality.voice_limit` voices per channel for the loaded notes, dimming the notes
+    /// a synth would cut off; call again whenever the notes or the limit change
+    pub fn allocate_voices(&mut self, notes: &[Note], config: &AppConfig) {
+        self.stolen = match config.quality.voice_limit {
+            Some(limit) => steal_voices(notes, limit as usize, config.quality.voice_stealing),
+            None => Vec::new(),
+        };
+    }
+
+    /// Give every track with notes its own lane for `display.track_lanes`
+    pub fn assign_lanes(&mut self, notes: &[Note]) {
+        self.lanes.clear();
+        for note in notes {
+            let track = note.track_index as usize;
+            if self.lanes.len() <= track {
+                self.lanes.resize(track + 1, None);
+            }
+            self.lanes[track] = Some(0);
+        }
+        self.lane_count = 0;
+        for lane in self.lanes.iter_mut().flatten() {
+            *lane = self.lane_count;
+            self.lane_count += 1;
+        }
+    }
+
+    /// Get the number of track lanes, one per track with notes
+    pub fn lane_count(&self) -> usize {
+        self.lane_count
     }
 
     /// Set the time window for visibility
     pub fn set_time_window(&mut self, seconds: f32) {
-        self.time_window = seconds.max(1.0);
+        self.time_window = seconds.clamp(1.0, MAX_TIME_WINDOW);
     }
 
     /// Get the time window
@@ -167,3 +902,721 @@ impl NoteRenderer {
         self.time_window
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+    use crate::midi::ColorRule;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_instance_buffer_is_reused() {
+        let size = winit::dpi::PhysicalSize::new(320, 240);
+        let mut config = AppConfig::default();
+        config.quality.max_note_count = 1000;
+        let pipeline = require_gpu(RenderPipeline::new_offscreen(size, &config));
+        let mut renderer = NoteRenderer::new(&config);
+
+        // One note per 100ms, so the visible count changes as time advances
+        let notes: Vec<Note> = (0..200)
+            .map(|i| Note::new(60, 100, i as f32 * 0.1, 0.05 + (i % 7) as f32 * 0.1, 0))
+            .collect();
+
+        let mut counts = Vec::new();
+        for time in [-4.0, -2.0, 0.0, 5.0, 19.0, 30.0] {
+            renderer.update(&pipeline, &notes, time, &config);
+            counts.push(renderer.visible_count());
+        }
+
+        counts.dedup();
+        assert!(counts.len() > 2, "visible counts should vary: {:?}", counts);
+        assert_eq!(renderer.buffer_allocations(), 1);
+        // The batch size is more than the note limit, so the buffer holds just the limit
+        assert_eq!(renderer.buffer_bytes(), 1000 * std::mem::size_of::<NoteInstance>() as u64);
+    }
+
+    #[test]
+    fn test_buffer_growth() {
+        // The first buffer holds a batch, or the limit when that is lower
+        assert_eq!(grown_capacity(0, 0, 10_000, 1_000_000), 10_000);
+        assert_eq!(grown_capacity(0, 50, 10_000, 1000), 1000);
+        assert_eq!(grown_capacity(0, 25_000, 10_000, 1_000_000), 25_000);
+        // Growing at least doubles, up to the limit
+        assert_eq!(grown_capacity(10_000, 10_001, 10_000, 1_000_000), 20_000);
+        assert_eq!(grown_capacity(600_000, 600_001, 10_000, 1_000_000), 1_000_000);
+        // A limit lowered by adaptive quality caps the growth
+        assert_eq!(grown_capacity(1000, 1500, 10_000, 2000), 2000);
+        assert_eq!(grown_capacity(0, 0, 0, 0), 1);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_non_instanced_path_renders() {
+        let size = winit::dpi::PhysicalSize::new(320, 240);
+        let mut config = AppConfig::default();
+        config.quality.use_instancing = false;
+        let pipeline = require_gpu(RenderPipeline::new_offscreen(size, &config));
+        let mut renderer = NoteRenderer::new(&config);
+        let notes: Vec<Note> = (0..100).map(|i| Note::new(i as u8, 100, i as f32 * 0.01, 0.5, 0)).collect();
+
+        renderer.update(&pipeline, &notes, 0.5, &config);
+        assert!(!renderer.is_instanced());
+        assert_eq!(renderer.visible_count(), 100);
+        assert!(renderer.instance_buffer.is_none());
+        assert_eq!(renderer.expanded.len(), 400);
+
+        let (frame, mut encoder) = pipeline.begin_render([0.0, 0.0, 0.0, 1.0]).unwrap();
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Test Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &frame.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            renderer.render(&mut render_pass, &pipeline);
+        }
+        pipeline.queue.submit(std::iter::once(encoder.finish()));
+        frame.present();
+    }
+
+    #[test]
+    fn test_muted_channels_are_ghosted_or_hidden() {
+        let mut config = AppConfig::default();
+        config.channels.muted = vec![1];
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(62, 100, 0.0, 1.0, 1)];
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(renderer.visible_count(), 2);
+        let [r, g, b, a] = renderer.instances[1].color;
+        assert!(r == g && g == b);
+        assert!((a - config.channels.ghost_alpha).abs() < 1e-6);
+        assert_eq!(renderer.instances[0].color[3], 1.0);
+
+        config.channels.ghost_mode = GhostMode::Hide;
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(renderer.visible_count(), 1);
+    }
+
+    #[test]
+    fn test_hand_focus() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(48, 100, 0.0, 1.0, 0), Note::new(72, 100, 0.0, 1.0, 0)];
+        renderer.collect_visible(&notes, 0.5, &config);
+        let plain: Vec<[f32; 4]> = renderer.instances.iter().map(|instance| instance.color).collect();
+
+        // Practicing the right hand grays out and dims the left one, and brightens the right one
+        config.hands.cycle_focus();
+        assert_eq!(config.hands.focus, HandFocus::Right);
+        renderer.collect_visible(&notes, 0.5, &config);
+        let [r, g, b, a] = renderer.instances[0].color;
+        assert!(r == g && g == b);
+        assert!((a - config.hands.unfocused_opacity).abs() < 1e-6);
+        let right = renderer.instances[1].color;
+        assert!(right[..3].iter().zip(&plain[1][..3]).all(|(focused, plain)| focused >= plain));
+        assert!(right[..3] != plain[1][..3]);
+        assert_eq!(right[3], 1.0);
+
+        // The split key belongs to the right hand
+        config.hands.cycle_focus();
+        assert!(config.hands.is_unfocused(60) && !config.hands.is_unfocused(59));
+        config.hands.cycle_focus();
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(renderer.instances.iter().map(|instance| instance.color).collect::<Vec<_>>(), plain);
+    }
+
+    #[test]
+    fn test_solo_overrides_mute() {
+        let mut config = AppConfig::default();
+        config.channels.ghost_mode = GhostMode::Hide;
+        config.channels.muted = vec![1];
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![
+            Note::new(60, 100, 0.0, 1.0, 0),
+            Note::new(62, 100, 0.0, 1.0, 1),
+            Note::new(64, 100, 0.0, 1.0, 2),
+        ];
+        let positions = |renderer: &NoteRenderer| -> Vec<f32> {
+            renderer.instances.iter().map(|instance| instance.position[0]).collect()
+        };
+        renderer.collect_visible(&notes, 0.5, &config);
+        let unmuted = positions(&renderer);
+        assert_eq!(unmuted.len(), 2);
+
+        // Only soloed channels play, even a muted one
+        config.channels.toggle_solo(1);
+        config.channels.toggle_solo(2);
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(renderer.visible_count(), 2);
+        assert!(!positions(&renderer).contains(&unmuted[0]));
+
+        // Taking back every solo brings the mutes back
+        config.channels.toggle_solo(1);
+        config.channels.toggle_solo(2);
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(positions(&renderer), unmuted);
+    }
+
+    #[test]
+    fn test_pitch_bend_splits_notes() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let mut expression = Expression::default();
+        expression.pitch_bends.push(0, 0.5, 1.0);
+        renderer.set_expression(expression);
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(64, 100, 0.0, 1.0, 1)];
+
+        renderer.collect_visible(&notes, 0.0, &config);
+        // The bent note is split into segments, the unbent one stays whole
+        assert_eq!(renderer.instances.len(), BEND_SEGMENTS + 1);
+        assert_eq!(renderer.visible_count(), 2);
+        let first = renderer.instances[0].position[0];
+        let last = renderer.instances[BEND_SEGMENTS - 1].position[0];
+        assert!((last - first - 2.0 / 128.0).abs() < 1e-6);
+
+        config.quality.pitch_bend = false;
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 2);
+    }
+
+    #[test]
+    fn test_aftertouch_modulates_brightness() {
+        let config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let mut expression = Expression::default();
+        expression.channel_pressure.push(0, 0.0, 0.0);
+        expression.channel_pressure.push(0, 1.0, 1.0);
+        renderer.set_expression(expression);
+        let notes = vec![Note::new(60, 64, 0.0, 2.0, 0)];
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        let soft = renderer.instances[0].color;
+        renderer.collect_visible(&notes, 1.5, &config);
+        let hard = renderer.instances[0].color;
+        assert!(hard[0] + hard[1] + hard[2] > soft[0] + soft[1] + soft[2]);
+    }
+
+    #[test]
+    fn test_channel_volume_modulates_brightness() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let mut expression = Expression::default();
+        expression.volume.push(0, 0.0, 1.0);
+        expression.expression.push(0, 1.0, 0.25);
+        renderer.set_expression(expression);
+        let notes = vec![Note::new(60, 127, 0.0, 2.0, 0), Note::new(62, 127, 0.0, 2.0, 1)];
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        let loud = renderer.instances[0].color;
+        renderer.collect_visible(&notes, 1.5, &config);
+        let faded = renderer.instances[0].color;
+        assert!(faded[0] < loud[0]);
+        // Other channels keep their brightness
+        assert_eq!(renderer.instances[1].color, notes[1].get_color_with_params(1.0, &config.display.note_colors));
+
+        config.quality.channel_volume = false;
+        renderer.collect_visible(&notes, 1.5, &config);
+        assert_eq!(renderer.instances[0].color, loud);
+    }
+
+    #[test]
+    fn test_color_map_overrides_channel_color() {
+        let config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        renderer.set_color_map(NoteColorMap::new(vec![ColorRule {
+            track: None,
+            channel: None,
+            pitches: Some((60, 60)),
+            color: [1.0, 0.8, 0.0, 1.0],
+        }]));
+        let notes = vec![Note::new(60, 127, 0.0, 1.0, 0), Note::new(62, 127, 0.0, 1.0, 0)];
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(renderer.instances[0].color, [1.0, 0.8, 0.0, 1.0]);
+        assert_eq!(renderer.instances[1].color, notes[1].get_color_with_params(1.0, &config.display.note_colors));
+
+        // Softer notes get a darker shade of the custom color
+        let soft = vec![Note::new(60, 0, 0.0, 1.0, 0)];
+        renderer.collect_visible(&soft, 0.5, &config);
+        let value = config.display.note_colors.base_value;
+        assert_eq!(renderer.instances[0].color, [value, 0.8 * value, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_note_width_leaves_gutter() {
+        let mut config = AppConfig::default();
+        config.display.note_width = 0.5;
+        config.display.note_gap = 0.0;
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(64, 100, 0.0, 1.0, 0)];
+
+        renderer.collect_visible(&notes, 0.0, &config);
+        let instance = renderer.instances[0];
+        let key_width = notes[0].get_width_from_pitch();
+        assert!((instance.size[0] - key_width * 0.5).abs() < 1e-6);
+        // Still centered on the key slot
+        let center = instance.position[0] + instance.size[0] * 0.5;
+        assert!((center - (notes[0].get_x_position_from_pitch() + key_width * 0.5)).abs() < 1e-6);
+
+        config.display.note_width = 2.0;
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert_eq!(renderer.instances[0].size[0], key_width);
+    }
+
+    #[test]
+    fn test_note_gap() {
+        let mut config = AppConfig::default();
+        config.display.width = 1280;
+        config.display.height = 1000;
+        config.display.note_width = 1.0;
+        config.display.note_gap = 2.0;
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(62, 100, 0.0, 0.001, 0)];
+
+        renderer.collect_visible(&notes, 0.0, &config);
+        let long = renderer.instances[0];
+        assert!((long.size[0] - (10.0 - 2.0) / 1280.0).abs() < 1e-6);
+        assert!((long.size[1] - (notes[0].get_height(5.0) - 2.0 / 1000.0)).abs() < 1e-6);
+        // A note shorter than the gap is still drawn at the minimum height
+        let short = renderer.instances[1];
+        assert_eq!(short.size[1], MIN_NOTE_HEIGHT_PX / 1000.0);
+    }
+
+    #[test]
+    fn test_stolen_voices_are_dimmed() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(60, 100, 0.0, 2.0, 0), Note::new(64, 100, 0.5, 2.0, 0)];
+
+        renderer.allocate_voices(&notes, &config);
+        renderer.collect_visible(&notes, 1.0, &config);
+        assert_eq!(renderer.instances[0].color[3], 1.0);
+
+        config.quality.voice_limit = Some(1);
+        renderer.allocate_voices(&notes, &config);
+        renderer.collect_visible(&notes, 1.0, &config);
+        // The first note keeps full alpha until the second one takes its voice a quarter in
+        assert_eq!(renderer.instances.len(), 3);
+        let (head, tail) = (renderer.instances[0], renderer.instances[1]);
+        assert_eq!(head.color[3], 1.0);
+        assert!((tail.color[3] - STOLEN_ALPHA).abs() < 1e-6);
+        let height = head.size[1] + tail.size[1];
+        assert!((head.size[1] - height * 0.25).abs() < 1e-5);
+        assert!((tail.position[1] - (head.position[1] + head.size[1])).abs() < 1e-6);
+        assert_eq!(renderer.instances[2].color[3], 1.0);
+        assert_eq!(renderer.visible_count(), 2);
+    }
+
+    #[test]
+    fn test_note_priority_over_cap() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![
+            Note::new(40, 127, 3.0, 0.5, 0),
+            Note::new(50, 20, 1.0, 0.5, 0),
+            Note::new(60, 60, 0.9, 0.5, 0),
+            Note::new(70, 100, 1.2, 0.5, 0),
+        ];
+        let positions = |renderer: &NoteRenderer| -> Vec<f32> {
+            renderer.instances.iter().map(|instance| instance.position[0]).collect()
+        };
+        renderer.collect_visible(&notes, 1.1, &config);
+        let all = positions(&renderer);
+        let x = |index: usize| all[index];
+        renderer.set_max_instances(2);
+
+        // The two notes sounding at the playhead win, drawn in file order
+        renderer.collect_visible(&notes, 1.1, &config);
+        assert_eq!(positions(&renderer), vec![x(1), x(2)]);
+
+        config.quality.note_priority = NotePriority::Velocity;
+        renderer.collect_visible(&notes, 1.1, &config);
+        assert_eq!(positions(&renderer), vec![x(0), x(3)]);
+
+        config.quality.note_priority = NotePriority::FileOrder;
+        renderer.collect_visible(&notes, 1.1, &config);
+        assert_eq!(positions(&renderer), vec![x(0), x(1)]);
+    }
+
+    #[test]
+    fn test_note_draw_order() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![
+            Note::new(60, 127, 1.5, 0.5, 0),
+            Note::new(62, 20, 1.0, 0.5, 0),
+            Note::new(64, 60, 1.2, 0.5, 0),
+            Note::new(65, 60, 2.0, 0.5, 0),
+        ];
+        let order = |renderer: &NoteRenderer| -> Vec<f32> {
+            renderer.instances.iter().map(|instance| instance.position[0]).collect()
+        };
+        renderer.collect_visible(&notes, 1.1, &config);
+        let all = order(&renderer);
+        let x = |index: usize| all[index];
+
+        // The loudest note is drawn last, equally loud ones in file order
+        config.quality.note_draw_order = NoteDrawOrder::Velocity;
+        renderer.collect_visible(&notes, 1.1, &config);
+        assert_eq!(order(&renderer), vec![x(1), x(2), x(3), x(0)]);
+
+        // The farthest notes from the playhead are drawn first, the sounding one last
+        config.quality.note_draw_order = NoteDrawOrder::Playhead;
+        renderer.collect_visible(&notes, 1.1, &config);
+        assert_eq!(order(&renderer), vec![x(3), x(0), x(2), x(1)]);
+    }
+
+    #[test]
+    fn test_track_lanes() {
+        let mut config = AppConfig::default();
+        config.display.note_gap = 0.0;
+        let mut renderer = NoteRenderer::new(&config);
+        let mut notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(60, 100, 0.0, 1.0, 0)];
+        notes[1].track_index = 3;
+        renderer.assign_lanes(&notes);
+        assert_eq!(renderer.lane_count(), 2);
+
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 2);
+        let full = renderer.instances[0];
+
+        config.display.track_lanes = true;
+        renderer.collect_visible(&notes, 0.0, &config);
+        // The lane guides are not counted as notes, nor do they count against the note cap
+        assert_eq!(renderer.visible_count(), 2);
+        renderer.set_max_instances(2);
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 2);
+        let notes_drawn = &renderer.instances[renderer.instances.len() - 2..];
+        let lane_height = (1.0 - config.display.piano_top()) / 2.0;
+        // The first track is in the top lane, the other one below it, both scaled to the lane
+        assert!((notes_drawn[0].position[1] - (1.0 - lane_height + full.position[1] * lane_height)).abs() < 1e-5);
+        assert!((notes_drawn[1].position[1] - notes_drawn[0].position[1] + lane_height).abs() < 1e-5);
+        assert!((notes_drawn[0].size[1] - full.size[1] * lane_height).abs() < 1e-5);
+
+        // Notes past the top of their lane are cut off at its edge
+        renderer.collect_visible(&notes, -4.2, &config);
+        let top = renderer.instances.last().unwrap();
+        assert!((top.position[1] + top.size[1] - (1.0 - lane_height)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_played_notes_fade() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(64, 100, 0.5, 1.0, 0)];
+
+        renderer.collect_visible(&notes, 1.25, &config);
+        assert_eq!(renderer.instances[0].color[3], 1.0);
+
+        config.quality.played_note_fade = 0.5;
+        renderer.collect_visible(&notes, 1.25, &config);
+        // Halfway through the fade, the note that is still sounding stays bright
+        let halfway = 1.0 - 0.5 * (1.0 - PLAYED_NOTE_ALPHA);
+        assert!((renderer.instances[0].color[3] - halfway).abs() < 1e-6);
+        assert_eq!(renderer.instances[1].color[3], 1.0);
+
+        renderer.collect_visible(&notes, 1.6, &config);
+        assert!((renderer.instances[0].color[3] - PLAYED_NOTE_ALPHA).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_note_opacity() {
+        let mut config = AppConfig::default();
+        config.display.note_opacity = 0.6;
+        config.channels.muted = vec![1];
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(62, 100, 0.0, 1.0, 1)];
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert!((renderer.instances[0].color[3] - 0.6).abs() < 1e-6);
+        // Ghost notes get fainter still
+        assert!((renderer.instances[1].color[3] - 0.6 * config.channels.ghost_alpha).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_focus_falloff() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let upcoming = renderer.time_window * 0.85;
+        let notes = vec![
+            Note::new(60, 100, 0.0, 1.0, 0),
+            Note::new(62, 100, upcoming * 0.5, 1.0, 0),
+        ];
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert!(renderer.instances.iter().all(|instance| instance.color[3] == 1.0));
+
+        config.quality.focus_falloff = 0.8;
+        renderer.collect_visible(&notes, 0.0, &config);
+        // The note at the playhead stays opaque, the one halfway up is faded halfway
+        assert_eq!(renderer.instances[0].color[3], 1.0);
+        assert!((renderer.instances[1].color[3] - 0.6).abs() < 1e-5);
+        assert!((NoteRenderer::focus_alpha(upcoming * 2.0, renderer.time_window, 0.8) - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_piano_height_moves_playhead() {
+        let mut config = AppConfig::default();
+        config.display.note_gap = 0.0;
+        let mut renderer = NoteRenderer::new(&config);
+        let window = renderer.time_window;
+        // One note hitting the playhead now, one further up
+        let notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(62, 100, window * 0.5, 1.0, 0)];
+
+        renderer.collect_visible(&notes, 0.0, &config);
+        let default_height = renderer.instances[0].size[1];
+        let default_ahead = renderer.instances[1].position[1] - PLAYHEAD_POSITION;
+        assert!((renderer.instances[0].position[1] - PLAYHEAD_POSITION).abs() < 1e-6);
+
+        for show_piano in [false, true] {
+            config.display.show_piano = show_piano;
+            config.display.piano_height = 0.3;
+            let playhead = config.display.playhead_position();
+            renderer.collect_visible(&notes, 0.0, &config);
+            assert!((renderer.instances[0].position[1] - playhead).abs() < 1e-6);
+            // The note area stretches between the playhead and the top of the screen
+            let stretch = (1.0 - playhead) / (1.0 - PLAYHEAD_POSITION);
+            assert!((renderer.instances[1].position[1] - (playhead + default_ahead * stretch)).abs() < 1e-5);
+            assert!((renderer.instances[0].size[1] - default_height * stretch).abs() < 1e-6);
+        }
+
+        // A note far in the past is still drawn across the taller piano
+        let past = vec![Note::new(60, 100, -window * 0.3, 0.1, 0)];
+        renderer.collect_visible(&past, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 1);
+        // But not once it has left the bottom of the screen
+        let gone = vec![Note::new(60, 100, -window * 0.8, 0.1, 0)];
+        renderer.collect_visible(&gone, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 0);
+
+        // Without the piano the playhead is low, and notes still reach the top of the screen
+        config.display.show_piano = false;
+        config.quality.cull_margin = 0.0;
+        let top = vec![Note::new(60, 100, window * 0.95, 0.01, 0)];
+        renderer.collect_visible(&top, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 1);
+    }
+
+    #[test]
+    fn test_note_longer_than_window() {
+        let config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        renderer.set_time_window(5.0);
+        // A 30 second drone, six times the window, among short loud notes
+        let drone = Note::new(48, 40, 10.0, 30.0, 0);
+        let mut notes = vec![drone];
+        notes.extend((0..40).map(|i| Note::new(72, 127, i as f32 + 0.5, 0.2, 1)));
+        notes.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        let drone_slot = drone.get_x_position_from_pitch()..drone.get_x_position_from_pitch() + 1.0 / 128.0;
+
+        let mut time = 0.0;
+        while time < 45.0 {
+            // A tight cap still keeps the drone while it sounds, it is on the playhead
+            let sounding = (10.0..40.0).contains(&time);
+            renderer.set_max_instances(if sounding { 3 } else { 100 });
+            renderer.collect_visible(&notes, time, &config);
+            let found = renderer.instances.iter().find(|instance| drone_slot.contains(&instance.position[0]));
+            // The screen spans the window above the playhead and the matching stretch below it,
+            // plus the culling margin
+            let cull_window = 5.0 / 0.85 * (1.0 + config.quality.cull_margin());
+            assert_eq!(found.is_some(), drone.is_visible(time, cull_window), "at {}s", time);
+            if let (true, Some(instance)) = (sounding, found) {
+                // The quad reaches from below the playhead to its end, off the top of the screen
+                // until the last window of the note
+                assert!(instance.position[1] <= PLAYHEAD_POSITION, "at {}s", time);
+                if drone.end_time() > time + 5.0 {
+                    assert!(instance.position[1] + instance.size[1] >= 1.0, "at {}s", time);
+                }
+            }
+            time += 0.5;
+        }
+    }
+
+    #[test]
+    fn test_note_shape() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let mut notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(64, 100, 0.0, 1.0, 0)];
+        notes[1].track_index = 1;
+        renderer.assign_lanes(&notes);
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert!(renderer.instances.iter().all(|instance| instance.shape == shape_id(NoteShape::Rectangle)));
+
+        config.display.note_shape = NoteShape::Diamond;
+        config.display.track_lanes = true;
+        renderer.collect_visible(&notes, 0.5, &config);
+        let (guides, drawn) = renderer.instances.split_at(renderer.instances.len() - 2);
+        assert!(drawn.iter().all(|instance| instance.shape == shape_id(NoteShape::Diamond)));
+        // The lane guides stay plain rectangles
+        assert!(guides.iter().all(|instance| instance.shape == 0));
+    }
+
+    #[test]
+    fn test_note_outline() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let mut notes = vec![Note::new(60, 100, 0.0, 1.0, 0), Note::new(64, 100, 0.0, 1.0, 0)];
+        notes[1].track_index = 1;
+        renderer.assign_lanes(&notes);
+
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert!(renderer.instances.iter().all(|instance| instance.outline == 0.0));
+
+        config.display.note_outline_width = 1.5;
+        config.display.track_lanes = true;
+        renderer.collect_visible(&notes, 0.5, &config);
+        let (guides, drawn) = renderer.instances.split_at(renderer.instances.len() - 2);
+        assert!(drawn.iter().all(|instance| instance.outline == 1.5));
+        // Only notes are outlined, not the lane guides
+        assert!(guides.iter().all(|instance| instance.outline == 0.0));
+
+        // Wide outlines are limited
+        config.display.note_outline_width = 10.0;
+        renderer.collect_visible(&notes, 0.5, &config);
+        assert_eq!(renderer.instances.last().unwrap().outline, 4.0);
+    }
+
+    #[test]
+    fn test_cull_margin() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        renderer.set_time_window(5.0);
+        // Starts just above the top of the screen
+        let notes = vec![Note::new(60, 100, 5.2, 1.0, 0)];
+
+        config.quality.cull_margin = 0.0;
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 0);
+
+        // Kept with a margin, ready to scroll in
+        config.quality.cull_margin = 0.1;
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert_eq!(renderer.visible_count(), 1);
+        assert!(renderer.instances[0].position[1] > 1.0);
+    }
+
+    #[test]
+    fn test_key_range() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let notes = vec![Note::new(21, 100, 0.0, 1.0, 0), Note::new(108, 100, 0.0, 1.0, 0), Note::new(10, 100, 0.0, 1.0, 0)];
+
+        // An 88-key piano fills the window
+        config.display.key_range = [21, 108];
+        config.display.note_width = 1.0;
+        config.display.note_gap = 0.0;
+        renderer.collect_visible(&notes, 0.5, &config);
+        // The key below the range is left out
+        assert_eq!(renderer.visible_count(), 2);
+        let (lowest, highest) = (renderer.instances[0], renderer.instances[1]);
+        assert!(lowest.position[0].abs() < 1e-6);
+        assert!((lowest.size[0] - 1.0 / 88.0).abs() < 1e-6);
+        assert!((highest.position[0] + highest.size[0] - 1.0).abs() < 1e-6);
+
+        // A reversed range shows the same keys
+        config.display.key_range = [108, 21];
+        assert_eq!(config.display.key_bounds(), (21, 108));
+    }
+
+    #[test]
+    fn test_edge_animations() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        renderer.set_time_window(5.0);
+        let notes = vec![
+            // Leaving past the bottom, entering halfway through the band at the top, and in between
+            Note::new(60, 100, 0.0, 0.2, 0),
+            Note::new(62, 100, 1.0, 1.0, 0),
+            Note::new(64, 100, 1.0 + 5.0 * 0.8 / 0.85, 1.0, 0),
+        ];
+        let time = 1.0;
+        renderer.collect_visible(&notes, time, &config);
+        let plain = renderer.instances.clone();
+
+        config.quality.note_spawn_animation = true;
+        config.quality.note_despawn_fade = true;
+        renderer.collect_visible(&notes, time, &config);
+        let animated = &renderer.instances;
+        // The notes on screen are untouched
+        assert_eq!(animated[1].size, plain[1].size);
+        assert_eq!(animated[1].color, plain[1].color);
+        // The entering note grows in from its center
+        assert!((animated[2].size[0] - plain[2].size[0] * 0.5).abs() < 1e-4);
+        let center = |instance: &NoteInstance| instance.position[0] + instance.size[0] * 0.5;
+        assert!((center(&animated[2]) - center(&plain[2])).abs() < 1e-6);
+        // The leaving note fades out towards the bottom edge
+        assert!(animated[0].color[3] < plain[0].color[3] * 0.5);
+        assert_eq!(animated[0].size, plain[0].size);
+    }
+
+    #[test]
+    fn test_note_envelope() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let mut expression = Expression::default();
+        expression.pitch_bends.push(0, 0.5, 1.0);
+        renderer.set_expression(expression);
+        let notes = vec![Note::new(60, 127, 0.0, 1.0, 0), Note::new(64, 0, 0.0, 1.0, 1)];
+
+        renderer.collect_visible(&notes, 0.0, &config);
+        assert!(renderer.instances.iter().all(|instance| instance.envelope == [0.0; 3]));
+
+        config.display.note_envelope = 1.0;
+        renderer.collect_visible(&notes, 0.0, &config);
+        let (segments, unbent) = renderer.instances.split_at(BEND_SEGMENTS);
+        // Louder notes get a stronger attack
+        assert_eq!(unbent[0].envelope[0], 0.5);
+        assert_eq!(segments[0].envelope[0], 1.0);
+        // Every segment of the bent note spans the envelope of the whole note
+        let whole = segments[0].envelope;
+        assert!(segments.iter().all(|segment| segment.envelope == whole));
+        assert_eq!(whole[1], segments[0].position[1]);
+        let top = segments[BEND_SEGMENTS - 1].position[1] + segments[BEND_SEGMENTS - 1].size[1];
+        assert!((whole[1] + whole[2] - top).abs() < 1e-5);
+
+        config.display.note_envelope = f32::NAN;
+        assert_eq!(config.display.note_envelope_strength(), 0.0);
+    }
+
+    #[test]
+    fn test_playhead_easing() {
+        let mut config = AppConfig::default();
+        let mut renderer = NoteRenderer::new(&config);
+        let window = renderer.time_window;
+        let notes = vec![
+            Note::new(60, 100, 1.0, 0.5, 0),
+            Note::new(62, 100, 1.0 + window * 0.5, 0.5, 0),
+            Note::new(64, 100, 1.0 + window, 0.5, 0),
+        ];
+        renderer.collect_visible(&notes, 1.0, &config);
+        let linear = renderer.instances.clone();
+
+        config.display.playhead_easing = 0.5;
+        renderer.collect_visible(&notes, 1.0, &config);
+        let eased = &renderer.instances;
+        // Notes still reach the playhead when they sound, and the top of the screen is unchanged
+        assert!((eased[0].position[1] - PLAYHEAD_POSITION).abs() < 1e-6);
+        assert!((eased[2].position[1] - linear[2].position[1]).abs() < 1e-5);
+        // In between they are closer to the playhead, slowing down as they approach it
+        let expected = PLAYHEAD_POSITION + 0.5 * (1.0 - 0.5 * 0.5) * (1.0 - PLAYHEAD_POSITION);
+        assert!((eased[1].position[1] - expected).abs() < 1e-5);
+        assert!(eased[1].position[1] < linear[1].position[1]);
+        // Played notes linger below the playhead, then leave the bottom edge at the same time
+        assert!(eased_y(0.0, 0.5).abs() < 1e-6);
+        assert!(eased_y(0.1, 0.5) > 0.1 && eased_y(0.05, 0.5) > 0.05);
+
+        config.display.playhead_easing = 5.0;
+        assert_eq!(config.display.playhead_easing_strength(), 0.9);
+    }
+}
@@ -1,16 +1,11 @@
 // src/renderer/overlay.rs
 
 use crate::config::AppConfig;
+use crate::midi::{MidiPlayer, ParseStats};
 use crate::performance::PerformanceMonitor;
+use std::time::{Duration, Instant};
 
-/// Position for the performance overlay
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum OverlayPosition {
-    TopLeft,
-    TopRight,
-    BottomLeft,
-    BottomRight,
-}
+pub use crate::config::OverlayPosition;
 
 /// Performance overlay that displays FPS and other stats
 pub struct PerformanceOverlay {
@@ -20,6 +15,26 @@ pub struct PerformanceOverlay {
     position: OverlayPosition,
     /// Cached display text lines
     lines: Vec<String>,
+    /// Statistics from the most recently loaded MIDI file
+    parse_stats: Option<ParseStats>,
+    /// Active FPS cap, None when uncapped
+    fps_cap: Option<u32>,
+    /// GPU memory of the note buffers in bytes and how often they were allocated
+    buffer_memory: (u64, u32),
+    /// Whether notes outside the time window are skipped, shown so it isn't left off by accident
+    frustum_culling: bool,
+    /// Playback position text, empty when no song is loaded
+    time_text: String,
+    /// Minimum time between window title updates
+    title_interval: Duration,
+    /// Minimum time between refreshes of the statistics while playing
+    refresh_interval: Duration,
+    /// When the statistics were last refreshed
+    last_refresh: Option<Instant>,
+    /// Window title set most recently
+    last_title: String,
+    /// When the window title was last set
+    last_title_update: Option<Instant>,
 }
 
 impl PerformanceOverlay {
@@ -27,8 +42,18 @@ impl PerformanceOverlay {
     pub fn new(config: &AppConfig) -> Self {
         PerformanceOverlay {
             visible: config.performance.enable_performance_overlay,
-            position: OverlayPosition::TopLeft,
+            position: config.performance.overlay_position,
             lines: Vec::new(),
+            parse_stats: None,
+            fps_cap: None,
+            buffer_memory: (0, 0),
+            frustum_culling: config.quality.frustum_culling,
+            time_text: String::new(),
+            title_interval: Duration::from_secs_f32(config.performance.title_update_interval.max(0.0)),
+            refresh_interval: Duration::from_secs_f32(config.performance.overlay_update_interval.max(0.0)),
+            last_refresh: None,
+            last_title: String::new(),
+            last_title_update: None,
         }
     }
 
@@ -57,24 +82,91 @@ impl PerformanceOverlay {
         self.position
     }
 
+    /// Set the parse statistics of the currently loaded file
+    pub fn set_parse_stats(&mut self, stats: Option<ParseStats>) {
+        self.parse_stats = stats;
+    }
+
+    /// Get the parse statistics of the currently loaded file
+    pub fn parse_stats(&self) -> Option<&ParseStats> {
+        self.parse_stats.as_ref()
+    }
+
+    /// Set the GPU memory of the note buffers and how often they were allocated, shown from
+    /// the next refresh on
+    pub fn set_buffer_memory(&mut self, bytes: u64, allocations: u32) {
+        self.buffer_memory = (bytes, allocations);
+    }
+
+    /// Set whether frustum culling is on, shown from the next refresh on
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled;
+    }
+
     /// Update the overlay with current performance data
+    /// Refresh the statistics shown, at most every `performance.overlay_update_interval` while
+    /// playing so the numbers can be read; paused frames may be the last for a while and
+    /// always refresh
     pub fn update(
         &mut self,
         monitor: &PerformanceMonitor,
         note_count: u32,
-        playback_speed: f32,
-        is_playing: bool,
+        player: &MidiPlayer,
+        fps_cap: Option<u32>,
+        instanced: bool,
+        now: Instant,
     ) {
+        if let Some(last) = self.last_refresh {
+            if player.is_playing() && now.duration_since(last) < self.refresh_interval {
+                return;
+            }
+        }
+        self.last_refresh = Some(now);
+
         self.lines.clear();
+        self.fps_cap = fps_cap;
+        self.time_text.clear();
+        if player.get_duration() > 0.0 {
+            self.time_text = player.get_time_text();
+        }
         
-        let fps = monitor.get_fps();
         let render_time = monitor.get_render_time();
         
-        self.lines.push(format!("FPS: {:.1}", fps));
+        // The smoothed rate reads steadily, the one-second count is what benchmarks compare
+        self.lines.push(format!("FPS: {:.1} (1s: {:.0})", monitor.get_smoothed_fps(), monitor.get_fps()));
+        self.lines.push(format!("FPS Cap: {}", self.fps_cap_label()));
         self.lines.push(format!("Frame Time: {:.2}ms", render_time.as_secs_f32() * 1000.0));
+        if let Some(gpu_time) = monitor.get_gpu_time() {
+            self.lines.push(format!("GPU: {:.2}ms", gpu_time.as_secs_f32() * 1000.0));
+        }
         self.lines.push(format!("Notes: {}", note_count));
-        self.lines.push(format!("Speed: {:.1}x", playback_speed));
-        self.lines.push(format!("Status: {}", if is_playing { "Playing" } else { "Paused" }));
+        self.lines.push(format!("Rendering: {}", if instanced { "Instanced" } else { "Non-instanced" }));
+        self.lines.push(format!("Culling: {}", if self.frustum_culling { "On" } else { "Off (F9)" }));
+        let (buffer_bytes, allocations) = self.buffer_memory;
+        if buffer_bytes > 0 {
+            self.lines.push(format!(
+                "Buffers: {:.1} MB ({} allocations)",
+                buffer_bytes as f64 / (1024.0 * 1024.0),
+                allocations
+            ));
+        }
+        self.lines.push(format!("Speed: {:.1}x", player.get_playback_speed()));
+        self.lines.push(format!("Status: {}", if player.is_playing() { "Playing" } else { "Paused" }));
+        if !self.time_text.is_empty() {
+            self.lines.push(format!("Time: {}", self.time_text));
+        }
+
+        if let Some(stats) = &self.parse_stats {
+            self.lines.push(format!("File: {}", stats));
+        }
+    }
+
+    /// Get the active FPS cap as display text
+    fn fps_cap_label(&self) -> String {
+        match self.fps_cap {
+            Some(cap) => cap.to_string(),
+            None => "Uncapped".to_string(),
+        }
     }
 
     /// Get the lines to display
@@ -85,25 +177,150 @@ impl PerformanceOverlay {
     /// Render the overlay text to the window title (simple fallback)
     /// In a full implementation, this would render text using a texture atlas
     pub fn get_title_text(&self, monitor: &PerformanceMonitor, note_count: u32) -> String {
-        if !self.visible {
-            return "MIDI-RS Visualizer".to_string();
+        let mut title = if !self.visible {
+            "MIDI-RS Visualizer".to_string()
+        } else {
+            format!(
+                "MIDI-RS | FPS: {:.0} (cap: {}) | Notes: {} | Frame: {:.2}ms",
+                monitor.get_smoothed_fps(),
+                self.fps_cap_label(),
+                note_count,
+                monitor.get_render_time().as_secs_f32() * 1000.0
+            )
+        };
+
+        if !self.time_text.is_empty() {
+            title.push_str(" | ");
+            title.push_str(&self.time_text);
         }
-        
-        format!(
-            "MIDI-RS | FPS: {:.0} | Notes: {} | Frame: {:.2}ms",
-            monitor.get_fps(),
-            note_count,
-            monitor.get_render_time().as_secs_f32() * 1000.0
-        )
+        title
+    }
+
+    /// Get a new window title if one is due
+    /// Setting the title every frame makes the title bar flicker on some platforms, so this
+    /// returns None until the update interval has passed and whenever the text is unchanged
+    pub fn title_update(&mut self, monitor: &PerformanceMonitor, note_count: u32, now: Instant) -> Option<String> {
+        if let Some(last) = self.last_title_update {
+            if now.duration_since(last) < self.title_interval {
+                return None;
+            }
+        }
+        self.flush_title(monitor, note_count, now)
+    }
+
+    /// Get the window title if its text changed, ignoring the update interval
+    pub fn flush_title(&mut self, monitor: &PerformanceMonitor, note_count: u32, now: Instant) -> Option<String> {
+        self.last_title_update = Some(now);
+
+        let title = self.get_title_text(monitor, note_count);
+        if title == self.last_title {
+            return None;
+        }
+        self.last_title.clone_from(&title);
+        Some(title)
     }
 }
 
 impl Default for PerformanceOverlay {
     fn default() -> Self {
-        PerformanceOverlay {
-            visible: false,
-            position: OverlayPosition::TopLeft,
-            lines: Vec::new(),
-        }
+        Self::new(&AppConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_state_round_trips_through_config() {
+        let mut config = AppConfig::default();
+        config.performance.enable_performance_overlay = true;
+        config.performance.overlay_position = OverlayPosition::BottomRight;
+
+        let json = serde_json::to_string(&config).unwrap();
+        let loaded: AppConfig = serde_json::from_str(&json).unwrap();
+
+        let overlay = PerformanceOverlay::new(&loaded);
+        assert!(overlay.is_visible());
+        assert_eq!(overlay.position(), OverlayPosition::BottomRight);
+    }
+
+    #[test]
+    fn test_title_updates_are_throttled() {
+        let mut config = AppConfig::default();
+        config.performance.title_update_interval = 0.25;
+        let mut overlay = PerformanceOverlay::new(&config);
+        let monitor = PerformanceMonitor::new();
+        let start = Instant::now();
+
+        assert_eq!(overlay.title_update(&monitor, 0, start).as_deref(), Some("MIDI-RS Visualizer"));
+        // Unchanged text is not set again
+        assert_eq!(overlay.title_update(&monitor, 0, start + Duration::from_secs(1)), None);
+
+        overlay.set_visible(true);
+        // Changed text waits for the interval
+        assert_eq!(overlay.title_update(&monitor, 0, start + Duration::from_millis(1100)), None);
+        assert!(overlay.title_update(&monitor, 0, start + Duration::from_millis(1300)).is_some());
+
+        // Flushing ignores the interval but still skips unchanged text
+        overlay.set_visible(false);
+        let flushed = overlay.flush_title(&monitor, 0, start + Duration::from_millis(1310));
+        assert_eq!(flushed.as_deref(), Some("MIDI-RS Visualizer"));
+        assert_eq!(overlay.flush_title(&monitor, 0, start + Duration::from_millis(1320)), None);
+    }
+
+    #[test]
+    fn test_gpu_time_line() {
+        let mut overlay = PerformanceOverlay::default();
+        let mut monitor = PerformanceMonitor::new();
+        let player = MidiPlayer::new();
+        let has_gpu_line = |overlay: &PerformanceOverlay| overlay.get_lines().iter().any(|line| line.starts_with("GPU:"));
+
+        // Without timestamp queries only the CPU frame time is shown
+        let now = Instant::now();
+        overlay.update(&monitor, 0, &player, None, true, now);
+        assert!(!has_gpu_line(&overlay));
+
+        monitor.gpu_frame_measured(Duration::from_micros(2500));
+        overlay.update(&monitor, 0, &player, None, true, now);
+        assert!(overlay.get_lines().iter().any(|line| line == "GPU: 2.50ms"));
+        assert!(has_gpu_line(&overlay));
+
+        // The note buffer memory shows once buffers are allocated
+        assert!(!overlay.get_lines().iter().any(|line| line.starts_with("Buffers:")));
+        overlay.set_buffer_memory(3 * 1024 * 1024 / 2, 2);
+        overlay.update(&monitor, 0, &player, None, true, now);
+        assert!(overlay.get_lines().iter().any(|line| line == "Buffers: 1.5 MB (2 allocations)"));
+
+        assert!(overlay.get_lines().iter().any(|line| line == "Culling: On"));
+        overlay.set_frustum_culling(false);
+        overlay.update(&monitor, 0, &player, None, true, now);
+        assert!(overlay.get_lines().iter().any(|line| line == "Culling: Off (F9)"));
+    }
+
+    #[test]
+    fn test_refresh_interval() {
+        let mut config = AppConfig::default();
+        config.performance.overlay_update_interval = 0.5;
+        let mut overlay = PerformanceOverlay::new(&config);
+        let monitor = PerformanceMonitor::new();
+        let mut player = MidiPlayer::new();
+        let notes_line =
+            |overlay: &PerformanceOverlay| overlay.get_lines().iter().find(|line| line.starts_with("Notes:")).cloned();
+        let start = Instant::now();
+
+        player.play();
+        overlay.update(&monitor, 10, &player, None, true, start);
+        assert_eq!(notes_line(&overlay).as_deref(), Some("Notes: 10"));
+        // While playing the numbers hold still until the interval has passed
+        overlay.update(&monitor, 20, &player, None, true, start + Duration::from_millis(300));
+        assert_eq!(notes_line(&overlay).as_deref(), Some("Notes: 10"));
+        overlay.update(&monitor, 30, &player, None, true, start + Duration::from_millis(500));
+        assert_eq!(notes_line(&overlay).as_deref(), Some("Notes: 30"));
+
+        // Paused frames always refresh
+        player.pause();
+        overlay.update(&monitor, 40, &player, None, true, start + Duration::from_millis(510));
+        assert_eq!(notes_line(&overlay).as_deref(), Some("Notes: 40"));
     }
 }
@@ -1,8 +1,9 @@
 // src/renderer/piano_renderer.rs
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, DisplayConfig};
 use crate::renderer::pipeline::RenderPipeline;
 use crate::renderer::note_renderer::NoteInstance;
+use crate::renderer::text_renderer::TextRenderer;
 use wgpu::util::DeviceExt;
 
 /// Renderer for the piano keyboard at the bottom of the screen
@@ -13,18 +14,69 @@ pub struct PianoRenderer {
     instance_count: u32,
     /// Keys currently being played (for lighting up)
     active_keys: [bool; 128],
-    /// Height of the piano area (normalized 0-1)
+    /// Keys played right in practice mode, shown in green
+    correct_keys: [bool; 128],
+    /// Keys played wrong in practice mode, shown in red
+    wrong_keys: [bool; 128],
+    /// Height of the piano area (normalized 0-1), 0 while the piano is hidden
     piano_height: f32,
+    /// Draw guide lines above every C across the falling-note area
+    octave_lines: bool,
 }
 
+/// MIDI pitch of middle C, marked in a different color on the keyboard
+const MIDDLE_C: u8 = 60;
+/// Color of the octave labels on the C keys
+const OCTAVE_LABEL_COLOR: [f32; 4] = [0.3, 0.3, 0.35, 1.0];
+/// Color of keys played right in practice mode
+const CORRECT_KEY_COLOR: [f32; 4] = [0.35, 0.85, 0.4, 1.0];
+/// Color of keys played wrong in practice mode
+const WRONG_KEY_COLOR: [f32; 4] = [0.9, 0.3, 0.3, 1.0];
+
 impl PianoRenderer {
     /// Create a new piano renderer
-    pub fn new(_config: &AppConfig) -> Self {
+    pub fn new(config: &AppConfig) -> Self {
         PianoRenderer {
             instance_buffer: None,
             instance_count: 0,
             active_keys: [false; 128],
-            piano_height: 0.12, // 12% of screen height
+            correct_keys: [false; 128],
+            wrong_keys: [false; 128],
+            piano_height: config.display.piano_top(),
+            octave_lines: config.display.show_octave_lines,
+        }
+    }
+
+    /// Toggle the octave guide lines and labels
+    pub fn toggle_octave_lines(&mut self) {
+        self.octave_lines = !self.octave_lines;
+    }
+
+    /// Check if the octave guide lines are shown
+    pub fn octave_lines(&self) -> bool {
+        self.octave_lines
+    }
+
+    /// Queue the octave labels of the C keys, e.g. "C4" for middle C, above their markers
+    ///
+    /// Labels are drawn with the octave guide lines, between the marker and the black keys;
+    /// a label wider than two keys or taller than that space is left out.
+    pub fn draw_octave_labels(&self, text: &mut TextRenderer, config: &AppConfig) {
+        if !self.octave_lines || self.piano_height <= 0.0 {
+            return;
+        }
+        let display = &config.display;
+        let key_width = display.key_scale() / 128.0;
+        let scale = (text.default_scale() * 0.5).round().max(1.0);
+        for pitch in (0..128u8).step_by(12) {
+            let label = format!("C{}", pitch as i32 / 12 - 1);
+            let [width, height] = text.text_size(&label, scale);
+            if width > key_width * 2.0 || height > self.piano_height * 0.2 {
+                continue;
+            }
+            // Centered on the key, spilling onto the white keys next to it
+            let x = display.key_x(pitch as f32 / 128.0) + (key_width - width) * 0.5;
+            text.draw_text(&label, [x, self.piano_height * 0.15 + height], scale, OCTAVE_LABEL_COLOR);
         }
     }
 
@@ -46,40 +98,140 @@ impl PianoRenderer {
         }
     }
 
+    /// Set the keys played right and wrong in practice mode, drawn over the active keys
+    pub fn set_key_feedback(&mut self, correct: &[u8], wrong: &[u8]) {
+        self.correct_keys = [false; 128];
+        self.wrong_keys = [false; 128];
+        for &pitch in correct.iter().filter(|&&pitch| pitch < 128) {
+            self.correct_keys[pitch as usize] = true;
+        }
+        for &pitch in wrong.iter().filter(|&&pitch| pitch < 128) {
+            self.wrong_keys[pitch as usize] = true;
+        }
+    }
+
+    /// Get the practice feedback color of a key, if it was played
+    fn feedback_color(&self, pitch: u8) -> Option<[f32; 4]> {
+        if self.wrong_keys[pitch as usize] {
+            Some(WRONG_KEY_COLOR)
+        } else if self.correct_keys[pitch as usize] {
+            Some(CORRECT_KEY_COLOR)
+        } else {
+            None
+        }
+    }
+
     /// Update the piano keyboard buffer
-    pub fn update(&mut self, pipeline: &RenderPipeline, active_pitches: &[u8]) {
+    pub fn update(&mut self, pipeline: &RenderPipeline, active_pitches: &[u8], config: &AppConfig) {
         self.set_active_keys(active_pitches);
+        self.piano_height = config.display.piano_top();
 
         // 128 MIDI notes total: 75 white keys + 53 black keys
         const WHITE_KEY_COUNT: usize = 75;
         const BLACK_KEY_COUNT: usize = 53;
-        let mut instances: Vec<NoteInstance> = Vec::with_capacity(WHITE_KEY_COUNT + BLACK_KEY_COUNT);
-        let key_width = 1.0 / 128.0;
+        const C_KEY_COUNT: usize = 11;
+        let mut instances: Vec<NoteInstance> =
+            Vec::with_capacity(WHITE_KEY_COUNT + BLACK_KEY_COUNT + 2 * C_KEY_COUNT);
+        let display = &config.display;
+        let key_width = display.key_scale() / 128.0;
+
+        // Faint one pixel guide lines at the left edge of every C, behind the falling notes
+        if self.octave_lines {
+            let line_width = 1.0 / pipeline.size.width.max(1) as f32;
+            for pitch in (0..128u8).step_by(12) {
+                instances.push(NoteInstance {
+                    position: [display.key_x(pitch as f32 / 128.0), self.piano_height],
+                    size: [line_width, 1.0 - self.piano_height],
+                    color: [1.0, 1.0, 1.0, 0.08],
+                    ..Default::default()
+                });
+            }
+        }
+
+        // The keys are left out while the piano is hidden, the guide lines stay
+        if self.piano_height > 0.0 {
+            let shading = config.quality.note_shading_strength();
+            self.push_keys(&mut instances, key_width, display, shading);
+        }
+
+        self.instance_count = instances.len() as u32;
+
+        if self.instance_count == 0 {
+            return;
+        }
+
+        // Create or update instance buffer
+        let buffer_size = (self.instance_count as usize * std::mem::size_of::<NoteInstance>()) as u64;
+
+        let needs_new_buffer = match &self.instance_buffer {
+            None => true,
+            Some(buffer) => buffer.size() < buffer_size,
+        };
+
+        if needs_new_buffer {
+            self.instance_buffer = Some(pipeline.device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("Piano Instance Buffer"),
+                    contents: bytemuck::cast_slice(&instances),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                },
+            ));
+        } else if let Some(buffer) = &self.instance_buffer {
+            pipeline.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+        }
+    }
+
+    /// Queue the white and black keys, lit up where notes are playing
+    /// `shading` gives the keys the bevel of the notes: a highlight along the top and left edge
+    fn push_keys(&self, instances: &mut Vec<NoteInstance>, key_width: f32, display: &DisplayConfig, shading: f32) {
+        let corner_radius = display.key_corner_px();
 
         // Draw keys at their MIDI pitch positions
         // Each MIDI pitch maps directly to a horizontal position
-        // This creates a linear mapping where all 128 keys fill the screen width
+        // This creates a linear mapping where the shown key range fills the screen width
+        // Keys outside it land off-screen
         
         // First draw white keys (they go behind black keys)
         for pitch in 0..128u8 {
             if Self::is_black_key(pitch) {
                 continue;
             }
-            let x = pitch as f32 / 128.0;
+            let x = display.key_x(pitch as f32 / 128.0);
             let y = 0.0;
             let height = self.piano_height;
 
-            // Color: white key, or lit up if active
-            let color = if self.active_keys[pitch as usize] {
+            // Color: white key, or lit up if active or played in practice mode
+            let color = if let Some(color) = self.feedback_color(pitch) {
+                color
+            } else if self.active_keys[pitch as usize] {
                 [0.6, 0.8, 1.0, 1.0] // Light blue when active
             } else {
                 [0.95, 0.95, 0.95, 1.0] // Off-white
             };
 
+            // White keys get a softer bevel, the shadow side would gray them out
             instances.push(NoteInstance {
                 position: [x, y],
                 size: [key_width, height],
                 color,
+                shading: shading * 0.5,
+                corner_radius,
+                ..Default::default()
+            });
+        }
+
+        // Mark every C near the bottom of its key, with middle C highlighted
+        for pitch in (0..128u8).step_by(12) {
+            let color = if pitch == MIDDLE_C {
+                [0.9, 0.5, 0.2, 1.0] // Orange for middle C
+            } else {
+                [0.6, 0.6, 0.65, 1.0] // Gray
+            };
+            instances.push(NoteInstance {
+                position: [display.key_x(pitch as f32 / 128.0) + key_width * 0.25, self.piano_height * 0.05],
+                size: [key_width * 0.5, self.piano_height * 0.08],
+                color,
+                ..Default::default()
             });
         }
 
@@ -88,12 +240,14 @@ impl PianoRenderer {
             if !Self::is_black_key(pitch) {
                 continue;
             }
-            let x = pitch as f32 / 128.0;
+            let x = display.key_x(pitch as f32 / 128.0);
             let y = self.piano_height * 0.35; // Black keys start 35% up the piano area
             let height = self.piano_height * 0.65; // Black keys are 65% of piano height
 
-            // Color: dark gray/black, or lit up if active
-            let color = if self.active_keys[pitch as usize] {
+            // Color: dark gray/black, or lit up if active or played in practice mode
+            let color = if let Some(color) = self.feedback_color(pitch) {
+                color
+            } else if self.active_keys[pitch as usize] {
                 [0.4, 0.6, 0.9, 1.0] // Light blue when active
             } else {
                 [0.1, 0.1, 0.1, 1.0] // Dark gray/black
@@ -103,34 +257,11 @@ impl PianoRenderer {
                 position: [x, y],
                 size: [key_width * 0.7, height],
                 color,
+                shading,
+                corner_radius: corner_radius * 0.7,
+                ..Default::default()
             });
         }
-
-        self.instance_count = instances.len() as u32;
-
-        if self.instance_count == 0 {
-            return;
-        }
-
-        // Create or update instance buffer
-        let buffer_size = (self.instance_count as usize * std::mem::size_of::<NoteInstance>()) as u64;
-
-        let needs_new_buffer = match &self.instance_buffer {
-            None => true,
-            Some(buffer) => buffer.size() < buffer_size,
-        };
-
-        if needs_new_buffer {
-            self.instance_buffer = Some(pipeline.device.create_buffer_init(
-                &wgpu::util::BufferInitDescriptor {
-                    label: Some("Piano Instance Buffer"),
-                    contents: bytemuck::cast_slice(&instances),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                },
-            ));
-        } else if let Some(buffer) = &self.instance_buffer {
-            pipeline.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
-        }
     }
 
     /// Render the piano keyboard
@@ -1,6 +1,7 @@
 // src/renderer/pipeline.rs
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, GpuBackend, NoteBlend, PowerPreference, PresentMode};
+use crate::renderer::note_renderer::NoteInstance;
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use winit::window::Window;
@@ -35,27 +36,230 @@ impl Vertex {
 }
 
 /// Unit quad vertices (0,0 to 1,1)
-const QUAD_VERTICES: &[Vertex] = &[
+pub(crate) const QUAD_VERTICES: &[Vertex] = &[
     Vertex { position: [0.0, 0.0], tex_coords: [0.0, 0.0] },
     Vertex { position: [1.0, 0.0], tex_coords: [1.0, 0.0] },
     Vertex { position: [1.0, 1.0], tex_coords: [1.0, 1.0] },
     Vertex { position: [0.0, 1.0], tex_coords: [0.0, 1.0] },
 ];
 
-const QUAD_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+pub(crate) const QUAD_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+
+/// Convert a cursor position to normalized screen coordinates
+///
+/// Everything drawn with the note pipeline (notes, piano keys, buttons, minimap) is
+/// positioned in normalized screen space: (0, 0) is the bottom-left corner and (1, 1)
+/// the top-right, with y pointing up. Cursor positions from winit are physical pixels
+/// measured from the top-left corner, so y is flipped here. `screen_width` and
+/// `screen_height` must be the physical window size.
+pub fn cursor_to_normalized(x: f32, y: f32, screen_width: f32, screen_height: f32) -> (f32, f32) {
+    (x / screen_width, 1.0 - y / screen_height)
+}
+
+/// Pick the wgpu present mode for the requested setting, falling back to vsync when unsupported
+fn select_present_mode(requested: PresentMode, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    let mode = match requested {
+        // The Auto modes are resolved by wgpu and always valid
+        PresentMode::AutoVsync => return wgpu::PresentMode::AutoVsync,
+        PresentMode::AutoNoVsync => return wgpu::PresentMode::AutoNoVsync,
+        PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+    };
+
+    if supported.contains(&mode) {
+        mode
+    } else {
+        log::warn!(
+            "Present mode {:?} is not supported by this surface (supported: {:?}), falling back to AutoVsync",
+            mode, supported
+        );
+        wgpu::PresentMode::AutoVsync
+    }
+}
+
+/// Pick the surface format to render into
+///
+/// Configured and computed colors are sRGB values, as picked in a color editor. Writing them
+/// to a non-sRGB format stores them unchanged; an sRGB format would treat them as linear and
+/// encode them again, showing every color lighter than set. Only the 8-bit formats store them
+/// as they are: float formats such as `Rgba16Float` are linear (scRGB) and 10-bit ones may be
+/// HDR, both shifting the brightness as well.
+fn select_surface_format(supported: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+    use wgpu::TextureFormat;
+    let plain = supported
+        .iter()
+        .copied()
+        .find(|format| matches!(format, TextureFormat::Bgra8Unorm | TextureFormat::Rgba8Unorm));
+    match plain {
+        Some(format) => format,
+        None => {
+            log::warn!(
+                "No 8-bit non-sRGB surface format available (supported: {:?}), colors may look different than configured",
+                supported
+            );
+            supported[0]
+        }
+    }
+}
+
+/// Pick how the window compositor blends the surface with what is behind it
+///
+/// A transparent window needs the compositor to take the alpha channel into account. The
+/// colors drawn are premultiplied by their alpha, see [`RenderPipeline::clear_color`].
+fn select_alpha_mode(transparent: bool, supported: &[wgpu::CompositeAlphaMode]) -> wgpu::CompositeAlphaMode {
+    use wgpu::CompositeAlphaMode;
+    if transparent {
+        let mode = [CompositeAlphaMode::PreMultiplied, CompositeAlphaMode::Inherit]
+            .into_iter()
+            .find(|mode| supported.contains(mode));
+        match mode {
+            Some(mode) => return mode,
+            None => log::warn!(
+                "Transparent windows are not supported by this GPU (alpha modes: {:?}), the background stays opaque",
+                supported
+            ),
+        }
+    }
+    supported[0]
+}
+
+/// Convert a background color to a clear color, premultiplying it by its alpha if asked to
+fn background_clear_color(background: [f32; 4], premultiply: bool) -> wgpu::Color {
+    let [mut r, mut g, mut b, a] = background.map(|channel| channel.clamp(0.0, 1.0) as f64);
+    if premultiply {
+        r *= a;
+        g *= a;
+        b *= a;
+    }
+    wgpu::Color { r, g, b, a }
+}
+
+/// Get the wgpu backends to create the instance with
+fn wgpu_backends(backend: GpuBackend) -> wgpu::Backends {
+    match backend {
+        GpuBackend::Auto => wgpu::Backends::all(),
+        GpuBackend::Vulkan => wgpu::Backends::VULKAN,
+        GpuBackend::Dx12 => wgpu::Backends::DX12,
+        GpuBackend::Metal => wgpu::Backends::METAL,
+        GpuBackend::Gl => wgpu::Backends::GL,
+    }
+}
+
+fn wgpu_power_preference(preference: PowerPreference) -> wgpu::PowerPreference {
+    match preference {
+        PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+    }
+}
+
+/// Error creating the render pipeline
+#[derive(Debug)]
+pub enum PipelineError {
+    /// The window surface could not be created
+    Surface(wgpu::CreateSurfaceError),
+    /// No adapter supports the requested backend; lists the adapters found on any backend
+    NoAdapter { backend: GpuBackend, available: Vec<String> },
+    /// The adapter refused to create a device
+    Device(wgpu::RequestDeviceError),
+    /// Another window can't be drawn with this pipeline's device
+    SharedDevice(&'static str),
+}
+
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineError::Surface(e) => write!(f, "Failed to create window surface: {}", e),
+            PipelineError::NoAdapter { backend, available } => {
+                write!(f, "No GPU adapter found for backend {:?}", backend)?;
+                if available.is_empty() {
+                    write!(f, " (no adapters found on any backend)")
+                } else {
+                    write!(f, " (available: {})", available.join(", "))
+                }
+            }
+            PipelineError::Device(e) => write!(f, "Failed to create GPU device: {}", e),
+            PipelineError::SharedDevice(reason) => write!(f, "Failed to share the GPU device: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Describe every adapter on any backend, for error messages
+fn available_adapters() -> Vec<String> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .iter()
+        .map(|adapter| {
+            let info = adapter.get_info();
+            format!("{} ({:?})", info.name, info.backend)
+        })
+        .collect()
+}
+
+/// Texture format used for offscreen rendering, non-sRGB like the window surface
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Where the pipeline presents its frames
+pub enum RenderTarget {
+    /// A window surface
+    Surface(wgpu::Surface<'static>),
+    /// An offscreen texture, for tests, benchmarks and export
+    Offscreen(wgpu::Texture),
+    /// Views provided by the application embedding the visualizer, see
+    /// [`RenderPipeline::from_device`]
+    External,
+}
+
+/// A frame being rendered, acquired with [`RenderPipeline::begin_render`]
+pub struct RenderFrame {
+    /// View of the frame's color target
+    pub view: wgpu::TextureView,
+    /// Surface texture to present, None for offscreen frames
+    surface_texture: Option<wgpu::SurfaceTexture>,
+}
+
+impl RenderFrame {
+    /// Present the frame to the window (no-op for offscreen frames)
+    pub fn present(self) {
+        if let Some(surface_texture) = self.surface_texture {
+            surface_texture.present();
+        }
+    }
+}
 
 /// Main rendering pipeline for the MIDI visualizer
 pub struct RenderPipeline {
-    pub surface: wgpu::Surface<'static>,
-    pub device: wgpu::Device,
-    pub queue: wgpu::Queue,
+    pub target: RenderTarget,
+    /// Shared with the embedding application for external targets
+    pub device: Arc<wgpu::Device>,
+    pub queue: Arc<wgpu::Queue>,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
     pub note_pipeline: wgpu::RenderPipeline,
+    /// Pipeline taking per-vertex note data, for drawing notes without instancing
+    pub note_pipeline_non_instanced: wgpu::RenderPipeline,
+    /// Falling note pipelines for `NoteBlend::Additive`, instanced and per-vertex
+    pub additive_note_pipeline: wgpu::RenderPipeline,
+    pub additive_note_pipeline_non_instanced: wgpu::RenderPipeline,
     pub quad_vertex_buffer: wgpu::Buffer,
     pub quad_index_buffer: wgpu::Buffer,
     pub uniform_buffer: wgpu::Buffer,
     pub uniform_bind_group: wgpu::BindGroup,
+    /// Layout of `uniform_bind_group`, for other pipelines reading the uniforms
+    pub uniform_bind_group_layout: wgpu::BindGroupLayout,
+    /// Instance and adapter of window pipelines, for more windows on the same device
+    surface_context: Option<Arc<SurfaceContext>>,
+}
+
+/// What creating and configuring a window surface on an existing device needs
+struct SurfaceContext {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
 }
 
 /// Uniform data passed to shaders
@@ -64,68 +268,295 @@ pub struct RenderPipeline {
 pub struct Uniforms {
     pub screen_size: [f32; 2],
     pub playhead_position: f32,
+    /// Playback time in seconds; while the scene is idle, the time the ambient animation has run
     pub time: f32,
 }
 
+/// Get the blend state drawing notes with `blend`
+pub fn note_blend_state(blend: NoteBlend) -> wgpu::BlendState {
+    match blend {
+        NoteBlend::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+        // Colors add up, weighted by alpha so faded notes still fade
+        NoteBlend::Additive => wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::OVER,
+        },
+    }
+}
+
+/// Create a note render pipeline reading note data through `note_layout`
+fn create_note_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    note_layout: wgpu::VertexBufferLayout<'static>,
+    blend: wgpu::BlendState,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc(), note_layout],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+/// Pick the device limits to request from the ones the adapter supports
+///
+/// The defaults suit desktop GPUs; older integrated GPUs and some GL drivers only reach the
+/// downlevel limits. Buffers and textures may be as large as the adapter allows, so dense
+/// files and large windows fit.
+fn select_limits(supported: &wgpu::Limits) -> wgpu::Limits {
+    let tiers = [
+        ("default", wgpu::Limits::default()),
+        ("downlevel", wgpu::Limits::downlevel_defaults()),
+        ("WebGL2", wgpu::Limits::downlevel_webgl2_defaults()),
+    ];
+    let limits = match tiers.into_iter().find(|(_, limits)| limits.check_limits(supported)) {
+        Some((name, limits)) => {
+            log::debug!("Requesting the {} device limits", name);
+            limits
+        }
+        None => {
+            log::warn!("The adapter is below the WebGL2 limits, requesting its own limits");
+            supported.clone()
+        }
+    };
+    wgpu::Limits {
+        max_buffer_size: supported.max_buffer_size,
+        ..limits
+    }
+    .using_resolution(supported.clone())
+}
+
+/// Request the device and queue used by the pipeline
+async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    let info = adapter.get_info();
+    log::info!("Using adapter: {} ({:?}, {:?})", info.name, info.backend, info.device_type);
+
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                // Timestamp queries measure GPU frame times where available
+                required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                required_limits: select_limits(&adapter.limits()),
+                label: Some("MIDI-RS Device"),
+            },
+            None,
+        )
+        .await
+}
+
+/// Configure a window surface for the adapter's formats and modes and the display settings
+fn configure_surface(
+    surface: &wgpu::Surface,
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    size: winit::dpi::PhysicalSize<u32>,
+    app_config: &AppConfig,
+) -> wgpu::SurfaceConfiguration {
+    let surface_caps = surface.get_capabilities(adapter);
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: select_surface_format(&surface_caps.formats),
+        width: size.width,
+        height: size.height,
+        present_mode: select_present_mode(app_config.display.present_mode, &surface_caps.present_modes),
+        alpha_mode: select_alpha_mode(app_config.display.transparent_window, &surface_caps.alpha_modes),
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
+    surface.configure(device, &config);
+    config
+}
+
+/// Create the texture offscreen pipelines render into
+fn create_offscreen_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: OFFSCREEN_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
 impl RenderPipeline {
     /// Create a new render pipeline
-    pub async fn new(window: Arc<Window>, _app_config: &AppConfig) -> Self {
+    ///
+    /// Uses the backend and power preference from `display.backend` and
+    /// `display.power_preference`, failing when no adapter supports the requested backend.
+    pub async fn new(window: Arc<Window>, app_config: &AppConfig) -> Result<Self, PipelineError> {
         let size = window.inner_size();
+        let backend = app_config.display.backend;
 
         // Create the instance
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
+            backends: wgpu_backends(backend),
             ..Default::default()
         });
 
         // Create the surface
-        let surface = instance.create_surface(window).unwrap();
+        let surface = instance.create_surface(window).map_err(PipelineError::Surface)?;
 
         // Request an adapter
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+                power_preference: wgpu_power_preference(app_config.display.power_preference),
                 compatible_surface: Some(&surface),
                 force_fallback_adapter: false,
             })
             .await
-            .unwrap();
-
-        log::info!("Using adapter: {:?}", adapter.get_info().name);
+            .ok_or_else(|| PipelineError::NoAdapter {
+                backend,
+                available: available_adapters(),
+            })?;
 
         // Request a device
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    label: Some("MIDI-RS Device"),
-                },
-                None,
-            )
-            .await
-            .unwrap();
+        let (device, queue) = request_device(&adapter).await.map_err(PipelineError::Device)?;
+
+        let config = configure_surface(&surface, &adapter, &device, size, app_config);
+        let mut pipeline = Self::from_parts(RenderTarget::Surface(surface), Arc::new(device), Arc::new(queue), config);
+        pipeline.surface_context = Some(Arc::new(SurfaceContext { instance, adapter }));
+        Ok(pipeline)
+    }
 
-        // Configure the surface
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+    /// Create a pipeline for another window, drawing with this window pipeline's device and queue
+    ///
+    /// Sharing the device saves a second set of GPU resources and lets both windows use
+    /// buffers created on either. Fails for offscreen and external pipelines.
+    pub fn for_window(&self, window: Arc<Window>, app_config: &AppConfig) -> Result<Self, PipelineError> {
+        let context = self
+            .surface_context
+            .as_ref()
+            .ok_or(PipelineError::SharedDevice("the pipeline doesn't draw into a window"))?;
+        let size = window.inner_size();
+        let surface = context.instance.create_surface(window).map_err(PipelineError::Surface)?;
+        if !context.adapter.is_surface_supported(&surface) {
+            return Err(PipelineError::SharedDevice("the adapter can't present to the window"));
+        }
+
+        let config = configure_surface(&surface, &context.adapter, &self.device, size, app_config);
+        let mut pipeline = Self::from_parts(
+            RenderTarget::Surface(surface),
+            Arc::clone(&self.device),
+            Arc::clone(&self.queue),
+            config,
+        );
+        pipeline.surface_context = Some(Arc::clone(context));
+        Ok(pipeline)
+    }
+
+    /// Create a render pipeline that draws into an offscreen texture instead of a window
+    ///
+    /// Returns None when no adapter or device is available (e.g. on a machine without a GPU).
+    pub async fn new_offscreen(size: winit::dpi::PhysicalSize<u32>, app_config: &AppConfig) -> Option<Self> {
+        let size = winit::dpi::PhysicalSize::new(size.width.max(1), size.height.max(1));
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu_backends(app_config.display.backend),
+            ..Default::default()
+        });
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu_power_preference(app_config.display.power_preference),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await?;
+
+        let (device, queue) = request_device(&adapter).await.ok()?;
 
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: OFFSCREEN_FORMAT,
             width: size.width,
             height: size.height,
             present_mode: wgpu::PresentMode::AutoVsync,
-            alpha_mode: surface_caps.alpha_modes[0],
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
-        surface.configure(&device, &config);
+        let texture = create_offscreen_texture(&device, size.width, size.height);
+
+        Some(Self::from_parts(RenderTarget::Offscreen(texture), Arc::new(device), Arc::new(queue), config))
+    }
+
+    /// Create a render pipeline on the device of another wgpu application, for embedding
+    /// the visualizer in it
+    ///
+    /// The pipeline has no frames of its own: draw into the application's texture views
+    /// with [`Visualizer::render`](crate::renderer::Visualizer::render) or by beginning
+    /// passes on them directly. `format` is the format of those views and `(width, height)`
+    /// their size in pixels; call [`RenderPipeline::resize`] when it changes.
+    pub fn from_device(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        format: wgpu::TextureFormat,
+        (width, height): (u32, u32),
+    ) -> Self {
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        Self::from_parts(RenderTarget::External, device, queue, config)
+    }
+
+    /// Build the shaders, pipeline and shared buffers for a configured target
+    fn from_parts(
+        target: RenderTarget,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let size = winit::dpi::PhysicalSize::new(config.width, config.height);
 
         // Create shader module
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -177,44 +608,43 @@ impl RenderPipeline {
             push_constant_ranges: &[],
         });
 
-        // Create the render pipeline
-        let note_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Note Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[
-                    Vertex::desc(),
-                    crate::renderer::note_renderer::NoteInstance::desc(),
-                ],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        // Create the render pipelines: instanced, and the per-vertex fallback for `use_instancing = false`
+        let note_pipeline = create_note_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            config.format,
+            NoteInstance::desc(),
+            note_blend_state(NoteBlend::Alpha),
+            "Note Render Pipeline",
+        );
+        let note_pipeline_non_instanced = create_note_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            config.format,
+            NoteInstance::desc_per_vertex(),
+            note_blend_state(NoteBlend::Alpha),
+            "Non-Instanced Note Render Pipeline",
+        );
+        let additive_note_pipeline = create_note_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            config.format,
+            NoteInstance::desc(),
+            note_blend_state(NoteBlend::Additive),
+            "Additive Note Render Pipeline",
+        );
+        let additive_note_pipeline_non_instanced = create_note_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            config.format,
+            NoteInstance::desc_per_vertex(),
+            note_blend_state(NoteBlend::Additive),
+            "Additive Non-Instanced Note Render Pipeline",
+        );
 
         // Create quad buffers
         let quad_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -230,16 +660,21 @@ impl RenderPipeline {
         });
 
         RenderPipeline {
-            surface,
+            target,
             device,
             queue,
             config,
             size,
             note_pipeline,
+            note_pipeline_non_instanced,
+            additive_note_pipeline,
+            additive_note_pipeline_non_instanced,
             quad_vertex_buffer,
             quad_index_buffer,
             uniform_buffer,
             uniform_bind_group,
+            uniform_bind_group_layout: bind_group_layout,
+            surface_context: None,
         }
     }
 
@@ -249,7 +684,14 @@ impl RenderPipeline {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            match &mut self.target {
+                RenderTarget::Surface(surface) => surface.configure(&self.device, &self.config),
+                RenderTarget::Offscreen(texture) => {
+                    *texture = create_offscreen_texture(&self.device, new_size.width, new_size.height);
+                }
+                // The application resizes its own views
+                RenderTarget::External => {}
+            }
 
             // Update uniform buffer with new size
             let uniforms = Uniforms {
@@ -272,16 +714,183 @@ impl RenderPipeline {
     }
 
     /// Begin a render pass
-    pub fn begin_render(&self, _background_color: [f32; 4]) -> Result<(wgpu::SurfaceTexture, wgpu::CommandEncoder), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+    ///
+    /// External targets have no frame to acquire and fail with `SurfaceError::Lost`.
+    pub fn begin_render(&self, _background_color: [f32; 4]) -> Result<(RenderFrame, wgpu::CommandEncoder), wgpu::SurfaceError> {
+        let output = match &self.target {
+            RenderTarget::Surface(surface) => {
+                let surface_texture = surface.get_current_texture()?;
+                RenderFrame {
+                    view: surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    surface_texture: Some(surface_texture),
+                }
+            }
+            RenderTarget::Offscreen(texture) => RenderFrame {
+                view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                surface_texture: None,
+            },
+            RenderTarget::External => return Err(wgpu::SurfaceError::Lost),
+        };
         let encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
         Ok((output, encoder))
     }
 
+    /// Get the color to clear a frame to for the `background` color
+    ///
+    /// Transparent windows expect colors premultiplied by their alpha; other targets get the
+    /// color unchanged, so a background alpha of 0 leaves the frame transparent for blending it
+    /// over other content.
+    pub fn clear_color(&self, background: [f32; 4]) -> wgpu::Color {
+        background_clear_color(background, self.is_transparent())
+    }
+
+    /// Check whether the window surface is composited with the desktop behind it
+    pub fn is_transparent(&self) -> bool {
+        matches!(
+            self.config.alpha_mode,
+            wgpu::CompositeAlphaMode::PreMultiplied | wgpu::CompositeAlphaMode::Inherit
+        )
+    }
+
+    /// Get the offscreen target texture, None when rendering to a window
+    pub fn offscreen_texture(&self) -> Option<&wgpu::Texture> {
+        match &self.target {
+            RenderTarget::Offscreen(texture) => Some(texture),
+            RenderTarget::Surface(_) | RenderTarget::External => None,
+        }
+    }
+
+    /// Get the most notes a note buffer can hold on this device, to cap `quality.max_note_count`
+    /// The non-instanced path stores every note once per quad corner, so it holds fewer
+    pub fn max_buffer_notes(&self, instanced: bool) -> u32 {
+        let copies = if instanced { 1 } else { 4 };
+        let note_size = std::mem::size_of::<NoteInstance>() as u64 * copies;
+        (self.device.limits().max_buffer_size / note_size).min(u32::MAX as u64) as u32
+    }
+
     /// Get the number of indices in the quad
     pub fn quad_index_count(&self) -> u32 {
         QUAD_INDICES.len() as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_offscreen_frame() {
+        let size = winit::dpi::PhysicalSize::new(64, 32);
+        let mut pipeline = require_gpu(RenderPipeline::new_offscreen(size, &AppConfig::default()));
+        assert!(pipeline.offscreen_texture().is_some());
+
+        let (frame, encoder) = pipeline.begin_render([0.0, 0.0, 0.0, 1.0]).unwrap();
+        pipeline.queue.submit(std::iter::once(encoder.finish()));
+        frame.present();
+
+        pipeline.resize(winit::dpi::PhysicalSize::new(128, 64));
+        let texture = pipeline.offscreen_texture().unwrap();
+        assert_eq!((texture.width(), texture.height()), (128, 64));
+    }
+
+    #[test]
+    fn test_backend_selection() {
+        assert_eq!(wgpu_backends(GpuBackend::Auto), wgpu::Backends::all());
+        assert_eq!(wgpu_backends(GpuBackend::Vulkan), wgpu::Backends::VULKAN);
+        assert_eq!(GpuBackend::from_name("DX12"), Some(GpuBackend::Dx12));
+        assert_eq!(GpuBackend::from_name("opengl"), Some(GpuBackend::Gl));
+        assert_eq!(GpuBackend::from_name("glide"), None);
+        assert_eq!(PowerPreference::from_name("Low"), Some(PowerPreference::LowPower));
+
+        let error = PipelineError::NoAdapter {
+            backend: GpuBackend::Metal,
+            available: vec!["llvmpipe (Vulkan)".to_string()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "No GPU adapter found for backend Metal (available: llvmpipe (Vulkan))"
+        );
+    }
+
+    #[test]
+    fn test_note_blend_state() {
+        assert_eq!(note_blend_state(NoteBlend::Alpha), wgpu::BlendState::ALPHA_BLENDING);
+        let additive = note_blend_state(NoteBlend::Additive);
+        assert_eq!(additive.color.src_factor, wgpu::BlendFactor::SrcAlpha);
+        assert_eq!(additive.color.dst_factor, wgpu::BlendFactor::One);
+    }
+
+    #[test]
+    fn test_surface_format_selection() {
+        use wgpu::TextureFormat;
+        assert_eq!(
+            select_surface_format(&[TextureFormat::Bgra8UnormSrgb, TextureFormat::Bgra8Unorm]),
+            TextureFormat::Bgra8Unorm
+        );
+        assert!(!OFFSCREEN_FORMAT.is_srgb());
+        // Without a choice the sRGB format is still used
+        assert_eq!(select_surface_format(&[TextureFormat::Rgba8UnormSrgb]), TextureFormat::Rgba8UnormSrgb);
+        // Linear float and 10-bit formats are passed over even when listed first
+        let hdr_first = [
+            TextureFormat::Rgba16Float,
+            TextureFormat::Rgb10a2Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Rgba8Unorm,
+        ];
+        assert_eq!(select_surface_format(&hdr_first), TextureFormat::Rgba8Unorm);
+        assert_eq!(select_surface_format(&hdr_first[..3]), TextureFormat::Rgba16Float);
+    }
+
+    #[test]
+    fn test_transparent_window() {
+        use wgpu::CompositeAlphaMode;
+        let supported = [CompositeAlphaMode::Opaque, CompositeAlphaMode::PreMultiplied, CompositeAlphaMode::Inherit];
+        assert_eq!(select_alpha_mode(true, &supported), CompositeAlphaMode::PreMultiplied);
+        assert_eq!(select_alpha_mode(true, &supported[..1]), CompositeAlphaMode::Opaque);
+        assert_eq!(select_alpha_mode(true, &[CompositeAlphaMode::Opaque, CompositeAlphaMode::Inherit]), CompositeAlphaMode::Inherit);
+        // Opaque windows keep the surface's preferred mode
+        assert_eq!(select_alpha_mode(false, &supported), CompositeAlphaMode::Opaque);
+
+        // Transparent windows get the color premultiplied by its alpha
+        let background = [0.5, 0.25, 2.0, 0.5];
+        let color = background_clear_color(background, true);
+        assert_eq!(color, wgpu::Color { r: 0.25, g: 0.125, b: 0.5, a: 0.5 });
+        assert_eq!(background_clear_color(background, false), wgpu::Color { r: 0.5, g: 0.25, b: 1.0, a: 0.5 });
+    }
+
+    #[test]
+    fn test_limits_selection() {
+        // Desktop GPUs get the default limits, with buffers as large as they allow
+        let desktop = wgpu::Limits {
+            max_buffer_size: 1 << 31,
+            max_texture_dimension_2d: 16384,
+            ..wgpu::Limits::default()
+        };
+        let limits = select_limits(&desktop);
+        assert_eq!(limits.max_buffer_size, 1 << 31);
+        assert_eq!(limits.max_texture_dimension_2d, 16384);
+        assert_eq!(limits.max_bind_groups, wgpu::Limits::default().max_bind_groups);
+
+        // GPUs below the defaults fall back to the downlevel limits instead of failing
+        let integrated = wgpu::Limits {
+            max_storage_buffers_per_shader_stage: 4,
+            ..desktop.clone()
+        };
+        assert!(!wgpu::Limits::default().check_limits(&integrated));
+        let limits = select_limits(&integrated);
+        assert!(limits.check_limits(&integrated));
+        assert_eq!(limits.max_storage_buffers_per_shader_stage, 4);
+        assert_eq!(limits.max_buffer_size, 1 << 31);
+
+        // Anything else gets its own limits
+        let constrained = wgpu::Limits {
+            max_vertex_attributes: 8,
+            ..wgpu::Limits::downlevel_webgl2_defaults()
+        };
+        assert!(select_limits(&constrained).check_limits(&constrained));
+    }
+}
@@ -0,0 +1,140 @@
+// src/renderer/playhead.rs
+
+use crate::config::AppConfig;
+use crate::renderer::note_renderer::NoteInstance;
+use crate::renderer::pipeline::RenderPipeline;
+use wgpu::util::DeviceExt;
+
+/// Height of the playhead above the bottom of the screen with the default piano (normalized 0-1)
+pub const PLAYHEAD_POSITION: f32 = 0.15;
+
+/// Width in pixels of the faded band drawn on each side of the line to soften its edges
+const PLAYHEAD_FEATHER_PX: f32 = 1.5;
+
+/// Renderer for the horizontal line marking where notes are hit
+pub struct PlayheadRenderer {
+    /// Instance buffer for the line and its feathered edges
+    instance_buffer: Option<wgpu::Buffer>,
+    /// Number of playhead instances
+    instance_count: u32,
+}
+
+impl PlayheadRenderer {
+    /// Create a new playhead renderer
+    pub fn new(_config: &AppConfig) -> Self {
+        PlayheadRenderer {
+            instance_buffer: None,
+            instance_count: 0,
+        }
+    }
+
+    /// Build the playhead quads for the current screen size and settings
+    fn instances(config: &AppConfig, screen_height: f32) -> Vec<NoteInstance> {
+        let display = &config.display;
+        if !display.show_playhead {
+            return Vec::new();
+        }
+
+        let pixel = 1.0 / screen_height.max(1.0);
+        let thickness = display.playhead_thickness.max(1.0) * pixel;
+        let feather = PLAYHEAD_FEATHER_PX * pixel;
+        let color = display.playhead_color;
+        let faded = [color[0], color[1], color[2], color[3] * 0.35];
+        let bottom = display.playhead_position() - thickness * 0.5;
+
+        // Faint bands above and below the solid core approximate an antialiased edge
+        vec![
+            NoteInstance {
+                position: [0.0, bottom - feather],
+                size: [1.0, feather],
+                color: faded,
+                ..Default::default()
+            },
+            NoteInstance {
+                position: [0.0, bottom + thickness],
+                size: [1.0, feather],
+                color: faded,
+                ..Default::default()
+            },
+            NoteInstance {
+                position: [0.0, bottom],
+                size: [1.0, thickness],
+                color,
+                ..Default::default()
+            },
+        ]
+    }
+
+    /// Update the playhead buffer
+    pub fn update(&mut self, pipeline: &RenderPipeline, config: &AppConfig) {
+        let instances = Self::instances(config, pipeline.size.height as f32);
+        self.instance_count = instances.len() as u32;
+
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let buffer_size = (self.instance_count as usize * std::mem::size_of::<NoteInstance>()) as u64;
+
+        let needs_new_buffer = match &self.instance_buffer {
+            None => true,
+            Some(buffer) => buffer.size() < buffer_size,
+        };
+
+        if needs_new_buffer {
+            self.instance_buffer = Some(pipeline.device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("Playhead Instance Buffer"),
+                    contents: bytemuck::cast_slice(&instances),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                },
+            ));
+        } else if let Some(buffer) = &self.instance_buffer {
+            pipeline.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+        }
+    }
+
+    /// Render the playhead line
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a RenderPipeline) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        if let Some(instance_buffer) = &self.instance_buffer {
+            render_pass.set_pipeline(&pipeline.note_pipeline);
+            render_pass.set_bind_group(0, &pipeline.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, pipeline.quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.set_index_buffer(pipeline.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..pipeline.quad_index_count(), 0, 0..self.instance_count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playhead_geometry() {
+        let mut config = AppConfig::default();
+        config.display.playhead_thickness = 4.0;
+
+        let instances = PlayheadRenderer::instances(&config, 1000.0);
+        assert_eq!(instances.len(), 3);
+        let line = instances[2];
+        assert!((line.size[1] - 0.004).abs() < 1e-6);
+        // The line is centered on the playhead and spans the full width
+        assert!((line.position[1] + line.size[1] * 0.5 - PLAYHEAD_POSITION).abs() < 1e-6);
+        assert_eq!(line.size[0], 1.0);
+
+        // Without the piano the playhead moves down with the notes
+        config.display.show_piano = false;
+        let line = PlayheadRenderer::instances(&config, 1000.0)[2];
+        assert!((line.position[1] + line.size[1] * 0.5 - config.display.playhead_position()).abs() < 1e-6);
+        assert!(config.display.playhead_position() < PLAYHEAD_POSITION);
+
+        config.display.show_playhead = false;
+        assert!(PlayheadRenderer::instances(&config, 1000.0).is_empty());
+    }
+}
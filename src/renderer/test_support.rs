@@ -0,0 +1,11 @@
+// src/renderer/test_support.rs
+
+use std::future::Future;
+
+/// Wait for a GPU resource in a test, failing the test when no adapter or device is available
+///
+/// Tests using it are marked `#[ignore = "needs a GPU adapter"]`, so `cargo test` skips them
+/// on machines without a GPU; run them with `cargo test -- --ignored`.
+pub fn require_gpu<T>(resource: impl Future<Output = Option<T>>) -> T {
+    pollster::block_on(resource).expect("No GPU adapter available for a test that needs one")
+}
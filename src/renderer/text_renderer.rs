@@ -0,0 +1,281 @@
+// src/renderer/text_renderer.rs
+
+use crate::renderer::note_renderer::NoteInstance;
+use crate::renderer::pipeline::RenderPipeline;
+use wgpu::util::DeviceExt;
+
+/// Width of a glyph in font pixels
+pub const GLYPH_WIDTH: u32 = 5;
+/// Height of a glyph in font pixels
+pub const GLYPH_HEIGHT: u32 = 7;
+/// Horizontal distance between glyph origins in font pixels
+const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+/// Vertical distance between lines in font pixels
+const LINE_ADVANCE: u32 = GLYPH_HEIGHT + 2;
+
+/// Renderer for simple text drawn with a built-in 5x7 bitmap font
+///
+/// Every lit font pixel run becomes a quad drawn with the note pipeline, so text needs
+/// no texture atlas. The font only has capitals; lowercase letters are drawn as capitals
+/// and characters without a glyph as `?`. Queue text each frame between
+/// [`TextRenderer::begin`] and [`TextRenderer::update`].
+pub struct TextRenderer {
+    /// Quads queued for the current frame
+    instances: Vec<NoteInstance>,
+    /// Instance buffer for the queued quads
+    instance_buffer: Option<wgpu::Buffer>,
+    /// Number of quads uploaded by the last update
+    instance_count: u32,
+    /// Physical screen size used to convert pixels to normalized coordinates
+    screen_size: (f32, f32),
+}
+
+impl TextRenderer {
+    /// Create a new text renderer
+    pub fn new() -> Self {
+        TextRenderer {
+            instances: Vec::new(),
+            instance_buffer: None,
+            instance_count: 0,
+            screen_size: (1.0, 1.0),
+        }
+    }
+
+    /// Start a new frame, dropping the text queued for the previous one
+    pub fn begin(&mut self, screen_width: f32, screen_height: f32) {
+        self.instances.clear();
+        self.screen_size = (screen_width.max(1.0), screen_height.max(1.0));
+    }
+
+    /// Get the ratio of screen height to width, to turn a normalized height into the same width
+    pub fn aspect(&self) -> f32 {
+        self.screen_size.1 / self.screen_size.0
+    }
+
+    /// Get a font scale (physical pixels per font pixel) that stays readable at any resolution
+    pub fn default_scale(&self) -> f32 {
+        (self.screen_size.1 / 270.0).round().max(2.0)
+    }
+
+    /// Get the size of `text` in normalized screen coordinates at the given scale
+    pub fn text_size(&self, text: &str, scale: f32) -> [f32; 2] {
+        let columns = text.lines().map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+        let rows = text.lines().count().max(1) as u32;
+        let width = (columns * GLYPH_ADVANCE).saturating_sub(1) as f32 * scale;
+        let height = ((rows - 1) * LINE_ADVANCE + GLYPH_HEIGHT) as f32 * scale;
+        [width / self.screen_size.0, height / self.screen_size.1]
+    }
+
+    /// Get the horizontal distance (normalized) from the start of a line to its character `column`
+    pub fn column_offset(&self, column: usize, scale: f32) -> f32 {
+        (column as u32 * GLYPH_ADVANCE) as f32 * scale / self.screen_size.0
+    }
+
+    /// Get how many characters fit in `width` (normalized) at the given scale
+    pub fn columns_in(&self, width: f32, scale: f32) -> usize {
+        let advance = GLYPH_ADVANCE as f32 * scale / self.screen_size.0;
+        ((width + scale / self.screen_size.0) / advance).floor().max(0.0) as usize
+    }
+
+    /// Queue a filled rectangle, positioned by its bottom-left corner in normalized coordinates
+    pub fn draw_rect(&mut self, position: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        self.instances.push(NoteInstance {
+            position,
+            size,
+            color,
+            ..Default::default()
+        });
+    }
+
+    /// Queue text with the top-left corner of its first line at `top_left` (normalized)
+    pub fn draw_text(&mut self, text: &str, top_left: [f32; 2], scale: f32, color: [f32; 4]) {
+        let (screen_width, screen_height) = self.screen_size;
+        // Snap to whole pixels so font pixels stay crisp
+        let origin_x = (top_left[0] * screen_width).round();
+        let origin_y = (top_left[1] * screen_height).round();
+        let pixel_width = scale / screen_width;
+        let pixel_height = scale / screen_height;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_top = origin_y - (line_index as u32 * LINE_ADVANCE) as f32 * scale;
+            for (column, character) in line.chars().enumerate() {
+                let glyph_x = origin_x + (column as u32 * GLYPH_ADVANCE) as f32 * scale;
+                for (row, bits) in glyph(character).iter().enumerate() {
+                    let y = (line_top - (row as f32 + 1.0) * scale) / screen_height;
+                    // One quad per horizontal run of lit pixels
+                    let mut bit = 0;
+                    while bit < GLYPH_WIDTH {
+                        if bits & (1 << (GLYPH_WIDTH - 1 - bit)) == 0 {
+                            bit += 1;
+                            continue;
+                        }
+                        let start = bit;
+                        while bit < GLYPH_WIDTH && bits & (1 << (GLYPH_WIDTH - 1 - bit)) != 0 {
+                            bit += 1;
+                        }
+                        self.instances.push(NoteInstance {
+                            position: [glyph_x / screen_width + start as f32 * pixel_width, y],
+                            size: [(bit - start) as f32 * pixel_width, pixel_height],
+                            color,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Upload the queued text
+    pub fn update(&mut self, pipeline: &RenderPipeline) {
+        self.instance_count = self.instances.len() as u32;
+
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let buffer_size = (self.instances.len() * std::mem::size_of::<NoteInstance>()) as u64;
+
+        let needs_new_buffer = match &self.instance_buffer {
+            None => true,
+            Some(buffer) => buffer.size() < buffer_size,
+        };
+
+        if needs_new_buffer {
+            self.instance_buffer = Some(pipeline.device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("Text Instance Buffer"),
+                    contents: bytemuck::cast_slice(&self.instances),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                },
+            ));
+        } else if let Some(buffer) = &self.instance_buffer {
+            pipeline.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.instances));
+        }
+    }
+
+    /// Render the uploaded text
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a RenderPipeline) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        if let Some(instance_buffer) = &self.instance_buffer {
+            render_pass.set_pipeline(&pipeline.note_pipeline);
+            render_pass.set_bind_group(0, &pipeline.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, pipeline.quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.set_index_buffer(pipeline.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..pipeline.quad_index_count(), 0, 0..self.instance_count);
+        }
+    }
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the rows of a glyph, top row first, with the leftmost pixel in bit 4
+fn glyph(character: char) -> [u8; 7] {
+    match character.to_ascii_uppercase() {
+        ' ' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '"' => [0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+        '$' => [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '&' => [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101],
+        '\'' => [0b00100, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        '*' => [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00110, 0b00100, 0b01000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        ';' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000],
+        '<' => [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '>' => [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000],
+        '@' => [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110],
+        'A' => [0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+        '\\' => [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000],
+        ']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+        '^' => [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '`' => [0b01000, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '{' => [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010],
+        '|' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        '}' => [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000],
+        '~' => [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_layout() {
+        let mut text = TextRenderer::new();
+        text.begin(600.0, 100.0);
+
+        // Two glyphs with a one pixel gap at scale 2
+        let size = text.text_size("AB", 2.0);
+        assert!((size[0] - 22.0 / 600.0).abs() < 1e-6);
+        assert!((size[1] - 14.0 / 100.0).abs() < 1e-6);
+        assert_eq!(text.text_size("AB\nC", 1.0)[1], 16.0 / 100.0);
+        assert_eq!(text.columns_in(size[0], 2.0), 2);
+
+        // 'I' is a three pixel run on the top and bottom rows and single pixels in between
+        text.draw_text("i", [0.0, 1.0], 1.0, [1.0; 4]);
+        assert_eq!(text.instances.len(), 7);
+        let top = text.instances[0];
+        assert!((top.position[1] - 0.99).abs() < 1e-6);
+        assert!((top.size[0] - 3.0 / 600.0).abs() < 1e-6);
+
+        text.begin(600.0, 100.0);
+        text.draw_text(" ", [0.0, 1.0], 1.0, [1.0; 4]);
+        assert!(text.instances.is_empty());
+    }
+}
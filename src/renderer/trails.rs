@@ -0,0 +1,386 @@
+// src/renderer/trails.rs
+
+use crate::config::AppConfig;
+use crate::renderer::note_renderer::NoteRenderer;
+use crate::renderer::pipeline::RenderPipeline;
+
+/// Largest playback step between frames that keeps the trail; bigger jumps are seeks and
+/// start it over instead of smearing notes across the screen (in seconds)
+const MAX_TRAIL_STEP: f32 = 0.5;
+
+/// GPU resources, created on first use
+struct TrailTargets {
+    /// Size of the trail texture in pixels
+    size: (u32, u32),
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    composite_pipeline: wgpu::RenderPipeline,
+    fade_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+}
+
+/// Motion trails for falling notes
+///
+/// Notes are drawn into a texture that is kept between frames and faded by the decay
+/// before each frame's notes, so moving notes leave a fading streak. The texture is then
+/// drawn over the scene in place of the notes.
+pub struct TrailRenderer {
+    /// Fraction of the trail kept each frame, 0 when trails are off
+    decay: f32,
+    /// Playback position of the previous frame
+    last_time: Option<f32>,
+    /// Whether this frame starts the trail over
+    clear: bool,
+    targets: Option<TrailTargets>,
+}
+
+impl TrailRenderer {
+    /// Create a new trail renderer
+    pub fn new(config: &AppConfig) -> Self {
+        TrailRenderer {
+            decay: config.quality.trail_decay_strength(),
+            last_time: None,
+            clear: true,
+            targets: None,
+        }
+    }
+
+    /// Check whether notes are drawn with trails
+    pub fn is_active(&self) -> bool {
+        self.decay > 0.0
+    }
+
+    /// Follow the settings and playback position, preparing the trail texture when active
+    pub fn update(&mut self, pipeline: &RenderPipeline, current_time: f32, config: &AppConfig) {
+        self.advance(current_time, config.quality.trail_decay_strength());
+        if !self.is_active() {
+            // Free the texture while trails are off
+            self.targets = None;
+            return;
+        }
+
+        let size = (pipeline.size.width.max(1), pipeline.size.height.max(1));
+        match &mut self.targets {
+            Some(targets) if targets.size == size => {}
+            Some(targets) => {
+                targets.resize(&pipeline.device, size, pipeline.config.format);
+                self.clear = true;
+            }
+            None => {
+                self.targets = Some(TrailTargets::new(&pipeline.device, size, pipeline.config.format));
+                self.clear = true;
+            }
+        }
+    }
+
+    /// Track the playback position, starting the trail over after seeks and when turned on
+    fn advance(&mut self, current_time: f32, decay: f32) {
+        let was_active = self.is_active();
+        self.decay = decay;
+        self.clear = !was_active
+            || self
+                .last_time
+                .is_none_or(|last| (current_time - last).abs() > MAX_TRAIL_STEP);
+        self.last_time = Some(current_time);
+    }
+
+    /// Fade the trail and draw this frame's notes into it, in a pass of its own
+    pub fn render_notes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &RenderPipeline,
+        notes: &NoteRenderer,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites>,
+    ) {
+        let Some(targets) = &self.targets else {
+            return;
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Trail Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &targets.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: if self.clear {
+                        wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes,
+            occlusion_query_set: None,
+        });
+
+        if !self.clear {
+            let decay = self.decay as f64;
+            render_pass.set_pipeline(&targets.fade_pipeline);
+            render_pass.set_blend_constant(wgpu::Color {
+                r: decay,
+                g: decay,
+                b: decay,
+                a: decay,
+            });
+            render_pass.draw(0..3, 0..1);
+        }
+
+        notes.render(&mut render_pass, pipeline);
+    }
+
+    /// Draw the trail, notes included, over the scene
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let Some(targets) = &self.targets {
+            render_pass.set_pipeline(&targets.composite_pipeline);
+            render_pass.set_bind_group(0, &targets.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+impl TrailTargets {
+    fn new(device: &wgpu::Device, size: (u32, u32), format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Trail Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../assets/shaders/trail.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Trail Bind Group Layout"),
+        });
+
+        let composite_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Trail Composite Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let fade_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Trail Fade Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let composite_pipeline = create_fullscreen_pipeline(
+            device,
+            &composite_layout,
+            &shader,
+            "fs_composite",
+            format,
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            "Trail Composite Pipeline",
+        );
+        // Keep `decay` of what is there and take off a little more
+        let fade = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::Constant,
+            operation: wgpu::BlendOperation::ReverseSubtract,
+        };
+        let fade_pipeline = create_fullscreen_pipeline(
+            device,
+            &fade_layout,
+            &shader,
+            "fs_fade",
+            format,
+            wgpu::BlendState { color: fade, alpha: fade },
+            "Trail Fade Pipeline",
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Trail Sampler"),
+            ..Default::default()
+        });
+
+        let (view, bind_group) = create_trail_texture(device, size, format, &bind_group_layout, &sampler);
+        TrailTargets {
+            size,
+            view,
+            bind_group,
+            composite_pipeline,
+            fade_pipeline,
+            bind_group_layout,
+            sampler,
+        }
+    }
+
+    /// Recreate the texture for a new screen size
+    fn resize(&mut self, device: &wgpu::Device, size: (u32, u32), format: wgpu::TextureFormat) {
+        let (view, bind_group) = create_trail_texture(device, size, format, &self.bind_group_layout, &self.sampler);
+        self.size = size;
+        self.view = view;
+        self.bind_group = bind_group;
+    }
+}
+
+/// Create a pipeline drawing one screen-covering triangle with the `fragment` entry point
+fn create_fullscreen_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment: &str,
+    format: wgpu::TextureFormat,
+    blend: wgpu::BlendState,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// Create the trail texture and the bind group sampling it
+fn create_trail_texture(
+    device: &wgpu::Device,
+    size: (u32, u32),
+    format: wgpu::TextureFormat,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+) -> (wgpu::TextureView, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Trail Texture"),
+        size: wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("Trail Bind Group"),
+    });
+    (view, bind_group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+
+    #[test]
+    fn test_trail_restarts_on_seek() {
+        let mut trails = TrailRenderer::new(&AppConfig::default());
+        assert!(!trails.is_active());
+
+        trails.advance(1.0, 0.8);
+        assert!(trails.is_active());
+        // Turning trails on starts from a clean texture
+        assert!(trails.clear);
+        trails.advance(1.016, 0.8);
+        assert!(!trails.clear);
+
+        // Seeking either way starts over instead of smearing
+        trails.advance(30.0, 0.8);
+        assert!(trails.clear);
+        trails.advance(30.016, 0.8);
+        trails.advance(2.0, 0.8);
+        assert!(trails.clear);
+
+        trails.advance(2.016, 0.0);
+        assert!(!trails.is_active());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_trail_frames() {
+        let mut config = AppConfig::default();
+        config.quality.trail_decay = 0.8;
+        let size = winit::dpi::PhysicalSize::new(64, 32);
+        let mut pipeline = require_gpu(RenderPipeline::new_offscreen(size, &config));
+        let notes = vec![crate::midi::Note::new(60, 100, 0.5, 1.0, 0)];
+        let mut note_renderer = NoteRenderer::new(&config);
+        let mut trails = TrailRenderer::new(&config);
+
+        for (frame, time) in [0.0, 0.016, 0.032].into_iter().enumerate() {
+            if frame == 2 {
+                // The texture follows the screen size
+                pipeline.resize(winit::dpi::PhysicalSize::new(128, 64));
+            }
+            note_renderer.update(&pipeline, &notes, time, &config);
+            trails.update(&pipeline, time, &config);
+            assert_eq!(trails.clear, frame != 1);
+
+            let (output, mut encoder) = pipeline.begin_render(config.display.background_color).unwrap();
+            trails.render_notes(&mut encoder, &pipeline, &note_renderer, None);
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Test Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &output.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                trails.render(&mut render_pass);
+            }
+            pipeline.queue.submit(std::iter::once(encoder.finish()));
+            output.present();
+        }
+        assert_eq!(trails.targets.as_ref().unwrap().size, (128, 64));
+
+        // Turning trails off frees the texture
+        config.quality.trail_decay = 0.0;
+        trails.update(&pipeline, 0.048, &config);
+        assert!(trails.targets.is_none());
+    }
+}
@@ -0,0 +1,245 @@
+// src/renderer/visualizer.rs
+
+use crate::config::AppConfig;
+use crate::midi::{Expression, Note, NoteColorMap, NoteEventEmitter};
+use crate::renderer::note_renderer::NoteRenderer;
+use crate::renderer::piano_renderer::PianoRenderer;
+use crate::renderer::pipeline::RenderPipeline;
+use crate::renderer::playhead::PlayheadRenderer;
+
+/// The falling notes, the piano and the playhead, drawn into any texture view
+///
+/// This is the entry point for embedding the visualizer in another wgpu application: create
+/// a [`RenderPipeline`] on the application's device with [`RenderPipeline::from_device`],
+/// load a song with [`Visualizer::set_notes`] and draw every frame with
+/// [`Visualizer::render`] or [`Visualizer::encode`]. The application drives the playback
+/// time itself, e.g. with a [`MidiPlayer`](crate::midi::MidiPlayer).
+pub struct Visualizer {
+    /// Notes of the loaded song, sorted by start time
+    notes: Vec<Note>,
+    note_renderer: NoteRenderer,
+    piano_renderer: PianoRenderer,
+    playhead: PlayheadRenderer,
+    /// Tracks which keys to light up as playback moves on
+    note_events: NoteEventEmitter,
+}
+
+impl Visualizer {
+    pub fn new(config: &AppConfig) -> Self {
+        Visualizer {
+            notes: Vec::new(),
+            note_renderer: NoteRenderer::new(config),
+            piano_renderer: PianoRenderer::new(config),
+            playhead: PlayheadRenderer::new(config),
+            note_events: NoteEventEmitter::new(),
+        }
+    }
+
+    /// Load the notes of a song, e.g. `ParsedMidi::notes`; they must be sorted by start time
+    pub fn set_notes(&mut self, notes: Vec<Note>, config: &AppConfig) {
+        self.notes = notes;
+        self.note_renderer.allocate_voices(&self.notes, config);
+        self.note_renderer.assign_lanes(&self.notes);
+        self.note_events.reset();
+    }
+
+    /// Set the controller timelines of the song, e.g. `ParsedMidi::expression`, for pitch bends
+    pub fn set_expression(&mut self, expression: Expression) {
+        self.note_renderer.set_expression(expression);
+    }
+
+    /// Set the custom note colors of the song, e.g. from [`NoteColorMap::load_for`]
+    pub fn set_color_map(&mut self, color_map: NoteColorMap) {
+        self.note_renderer.set_color_map(color_map);
+    }
+
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    /// Get the renderer of the falling notes, e.g. to change the time window
+    pub fn note_renderer_mut(&mut self) -> &mut NoteRenderer {
+        &mut self.note_renderer
+    }
+
+    /// Draw the frame at `current_time` into `view` and submit it to the pipeline's queue
+    pub fn render(&mut self, pipeline: &RenderPipeline, view: &wgpu::TextureView, current_time: f32, config: &AppConfig) {
+        let mut encoder = pipeline.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Visualizer Encoder"),
+        });
+        self.encode(pipeline, &mut encoder, view, current_time, config);
+        pipeline.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Record the frame at `current_time` into the application's `encoder`, drawing into `view`
+    ///
+    /// The view is cleared to `display.background_color` first; make its alpha 0 to draw over
+    /// the application's own content later.
+    pub fn encode(
+        &mut self,
+        pipeline: &RenderPipeline,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        current_time: f32,
+        config: &AppConfig,
+    ) {
+        pipeline.update_uniforms(config.display.playhead_position(), current_time);
+
+        let active_pitches: Vec<u8> = if self.notes.is_empty() {
+            Vec::new()
+        } else {
+            self.note_events.update(&self.notes, current_time);
+            self.note_events.sounded_notes(&self.notes).map(|note| note.pitch).collect()
+        };
+        update_scene(
+            pipeline,
+            &mut self.note_renderer,
+            &mut self.piano_renderer,
+            &mut self.playhead,
+            &self.notes,
+            &active_pitches,
+            current_time,
+            config,
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Visualizer Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(pipeline.clear_color(config.display.background_color)),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let note_renderer = &self.note_renderer;
+        draw_scene(&mut render_pass, pipeline, &self.piano_renderer, &self.playhead, |render_pass| {
+            note_renderer.render(render_pass, pipeline)
+        });
+    }
+}
+
+/// Update the renderers of the scene for the frame at `current_time`
+///
+/// Shared by [`Visualizer`] and the application window, which draws more around the scene.
+#[allow(clippy::too_many_arguments)]
+pub fn update_scene(
+    pipeline: &RenderPipeline,
+    note_renderer: &mut NoteRenderer,
+    piano_renderer: &mut PianoRenderer,
+    playhead: &mut PlayheadRenderer,
+    notes: &[Note],
+    active_pitches: &[u8],
+    current_time: f32,
+    config: &AppConfig,
+) {
+    note_renderer.update(pipeline, notes, current_time, config);
+    piano_renderer.update(pipeline, active_pitches, config);
+    playhead.update(pipeline, config);
+}
+
+/// Draw the scene updated by [`update_scene`]: the piano below the notes, the playhead on top
+///
+/// `draw_notes` draws the falling notes, directly or through another texture like the trails.
+pub fn draw_scene<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    pipeline: &'a RenderPipeline,
+    piano_renderer: &'a PianoRenderer,
+    playhead: &'a PlayheadRenderer,
+    draw_notes: impl FnOnce(&mut wgpu::RenderPass<'a>),
+) {
+    piano_renderer.render(render_pass, pipeline);
+    draw_notes(render_pass);
+    playhead.render(render_pass, pipeline);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_support::require_gpu;
+    use std::sync::Arc;
+
+    /// Read the RGBA8 pixel at (`x`, `y`), counted from the top-left corner
+    fn read_pixel(pipeline: &RenderPipeline, texture: &wgpu::Texture, x: u32, y: u32) -> [u8; 4] {
+        // Rows of the copy must be 256-byte aligned, which one 64 pixel wide row is
+        let buffer = pipeline.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Test Readback Buffer"),
+            size: 256,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = pipeline.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: 0, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(256),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: texture.width(),
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        pipeline.queue.submit(std::iter::once(encoder.finish()));
+        buffer.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        pipeline.device.poll(wgpu::Maintain::Wait);
+        let data = buffer.slice(..).get_mapped_range();
+        let offset = x as usize * 4;
+        [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn test_render_into_application_view() {
+        let mut config = AppConfig::default();
+        config.display.background_color = [0.0, 0.0, 0.0, 1.0];
+        let size = winit::dpi::PhysicalSize::new(64, 64);
+        let host = require_gpu(RenderPipeline::new_offscreen(size, &config));
+
+        // The application owns the device, the queue and the texture
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        let pipeline = RenderPipeline::from_device(Arc::clone(&host.device), Arc::clone(&host.queue), format, (64, 64));
+        assert!(pipeline.offscreen_texture().is_none());
+        assert!(pipeline.begin_render(config.display.background_color).is_err());
+        let texture = pipeline.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Application Texture"),
+            size: wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // A note on all 128 keys, so every column above the playhead is covered
+        let notes = (0..128u8).map(|pitch| Note::new(pitch, 127, 0.0, 10.0, 0)).collect();
+        let mut visualizer = Visualizer::new(&config);
+        visualizer.set_notes(notes, &config);
+        visualizer.render(&pipeline, &view, 1.0, &config);
+        assert_eq!(visualizer.note_renderer_mut().visible_count(), 128);
+
+        // Notes fill the middle of the view, which would otherwise show the black background
+        let pixel = read_pixel(&pipeline, &texture, 32, 32);
+        assert!(pixel[..3].iter().any(|&channel| channel > 64), "{:?}", pixel);
+    }
+}
@@ -0,0 +1,115 @@
+// src/session.rs
+
+use crate::config::ChannelConfig;
+use crate::midi::MidiPlayer;
+use serde::{Deserialize, Serialize};
+
+/// Playback state saved on exit so a long file can be resumed exactly where it was left
+///
+/// Unlike [`AppConfig`](crate::config::AppConfig), which holds preferences, this describes
+/// the file that was playing and is only restored when that file is opened again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybackSession {
+    /// Path of the file the state belongs to
+    pub file: Option<String>,
+    /// Playback position in seconds
+    pub time: f32,
+    pub speed: f32,
+    /// Channels muted while the file played (0-15)
+    pub muted_channels: Vec<u8>,
+    /// Channels soloed while the file played (0-15)
+    pub soloed_channels: Vec<u8>,
+}
+
+impl PlaybackSession {
+    /// Capture the playback state of `file`
+    pub fn capture(file: Option<String>, player: &MidiPlayer, channels: &ChannelConfig) -> Self {
+        PlaybackSession {
+            file,
+            time: player.get_current_time(),
+            speed: player.get_playback_speed(),
+            muted_channels: channels.muted.clone(),
+            soloed_channels: channels.soloed.clone(),
+        }
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let session = serde_json::from_str(&content)?;
+        Ok(session)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Check whether the session belongs to `file`
+    pub fn matches(&self, file: &str) -> bool {
+        self.file.as_deref() == Some(file)
+    }
+
+    /// Apply the saved position, speed and channel mask to a freshly loaded file
+    ///
+    /// A session saved at the end of the song starts it over instead.
+    pub fn restore(&self, player: &mut MidiPlayer, channels: &mut ChannelConfig) {
+        if self.time < player.get_duration() {
+            player.seek(self.time);
+        }
+        player.set_playback_speed(self.speed);
+        channels.muted = self.muted_channels.iter().copied().filter(|&channel| channel < 16).collect();
+        channels.soloed = self.soloed_channels.iter().copied().filter(|&channel| channel < 16).collect();
+    }
+}
+
+impl Default for PlaybackSession {
+    fn default() -> Self {
+        PlaybackSession {
+            file: None,
+            time: 0.0,
+            speed: 1.0,
+            muted_channels: Vec::new(),
+            soloed_channels: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip() {
+        let mut player = MidiPlayer::new();
+        player.set_duration(600.0);
+        player.seek(321.5);
+        player.set_playback_speed(1.5);
+        let channels = ChannelConfig {
+            muted: vec![2, 9],
+            soloed: vec![0],
+            ..Default::default()
+        };
+
+        let session = PlaybackSession::capture(Some("song.mid".to_string()), &player, &channels);
+        let json = serde_json::to_string(&session).unwrap();
+        let loaded: PlaybackSession = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, session);
+        assert!(loaded.matches("song.mid"));
+        assert!(!loaded.matches("other.mid"));
+
+        let mut resumed = MidiPlayer::new();
+        resumed.set_duration(600.0);
+        let mut resumed_channels = ChannelConfig::default();
+        loaded.restore(&mut resumed, &mut resumed_channels);
+        assert_eq!(resumed.get_current_time(), 321.5);
+        assert_eq!(resumed.get_playback_speed(), 1.5);
+        assert_eq!(resumed_channels.muted, vec![2, 9]);
+        assert_eq!(resumed_channels.soloed, vec![0]);
+
+        // Missing fields fall back to the defaults
+        let partial: PlaybackSession = serde_json::from_str(r#"{"time": 5.0}"#).unwrap();
+        assert_eq!(partial.speed, 1.0);
+    }
+}
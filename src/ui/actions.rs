@@ -0,0 +1,70 @@
+// src/ui/actions.rs
+
+use crate::config::AppConfig;
+use crate::midi::MidiPlayer;
+use crate::renderer::overlay::PerformanceOverlay;
+
+/// Playback and display actions shared by keyboard shortcuts and UI buttons
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerAction {
+    /// Toggle play/pause
+    TogglePlayback,
+    /// Reset playback to start
+    Reset,
+    /// Increase playback speed
+    IncreaseSpeed,
+    /// Decrease playback speed
+    DecreaseSpeed,
+    /// Toggle slow mode
+    ToggleSlowMode,
+    /// Toggle reduced visual effects
+    ToggleReducedEffects,
+    /// Toggle performance overlay
+    ToggleOverlay,
+    /// Cycle through common FPS caps
+    CycleFrameLock,
+}
+
+/// Apply a player action to the application state
+pub fn apply_player_action(
+    action: PlayerAction,
+    player: &mut MidiPlayer,
+    overlay: &mut PerformanceOverlay,
+    config: &mut AppConfig,
+) {
+    match action {
+        PlayerAction::TogglePlayback => {
+            player.toggle_playback();
+            log::debug!("Playback: {}", if player.is_playing() { "Playing" } else { "Paused" });
+        }
+        PlayerAction::Reset => {
+            player.reset();
+            log::debug!("Playback reset to start");
+        }
+        PlayerAction::IncreaseSpeed => {
+            player.increase_speed(config.controls.speed_step);
+            log::debug!("Speed: {:.1}x", player.get_playback_speed());
+        }
+        PlayerAction::DecreaseSpeed => {
+            player.decrease_speed(config.controls.speed_step);
+            log::debug!("Speed: {:.1}x", player.get_playback_speed());
+        }
+        PlayerAction::ToggleSlowMode => {
+            config.toggle_slow_mode();
+            log::debug!("Slow mode: {}", if config.performance.slow_mode { "Enabled" } else { "Disabled" });
+        }
+        PlayerAction::ToggleReducedEffects => {
+            config.toggle_reduced_effects();
+            log::debug!("Reduced effects: {}", if config.performance.reduced_effects { "Enabled" } else { "Disabled" });
+        }
+        PlayerAction::ToggleOverlay => {
+            overlay.toggle();
+            config.performance.enable_performance_overlay = overlay.is_visible();
+            log::debug!("Overlay: {}", if overlay.is_visible() { "Visible" } else { "Hidden" });
+        }
+        PlayerAction::CycleFrameLock => {
+            config.cycle_frame_lock();
+            log::debug!("FPS cap: {:?}", config.performance.frame_lock);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// src/ui/banner.rs
+
+use crate::renderer::text_renderer::TextRenderer;
+use std::time::{Duration, Instant};
+
+/// How long an error stays on screen
+pub const BANNER_DURATION: Duration = Duration::from_secs(6);
+
+/// Vertical center of the error banner (normalized), clear of the playhead and the top fade
+const BANNER_CENTER: f32 = 0.6;
+/// Fraction of the screen width the banner may cover
+const BANNER_MAX_WIDTH: f32 = 0.9;
+
+/// On-screen error messages, such as files that failed to load
+pub struct MessageBanner {
+    /// Error text and when it stops being shown
+    error: Option<(String, Instant)>,
+}
+
+impl MessageBanner {
+    pub fn new() -> Self {
+        MessageBanner { error: None }
+    }
+
+    /// Show an error until [`BANNER_DURATION`] has passed or it is cleared
+    pub fn show_error(&mut self, message: String, now: Instant) {
+        self.error = Some((message, now + BANNER_DURATION));
+    }
+
+    /// Dismiss the error, e.g. after a file loaded successfully
+    pub fn clear_error(&mut self) {
+        self.error = None;
+    }
+
+    /// Get the error currently shown
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    /// Get when the shown error expires, so an idle event loop can wake up to dismiss it
+    pub fn deadline(&self) -> Option<Instant> {
+        self.error.as_ref().map(|&(_, deadline)| deadline)
+    }
+
+    /// Dismiss the error once it has expired
+    pub fn update(&mut self, now: Instant) {
+        if self.deadline().is_some_and(|deadline| now >= deadline) {
+            self.error = None;
+        }
+    }
+
+    /// Queue the banner text
+    pub fn draw(&self, text: &mut TextRenderer) {
+        if let Some((message, _)) = &self.error {
+            // Shrink and then cut off messages too long for the screen
+            let mut scale = text.default_scale();
+            while scale > 1.0 && text.text_size(message, scale)[0] > BANNER_MAX_WIDTH {
+                scale -= 1.0;
+            }
+            let message = truncate(message, text.columns_in(BANNER_MAX_WIDTH, scale));
+            let size = text.text_size(&message, scale);
+            let margin = [size[1] * 0.8 * text.aspect(), size[1] * 0.8];
+
+            text.draw_rect(
+                [0.5 - size[0] * 0.5 - margin[0], BANNER_CENTER - size[1] * 0.5 - margin[1]],
+                [size[0] + margin[0] * 2.0, size[1] + margin[1] * 2.0],
+                [0.45, 0.08, 0.08, 0.9],
+            );
+            text.draw_text(
+                &message,
+                [0.5 - size[0] * 0.5, BANNER_CENTER + size[1] * 0.5],
+                scale,
+                [1.0, 1.0, 1.0, 1.0],
+            );
+        }
+    }
+}
+
+impl Default for MessageBanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cut `message` down to `columns` characters, ending it with "..." when shortened
+fn truncate(message: &str, columns: usize) -> String {
+    if message.chars().count() <= columns {
+        return message.to_string();
+    }
+    let kept: String = message.chars().take(columns.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_expires() {
+        let mut banner = MessageBanner::new();
+        let now = Instant::now();
+        banner.show_error("Invalid file".to_string(), now);
+        assert_eq!(banner.error(), Some("Invalid file"));
+
+        banner.update(now + BANNER_DURATION / 2);
+        assert!(banner.error().is_some());
+        banner.update(now + BANNER_DURATION);
+        assert_eq!(banner.error(), None);
+        assert_eq!(banner.deadline(), None);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("a much longer message", 10), "a much ...");
+    }
+}
@@ -0,0 +1,168 @@
+// src/ui/control_window.rs
+
+use crate::config::AppConfig;
+use crate::renderer::pipeline::{PipelineError, RenderPipeline};
+use crate::renderer::text_renderer::TextRenderer;
+use crate::ui::controls::{ButtonAction, UIControls};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use winit::window::{Window, WindowId};
+
+/// Top of the statistics text, below the buttons and clear of the shader's top fade (normalized)
+const STATS_TOP: f32 = 0.82;
+/// Bottom of the statistics text, clear of the playhead glow (normalized)
+const STATS_BOTTOM: f32 = 0.2;
+/// Space between text lines, as a fraction of the line height
+const LINE_GAP: f32 = 0.5;
+/// Shortest time between redraws for changed statistics; button states and input redraw at once
+const STATS_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A second window with the playback buttons and performance statistics, so the main window
+/// can show a clean visual, e.g. captured fullscreen on another monitor while streaming
+///
+/// The window has a render pipeline of its own on the main window's device; events for it
+/// are told apart by [`Self::id`]. It is only redrawn when what it shows changes, with the
+/// statistics refreshed at most every [`STATS_REFRESH_INTERVAL`], so presenting it doesn't
+/// hold up the frames of the main window.
+pub struct ControlWindow {
+    window: Arc<Window>,
+    pipeline: RenderPipeline,
+    controls: UIControls,
+    text: TextRenderer,
+    /// Cursor position in physical pixels, for clicks
+    cursor: (f32, f32),
+    /// Statistics shown in the window
+    lines: Vec<String>,
+    /// Button states shown: playing, slow mode, reduced effects, overlay visible
+    states: [bool; 4],
+    /// Whether the statistics changed since the last redraw
+    stale: bool,
+    /// When the window was last drawn
+    last_drawn: Instant,
+}
+
+impl ControlWindow {
+    /// Create the render pipeline for an already opened window on the device of `main`
+    pub fn new(window: Arc<Window>, main: &RenderPipeline, config: &AppConfig) -> Result<Self, PipelineError> {
+        let pipeline = main.for_window(window.clone(), config)?;
+        let mut controls = UIControls::new(config);
+        let size = window.inner_size();
+        controls.set_screen_size(size.width as f32, size.height as f32);
+
+        Ok(ControlWindow {
+            window,
+            pipeline,
+            controls,
+            text: TextRenderer::new(),
+            cursor: (0.0, 0.0),
+            lines: Vec::new(),
+            states: [false; 4],
+            stale: false,
+            last_drawn: Instant::now(),
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Resize the surface to the window's current size
+    pub fn resize(&mut self) {
+        let size = self.window.inner_size();
+        self.pipeline.resize(size);
+        self.controls.set_screen_size(size.width as f32, size.height as f32);
+    }
+
+    /// Track the cursor for hover highlights and clicks
+    pub fn handle_mouse_move(&mut self, x: f32, y: f32) {
+        self.cursor = (x, y);
+        self.controls.handle_mouse_move(x, y);
+    }
+
+    /// Get the button under the cursor when the window is clicked
+    pub fn handle_mouse_click(&self) -> Option<ButtonAction> {
+        self.controls.handle_mouse_click(self.cursor.0, self.cursor.1)
+    }
+
+    /// Update button active states, see [`UIControls::update_states`], redrawing when they change
+    pub fn update_states(&mut self, is_playing: bool, slow_mode: bool, reduced_effects: bool, overlay_visible: bool) {
+        let states = [is_playing, slow_mode, reduced_effects, overlay_visible];
+        if states != self.states {
+            self.states = states;
+            self.controls
+                .update_states(is_playing, slow_mode, reduced_effects, overlay_visible);
+            self.window.request_redraw();
+        }
+    }
+
+    /// Set the statistics to show, redrawing when they changed and the last redraw is a while ago
+    pub fn set_lines(&mut self, lines: &[String], now: Instant) {
+        if lines != self.lines.as_slice() {
+            self.lines = lines.to_vec();
+            self.stale = true;
+        }
+        if self.stale && now.duration_since(self.last_drawn) >= STATS_REFRESH_INTERVAL {
+            self.window.request_redraw();
+        }
+    }
+
+    /// Draw the buttons and the statistics
+    pub fn render(&mut self, config: &AppConfig) -> Result<(), wgpu::SurfaceError> {
+        self.stale = false;
+        self.last_drawn = Instant::now();
+        let pipeline = &self.pipeline;
+        self.controls.update(pipeline);
+        self.text.begin(pipeline.size.width as f32, pipeline.size.height as f32);
+        draw_lines(&mut self.text, &self.lines);
+        self.text.update(pipeline);
+
+        let (output, mut encoder) = pipeline.begin_render(config.display.background_color)?;
+        {
+            let [r, g, b, a] = config.display.background_color;
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Control Window Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &output.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: r as f64,
+                            g: g as f64,
+                            b: b as f64,
+                            a: a as f64,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.controls.render(&mut render_pass, pipeline);
+            self.text.render(&mut render_pass, pipeline);
+        }
+
+        pipeline.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+/// Queue `lines` top to bottom, shrinking the text until all of them fit
+fn draw_lines(text: &mut TextRenderer, lines: &[String]) {
+    let mut scale = text.default_scale();
+    let line_height = |text: &TextRenderer, scale: f32| text.text_size("A", scale)[1] * (1.0 + LINE_GAP);
+    while scale > 1.0 && line_height(text, scale) * lines.len() as f32 > STATS_TOP - STATS_BOTTOM {
+        scale -= 1.0;
+    }
+
+    let step = line_height(text, scale);
+    let left = text.column_offset(2, scale);
+    for (i, line) in lines.iter().enumerate() {
+        text.draw_text(line, [left, STATS_TOP - i as f32 * step], scale, [0.85, 0.85, 0.85, 1.0]);
+    }
+}
@@ -1,13 +1,13 @@
 // src/ui/controls.rs
 
 use crate::config::AppConfig;
-use crate::midi::MidiPlayer;
-use crate::renderer::overlay::PerformanceOverlay;
-use crate::renderer::pipeline::RenderPipeline;
+use crate::renderer::pipeline::{cursor_to_normalized, RenderPipeline};
+use crate::ui::actions::PlayerAction;
 use crate::renderer::note_renderer::NoteInstance;
 use wgpu::util::DeviceExt;
 
-/// UI Button definition
+/// UI Button definition, positioned by its bottom-left corner in normalized screen space
+/// (see [`cursor_to_normalized`])
 #[derive(Debug, Clone, Copy)]
 pub struct Button {
     pub x: f32,
@@ -28,6 +28,24 @@ pub enum ButtonAction {
     DecreaseSpeed,
     ToggleSlowMode,
     ToggleOverlay,
+    CycleFrameLock,
+    ToggleReducedEffects,
+}
+
+impl ButtonAction {
+    /// Get the shared player action triggered by this button
+    pub fn player_action(self) -> PlayerAction {
+        match self {
+            ButtonAction::PlayPause => PlayerAction::TogglePlayback,
+            ButtonAction::Reset => PlayerAction::Reset,
+            ButtonAction::IncreaseSpeed => PlayerAction::IncreaseSpeed,
+            ButtonAction::DecreaseSpeed => PlayerAction::DecreaseSpeed,
+            ButtonAction::ToggleSlowMode => PlayerAction::ToggleSlowMode,
+            ButtonAction::ToggleOverlay => PlayerAction::ToggleOverlay,
+            ButtonAction::CycleFrameLock => PlayerAction::CycleFrameLock,
+            ButtonAction::ToggleReducedEffects => PlayerAction::ToggleReducedEffects,
+        }
+    }
 }
 
 impl Button {
@@ -55,6 +73,7 @@ impl Button {
                 ButtonAction::PlayPause => [0.2, 0.7, 0.2, 0.9], // Green when playing
                 ButtonAction::ToggleSlowMode => [0.7, 0.5, 0.2, 0.9], // Orange when slow mode
                 ButtonAction::ToggleOverlay => [0.2, 0.5, 0.7, 0.9], // Blue when overlay visible
+                ButtonAction::ToggleReducedEffects => [0.6, 0.4, 0.7, 0.9], // Purple when effects reduced
                 _ => [0.4, 0.4, 0.6, 0.9],
             }
         } else if self.is_hovered {
@@ -103,6 +122,10 @@ impl UIControls {
             Button::new(start_x + 4.0 * (button_width + button_spacing), start_y, button_width, button_height, ButtonAction::ToggleSlowMode),
             // Overlay toggle
             Button::new(start_x + 5.0 * (button_width + button_spacing), start_y, button_width, button_height, ButtonAction::ToggleOverlay),
+            // FPS cap cycle
+            Button::new(start_x + 6.0 * (button_width + button_spacing), start_y, button_width, button_height, ButtonAction::CycleFrameLock),
+            // Reduced effects toggle
+            Button::new(start_x + 7.0 * (button_width + button_spacing), start_y, button_width, button_height, ButtonAction::ToggleReducedEffects),
         ];
 
         UIControls {
@@ -116,6 +139,7 @@ impl UIControls {
     }
 
     /// Update screen size for coordinate conversion
+    /// Must be the physical window size, matching the cursor positions given to the mouse handlers
     pub fn set_screen_size(&mut self, width: f32, height: f32) {
         self.screen_width = width;
         self.screen_height = height;
@@ -132,10 +156,9 @@ impl UIControls {
     }
 
     /// Handle mouse move to update hover states
+    /// `x` and `y` are the cursor position in physical pixels from the top-left corner
     pub fn handle_mouse_move(&mut self, x: f32, y: f32) {
-        // Convert screen coordinates to normalized coordinates
-        let norm_x = x / self.screen_width;
-        let norm_y = 1.0 - (y / self.screen_height); // Flip Y coordinate
+        let (norm_x, norm_y) = cursor_to_normalized(x, y, self.screen_width, self.screen_height);
 
         for button in &mut self.buttons {
             button.is_hovered = button.contains(norm_x, norm_y);
@@ -143,14 +166,13 @@ impl UIControls {
     }
 
     /// Handle mouse click and return the action if any button was clicked
+    /// `x` and `y` are the cursor position in physical pixels from the top-left corner
     pub fn handle_mouse_click(&self, x: f32, y: f32) -> Option<ButtonAction> {
         if !self.visible {
             return None;
         }
 
-        // Convert screen coordinates to normalized coordinates
-        let norm_x = x / self.screen_width;
-        let norm_y = 1.0 - (y / self.screen_height); // Flip Y coordinate
+        let (norm_x, norm_y) = cursor_to_normalized(x, y, self.screen_width, self.screen_height);
 
         for button in &self.buttons {
             if button.contains(norm_x, norm_y) {
@@ -161,60 +183,24 @@ impl UIControls {
     }
 
     /// Update button active states based on current application state
-    pub fn update_states(&mut self, is_playing: bool, slow_mode: bool, overlay_visible: bool) {
+    pub fn update_states(
+        &mut self,
+        is_playing: bool,
+        slow_mode: bool,
+        reduced_effects: bool,
+        overlay_visible: bool,
+    ) {
         for button in &mut self.buttons {
             button.is_active = match button.action {
                 ButtonAction::PlayPause => is_playing,
                 ButtonAction::ToggleSlowMode => slow_mode,
+                ButtonAction::ToggleReducedEffects => reduced_effects,
                 ButtonAction::ToggleOverlay => overlay_visible,
                 _ => false,
             };
         }
     }
 
-    /// Apply a button action to the application state
-    pub fn apply_action(
-        action: ButtonAction,
-        player: &mut MidiPlayer,
-        overlay: &mut PerformanceOverlay,
-        config: &mut AppConfig,
-    ) {
-        match action {
-            ButtonAction::PlayPause => {
-                player.toggle_playback();
-                log::debug!("UI: Playback: {}", if player.is_playing() { "Playing" } else { "Paused" });
-            }
-            ButtonAction::Reset => {
-                player.reset();
-                log::debug!("UI: Playback reset to start");
-            }
-            ButtonAction::IncreaseSpeed => {
-                player.increase_speed();
-                log::debug!("UI: Speed: {:.1}x", player.get_playback_speed());
-            }
-            ButtonAction::DecreaseSpeed => {
-                player.decrease_speed();
-                log::debug!("UI: Speed: {:.1}x", player.get_playback_speed());
-            }
-            ButtonAction::ToggleSlowMode => {
-                config.performance.slow_mode = !config.performance.slow_mode;
-                if config.performance.slow_mode {
-                    config.performance.frame_lock = Some(30);
-                    config.quality.particle_density = 0.5;
-                } else {
-                    config.performance.frame_lock = None;
-                    config.quality.particle_density = 1.0;
-                }
-                log::debug!("UI: Slow mode: {}", if config.performance.slow_mode { "Enabled" } else { "Disabled" });
-            }
-            ButtonAction::ToggleOverlay => {
-                overlay.toggle();
-                config.performance.enable_performance_overlay = overlay.is_visible();
-                log::debug!("UI: Overlay: {}", if overlay.is_visible() { "Visible" } else { "Hidden" });
-            }
-        }
-    }
-
     /// Update the instance buffer for rendering
     pub fn update(&mut self, pipeline: &RenderPipeline) {
         if !self.visible {
@@ -228,6 +214,7 @@ impl UIControls {
                 position: [button.x, button.y],
                 size: [button.width, button.height],
                 color: button.get_color(),
+                ..Default::default()
             })
             .collect();
 
@@ -290,6 +277,8 @@ impl UIControls {
                     ButtonAction::DecreaseSpeed => "-",
                     ButtonAction::ToggleSlowMode => "🐢",
                     ButtonAction::ToggleOverlay => "📊",
+                    ButtonAction::CycleFrameLock => "⏱",
+                    ButtonAction::ToggleReducedEffects => "✨",
                 };
                 (label, button.x + button.width / 2.0, button.y + button.height / 2.0)
             })
@@ -302,3 +291,30 @@ impl Default for UIControls {
         Self::new(&AppConfig::default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_hits_button_at_pixel() {
+        let mut controls = UIControls::default();
+        controls.set_screen_size(1920.0, 1080.0);
+
+        // Center of the first button: x = 0.04, y = 0.9575 from the bottom -> 46px from the top
+        assert_eq!(controls.handle_mouse_click(76.8, 45.9), Some(ButtonAction::PlayPause));
+        // The same distance from the bottom edge is empty space
+        assert_eq!(controls.handle_mouse_click(76.8, 1080.0 - 45.9), None);
+    }
+
+    #[test]
+    fn test_click_hits_button_near_bottom() {
+        let mut controls = UIControls::default();
+        controls.set_screen_size(1000.0, 500.0);
+        controls.buttons[0].y = 0.02;
+
+        // Button spans 0.02-0.055 from the bottom, i.e. 472.5-490px from the top
+        assert_eq!(controls.handle_mouse_click(40.0, 480.0), Some(ButtonAction::PlayPause));
+        assert_eq!(controls.handle_mouse_click(40.0, 20.0), None);
+    }
+}
@@ -0,0 +1,138 @@
+// src/ui/density_gauge.rs
+
+use crate::config::AppConfig;
+use crate::midi::Note;
+use crate::performance::notes_per_second;
+use crate::renderer::text_renderer::TextRenderer;
+
+/// Left edge of the gauge (normalized)
+const GAUGE_LEFT: f32 = 0.01;
+/// Width of the gauge (normalized)
+const GAUGE_WIDTH: f32 = 0.012;
+/// Gap between the piano and the bottom of the gauge (normalized)
+const GAUGE_BOTTOM_MARGIN: f32 = 0.03;
+/// Top edge of the gauge (normalized), clear of the UI controls
+const GAUGE_TOP: f32 = 0.75;
+const GAUGE_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+/// Colors of the heat ramp from no notes to the song's densest second
+const HEAT_STOPS: [[f32; 3]; 5] = [
+    [0.1, 0.3, 1.0],
+    [0.0, 0.85, 0.9],
+    [0.2, 0.9, 0.2],
+    [1.0, 0.85, 0.1],
+    [1.0, 0.15, 0.05],
+];
+
+/// Shows how many notes per second pass the playhead, ramping from cool to hot
+///
+/// The gauge fills relative to the densest second of the song on a logarithmic scale, so
+/// quiet passages still register next to the walls of notes in Black MIDI files.
+pub struct DensityGauge {
+    visible: bool,
+    /// Most notes started within one second anywhere in the song
+    peak: usize,
+}
+
+impl DensityGauge {
+    pub fn new(config: &AppConfig) -> Self {
+        DensityGauge {
+            visible: config.display.show_density_gauge,
+            peak: 0,
+        }
+    }
+
+    /// Find the densest second of a newly loaded song; `notes` must be sorted by start time
+    pub fn set_notes(&mut self, notes: &[Note]) {
+        let mut first = 0;
+        self.peak = 0;
+        for (last, note) in notes.iter().enumerate() {
+            while notes[first].start_time <= note.start_time - 1.0 {
+                first += 1;
+            }
+            self.peak = self.peak.max(last + 1 - first);
+        }
+    }
+
+    pub fn toggle_visibility(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Get how full the gauge is at a density of `notes_per_second`, from 0 to 1
+    fn level(&self, notes_per_second: usize) -> f32 {
+        if self.peak == 0 {
+            return 0.0;
+        }
+        ((notes_per_second as f32).ln_1p() / (self.peak as f32).ln_1p()).min(1.0)
+    }
+
+    /// Queue the gauge for the density at `time` between the piano at `bottom` and the controls
+    pub fn draw(&self, text: &mut TextRenderer, notes: &[Note], time: f32, bottom: f32) {
+        if !self.visible {
+            return;
+        }
+
+        let density = notes_per_second(notes, time);
+        let level = self.level(density);
+        let bottom = bottom + GAUGE_BOTTOM_MARGIN;
+        let height = (GAUGE_TOP - bottom).max(0.0);
+        text.draw_rect([GAUGE_LEFT, bottom], [GAUGE_WIDTH, height], GAUGE_BACKGROUND);
+        if level > 0.0 {
+            let [r, g, b] = heat_color(level);
+            text.draw_rect([GAUGE_LEFT, bottom], [GAUGE_WIDTH, height * level], [r, g, b, 0.9]);
+        }
+
+        let scale = (text.default_scale() * 0.5).round().max(1.0);
+        let label = format!("{}/s", density);
+        let line_height = text.text_size(&label, scale)[1];
+        text.draw_text(&label, [GAUGE_LEFT, GAUGE_TOP + line_height * 1.5], scale, TEXT_COLOR);
+    }
+}
+
+/// Get the color of the heat ramp at `t`, from blue at 0 through green to red at 1
+fn heat_color(t: f32) -> [f32; 3] {
+    let position = t.clamp(0.0, 1.0) * (HEAT_STOPS.len() - 1) as f32;
+    let index = (position as usize).min(HEAT_STOPS.len() - 2);
+    let fraction = position - index as f32;
+    let (from, to) = (HEAT_STOPS[index], HEAT_STOPS[index + 1]);
+    [0, 1, 2].map(|channel| from[channel] * (1.0 - fraction) + to[channel] * fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_density_level() {
+        // One note per second, then ten within the second after 5s
+        let mut notes: Vec<Note> = (0..5).map(|i| Note::new(60, 100, i as f32, 0.5, 0)).collect();
+        notes.extend((0..10).map(|i| Note::new(62, 100, 5.0 + i as f32 * 0.05, 0.5, 0)));
+        let mut gauge = DensityGauge::new(&AppConfig::default());
+        gauge.set_notes(&notes);
+        assert_eq!(gauge.peak, 10);
+
+        assert_eq!(gauge.level(0), 0.0);
+        assert_eq!(gauge.level(10), 1.0);
+        assert_eq!(gauge.level(notes_per_second(&notes, 5.5)), 1.0);
+        let quiet = gauge.level(notes_per_second(&notes, 2.5));
+        assert!(quiet > 0.2 && quiet < 0.5, "{}", quiet);
+
+        // No notes leave the gauge empty
+        gauge.set_notes(&[]);
+        assert_eq!(gauge.level(5), 0.0);
+    }
+
+    #[test]
+    fn test_heat_color_ramp() {
+        assert_eq!(heat_color(0.0), HEAT_STOPS[0]);
+        assert_eq!(heat_color(1.0), HEAT_STOPS[4]);
+        assert_eq!(heat_color(2.0), HEAT_STOPS[4]);
+        assert_eq!(heat_color(0.5), HEAT_STOPS[2]);
+        // Hotter densities get redder
+        assert!(heat_color(0.9)[0] > heat_color(0.3)[0]);
+    }
+}
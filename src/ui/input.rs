@@ -1,44 +1,91 @@
 // src/ui/input.rs
 
-use crate::config::AppConfig;
-use crate::midi::MidiPlayer;
-use crate::renderer::overlay::PerformanceOverlay;
-use winit::event::{ElementState, KeyEvent, MouseButton, WindowEvent};
-use winit::keyboard::{Key, NamedKey};
+use crate::config::ControlsConfig;
+use crate::ui::actions::PlayerAction;
+use std::time::{Duration, Instant};
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
 
 /// Actions that can be triggered by input
 #[derive(Debug, Clone, PartialEq)]
 pub enum InputAction {
     /// No action
     None,
-    /// Toggle play/pause
-    TogglePlayback,
-    /// Increase playback speed
-    IncreaseSpeed,
-    /// Decrease playback speed
-    DecreaseSpeed,
-    /// Toggle performance overlay
-    ToggleOverlay,
-    /// Toggle slow mode
-    ToggleSlowMode,
-    /// Reset playback to start
-    Reset,
+    /// Playback or display action shared with the UI buttons
+    Player(PlayerAction),
+    /// Raise the minimum note duration filter
+    IncreaseMinDuration,
+    /// Lower the minimum note duration filter
+    DecreaseMinDuration,
+    /// Toggle the song overview minimap
+    ToggleMinimap,
+    /// Toggle the note density gauge
+    ToggleDensityGauge,
+    /// Fit the key range to the keys the loaded song plays
+    FitKeyRange,
+    /// Toggle the histogram of how often each key is played
+    TogglePitchHistogram,
+    /// Focus the right hand, the left hand or both for practicing
+    CycleHandFocus,
+    /// Turn frustum culling off or back on, to compare against drawing every note when
+    /// looking into pop-in
+    ToggleFrustumCulling,
+    /// Toggle the octave guide lines and labels
+    ToggleOctaveLines,
+    /// Toggle the lyrics of karaoke files
+    ToggleLyrics,
+    /// Toggle stacking tracks in separate lanes
+    ToggleTrackLanes,
+    /// Show or hide the piano keyboard
+    TogglePiano,
+    /// Seek to the next section marker
+    NextMarker,
+    /// Seek to the previous section marker
+    PreviousMarker,
+    /// Mute or unmute a MIDI channel (0-15)
+    ToggleChannelMute(u8),
+    /// Solo a MIDI channel (0-15) or take its solo back
+    ToggleChannelSolo(u8),
+    /// Switch muted channels between ghosted and hidden
+    ToggleGhostMode,
+    /// Save the loaded notes of unmuted channels to a MIDI file
+    ExportNotes,
+    /// Switch to the next file in the playlist
+    NextFile,
+    /// Switch to the previous file in the playlist
+    PreviousFile,
     /// Request to open a file
     OpenFile,
     /// Toggle fullscreen
     ToggleFullscreen,
     /// Quit application
     Quit,
-    /// Window resize
+    /// Window resize, in physical pixels
     Resize(u32, u32),
+    /// The window moved to a display with a different DPI scale factor
+    ScaleFactorChanged(f64),
+    /// Window gained (true) or lost (false) focus
+    FocusChanged(bool),
     /// File dropped
     FileDropped(std::path::PathBuf),
-    /// Mouse moved
+    /// Mouse moved, in physical pixels from the top-left corner
     MouseMoved(f64, f64),
-    /// Mouse clicked
+    /// Mouse clicked, in physical pixels from the top-left corner
     MouseClicked(f64, f64),
+    /// Seek by this many seconds relative to the current position (touch drag or trackpad swipe)
+    Seek(f32),
+    /// Start or stop playing backward, for as long as the rewind key is held
+    Rewind(bool),
+    /// Multiply the visible time window by this factor (pinch); below 1.0 zooms in
+    Zoom(f32),
 }
 
+/// Seconds seeked by dragging across the full width of the window
+const DRAG_SEEK_SECONDS: f32 = 10.0;
+
+/// How long a key is held before it starts repeating
+const KEY_REPEAT_DELAY: Duration = Duration::from_millis(350);
+
 /// Handles all input for the application
 pub struct InputHandler {
     /// Whether a file is being dragged over the window
@@ -48,6 +95,16 @@ pub struct InputHandler {
     /// Current mouse position
     mouse_x: f64,
     mouse_y: f64,
+    /// Window width in physical pixels, for converting drags to seek times
+    screen_width: f64,
+    /// Active touch points: (finger id, position)
+    touches: Vec<(u64, (f64, f64))>,
+    /// Keyboard control settings
+    controls: ControlsConfig,
+    /// Held keys that repeat their action and when they fire next
+    held_keys: Vec<(NamedKey, Instant)>,
+    /// Whether Shift is held, which turns the channel mute keys into solo keys
+    shift_held: bool,
 }
 
 impl InputHandler {
@@ -58,6 +115,19 @@ impl InputHandler {
             fullscreen: false,
             mouse_x: 0.0,
             mouse_y: 0.0,
+            screen_width: 1920.0,
+            touches: Vec::new(),
+            controls: ControlsConfig::default(),
+            held_keys: Vec::new(),
+            shift_held: false,
+        }
+    }
+
+    /// Apply the keyboard control settings
+    pub fn set_controls(&mut self, controls: &ControlsConfig) {
+        self.controls = controls.clone();
+        if !self.controls.key_repeat {
+            self.held_keys.clear();
         }
     }
 
@@ -65,8 +135,28 @@ impl InputHandler {
     pub fn process_event(&mut self, event: &WindowEvent) -> InputAction {
         match event {
             WindowEvent::KeyboardInput { event, .. } => self.process_key_event(event),
+
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.shift_held = modifiers.state().shift_key();
+                InputAction::None
+            }
             
-            WindowEvent::Resized(size) => InputAction::Resize(size.width, size.height),
+            WindowEvent::Resized(size) => {
+                self.screen_width = size.width.max(1) as f64;
+                InputAction::Resize(size.width, size.height)
+            }
+
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                InputAction::ScaleFactorChanged(*scale_factor)
+            }
+
+            WindowEvent::Focused(focused) => {
+                // Key releases are not delivered while unfocused
+                if !focused {
+                    self.held_keys.clear();
+                }
+                InputAction::FocusChanged(*focused)
+            }
             
             WindowEvent::DroppedFile(path) => {
                 self.file_hovered = false;
@@ -91,6 +181,19 @@ impl InputHandler {
                 InputAction::MouseMoved(position.x, position.y)
             }
 
+            WindowEvent::Touch(touch) => {
+                self.process_touch(touch.id, touch.phase, touch.location.x, touch.location.y)
+            }
+
+            // Pinching out on the trackpad magnifies, i.e. shows a shorter time window
+            WindowEvent::TouchpadMagnify { delta, .. } => InputAction::Zoom(1.0 / (1.0 + *delta as f32).max(0.1)),
+
+            // Horizontal two-finger swipes on a trackpad seek like a touch drag
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(position),
+                ..
+            } if position.x.abs() > position.y.abs() => self.drag_to_seek(position.x),
+
             WindowEvent::MouseInput { state, button, .. } => {
                 if *state == ElementState::Pressed && *button == MouseButton::Left {
                     InputAction::MouseClicked(self.mouse_x, self.mouse_y)
@@ -105,25 +208,76 @@ impl InputHandler {
 
     /// Process a keyboard event
     fn process_key_event(&mut self, event: &KeyEvent) -> InputAction {
+        if event.logical_key == Key::Named(NamedKey::Backspace) {
+            return match event.state {
+                ElementState::Pressed if !event.repeat => InputAction::Rewind(true),
+                ElementState::Pressed => InputAction::None,
+                ElementState::Released => InputAction::Rewind(false),
+            };
+        }
+
+        if let Key::Named(key) = event.logical_key {
+            if let Some(action) = self.repeatable_action(key) {
+                return match event.state {
+                    ElementState::Pressed if self.press_repeatable(key, event.repeat, Instant::now()) => action,
+                    ElementState::Pressed => InputAction::None,
+                    ElementState::Released => {
+                        self.held_keys.retain(|&(held, _)| held != key);
+                        InputAction::None
+                    }
+                };
+            }
+        }
+
         // Only handle key press events
         if event.state != ElementState::Pressed {
             return InputAction::None;
         }
 
+        // Shift turns the digit typed into another character, so solo keys go by key position
+        if self.shift_held {
+            if let Some(channel) = Self::digit_channel(event.physical_key) {
+                return InputAction::ToggleChannelSolo(channel);
+            }
+        }
+
         match &event.logical_key {
-            Key::Named(NamedKey::Space) => InputAction::TogglePlayback,
-            Key::Named(NamedKey::ArrowUp) => InputAction::IncreaseSpeed,
-            Key::Named(NamedKey::ArrowDown) => InputAction::DecreaseSpeed,
+            Key::Named(NamedKey::Space) => InputAction::Player(PlayerAction::TogglePlayback),
+            Key::Named(NamedKey::PageDown) => InputAction::NextFile,
+            Key::Named(NamedKey::PageUp) => InputAction::PreviousFile,
             Key::Named(NamedKey::Escape) => InputAction::Quit,
+            Key::Named(NamedKey::F9) => InputAction::ToggleFrustumCulling,
             Key::Named(NamedKey::F11) => {
                 self.fullscreen = !self.fullscreen;
                 InputAction::ToggleFullscreen
             }
             Key::Character(c) => match c.as_str() {
-                "p" | "P" => InputAction::ToggleOverlay,
-                "s" | "S" => InputAction::ToggleSlowMode,
-                "r" | "R" => InputAction::Reset,
+                "p" | "P" => InputAction::Player(PlayerAction::ToggleOverlay),
+                "s" | "S" => InputAction::Player(PlayerAction::ToggleSlowMode),
+                "f" | "F" => InputAction::Player(PlayerAction::CycleFrameLock),
+                "e" | "E" => InputAction::Player(PlayerAction::ToggleReducedEffects),
+                "r" | "R" => InputAction::Player(PlayerAction::Reset),
                 "o" | "O" => InputAction::OpenFile,
+                "m" | "M" => InputAction::ToggleMinimap,
+                "d" | "D" => InputAction::ToggleDensityGauge,
+                "j" | "J" => InputAction::CycleHandFocus,
+                "u" | "U" => InputAction::TogglePitchHistogram,
+                "a" | "A" => InputAction::FitKeyRange,
+                "l" | "L" => InputAction::ToggleOctaveLines,
+                "k" | "K" => InputAction::ToggleLyrics,
+                "t" | "T" => InputAction::ToggleTrackLanes,
+                "h" | "H" => InputAction::TogglePiano,
+                "." => InputAction::NextMarker,
+                "," => InputAction::PreviousMarker,
+                "g" | "G" => InputAction::ToggleGhostMode,
+                "x" | "X" => InputAction::ExportNotes,
+                // 1-9 mute channels 1-9, 0 mutes channel 10 (drums)
+                "0" => InputAction::ToggleChannelMute(9),
+                digit @ ("1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9") => {
+                    InputAction::ToggleChannelMute(digit.as_bytes()[0] - b'1')
+                }
+                "]" => InputAction::IncreaseMinDuration,
+                "[" => InputAction::DecreaseMinDuration,
                 "q" | "Q" => InputAction::Quit,
                 _ => InputAction::None,
             },
@@ -131,50 +285,122 @@ impl InputHandler {
         }
     }
 
-    /// Apply an action to the application state
-    pub fn apply_action(
-        action: &InputAction,
-        player: &mut MidiPlayer,
-        overlay: &mut PerformanceOverlay,
-        config: &mut AppConfig,
-    ) {
-        match action {
-            InputAction::TogglePlayback => {
-                player.toggle_playback();
-                log::debug!("Playback: {}", if player.is_playing() { "Playing" } else { "Paused" });
-            }
-            InputAction::IncreaseSpeed => {
-                player.increase_speed();
-                log::debug!("Speed: {:.1}x", player.get_playback_speed());
-            }
-            InputAction::DecreaseSpeed => {
-                player.decrease_speed();
-                log::debug!("Speed: {:.1}x", player.get_playback_speed());
-            }
-            InputAction::ToggleOverlay => {
-                overlay.toggle();
-                config.performance.enable_performance_overlay = overlay.is_visible();
-                log::debug!("Overlay: {}", if overlay.is_visible() { "Visible" } else { "Hidden" });
-            }
-            InputAction::ToggleSlowMode => {
-                config.performance.slow_mode = !config.performance.slow_mode;
-                if config.performance.slow_mode {
-                    config.performance.frame_lock = Some(30);
-                    config.quality.particle_density = 0.5;
-                } else {
-                    config.performance.frame_lock = None;
-                    config.quality.particle_density = 1.0;
+    /// Get the channel of a digit key position: 1-9 are channels 1-9, 0 is channel 10 (drums)
+    fn digit_channel(key: PhysicalKey) -> Option<u8> {
+        let PhysicalKey::Code(code) = key else {
+            return None;
+        };
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+            KeyCode::Digit0,
+        ];
+        digits.iter().position(|&digit| digit == code).map(|index| index as u8)
+    }
+
+    /// Get the action of a key that keeps adjusting while held: speed and seeking
+    fn repeatable_action(&self, key: NamedKey) -> Option<InputAction> {
+        match key {
+            NamedKey::ArrowUp => Some(InputAction::Player(PlayerAction::IncreaseSpeed)),
+            NamedKey::ArrowDown => Some(InputAction::Player(PlayerAction::DecreaseSpeed)),
+            NamedKey::ArrowRight => Some(InputAction::Seek(self.controls.seek_step)),
+            NamedKey::ArrowLeft => Some(InputAction::Seek(-self.controls.seek_step)),
+            _ => None,
+        }
+    }
+
+    /// Handle a press of a repeatable key and return whether it fires its action
+    ///
+    /// With `controls.key_repeat` the first press fires and starts tracking the key, and the
+    /// system's own repeats are ignored in favor of [`Self::repeat_actions`]; without it every
+    /// press and system repeat fires.
+    fn press_repeatable(&mut self, key: NamedKey, system_repeat: bool, now: Instant) -> bool {
+        if !self.controls.key_repeat {
+            return true;
+        }
+        if system_repeat || self.held_keys.iter().any(|&(held, _)| held == key) {
+            return false;
+        }
+        self.held_keys.push((key, now + KEY_REPEAT_DELAY));
+        true
+    }
+
+    /// Check whether a repeating key is held, so the event loop keeps running frames
+    pub fn has_held_keys(&self) -> bool {
+        !self.held_keys.is_empty()
+    }
+
+    /// Get the actions of the held keys that are due by `now`, at `controls.key_repeat_rate`
+    pub fn repeat_actions(&mut self, now: Instant) -> Vec<InputAction> {
+        let interval = Duration::from_secs_f32(1.0 / self.controls.key_repeat_rate.clamp(1.0, 120.0));
+        let mut actions = Vec::new();
+        for i in 0..self.held_keys.len() {
+            let (key, mut next) = self.held_keys[i];
+            while next <= now {
+                actions.extend(self.repeatable_action(key));
+                next += interval;
+            }
+            self.held_keys[i].1 = next;
+        }
+        actions
+    }
+
+    /// Track touch points: dragging one finger sideways seeks, pinching two fingers zooms
+    fn process_touch(&mut self, id: u64, phase: TouchPhase, x: f64, y: f64) -> InputAction {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.push((id, (x, y)));
+                InputAction::None
+            }
+            TouchPhase::Moved => {
+                let Some(index) = self.touches.iter().position(|&(touch_id, _)| touch_id == id) else {
+                    return InputAction::None;
+                };
+                let (old_x, _) = self.touches[index].1;
+                let old_distance = self.pinch_distance();
+                self.touches[index].1 = (x, y);
+
+                match self.touches.len() {
+                    1 => self.drag_to_seek(x - old_x),
+                    2 => match (old_distance, self.pinch_distance()) {
+                        (Some(old), Some(new)) if old > 1.0 && new > 1.0 => {
+                            InputAction::Zoom((old / new) as f32)
+                        }
+                        _ => InputAction::None,
+                    },
+                    _ => InputAction::None,
                 }
-                log::debug!("Slow mode: {}", if config.performance.slow_mode { "Enabled" } else { "Disabled" });
             }
-            InputAction::Reset => {
-                player.reset();
-                log::debug!("Playback reset to start");
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.retain(|&(touch_id, _)| touch_id != id);
+                InputAction::None
             }
-            _ => {}
         }
     }
 
+    /// Distance between the two active touch points, if exactly two are down
+    fn pinch_distance(&self) -> Option<f64> {
+        match self.touches.as_slice() {
+            [(_, (x1, y1)), (_, (x2, y2))] => Some((x2 - x1).hypot(y2 - y1)),
+            _ => None,
+        }
+    }
+
+    /// Convert a horizontal drag in pixels to a seek; dragging right moves forward
+    fn drag_to_seek(&self, dx: f64) -> InputAction {
+        if dx == 0.0 {
+            return InputAction::None;
+        }
+        InputAction::Seek((dx / self.screen_width) as f32 * DRAG_SEEK_SECONDS)
+    }
+
     /// Check if a file is being hovered over the window
     pub fn is_file_hovered(&self) -> bool {
         self.file_hovered
@@ -185,6 +411,11 @@ impl InputHandler {
         self.fullscreen
     }
 
+    /// Set whether fullscreen is enabled, e.g. when starting in fullscreen
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+
     /// Get current mouse position
     pub fn mouse_position(&self) -> (f64, f64) {
         (self.mouse_x, self.mouse_y)
@@ -196,3 +427,65 @@ impl Default for InputHandler {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touch_drag_seeks() {
+        let mut input = InputHandler::new();
+        assert_eq!(input.process_touch(1, TouchPhase::Started, 100.0, 500.0), InputAction::None);
+        // Half the default 1920px width forward
+        assert_eq!(
+            input.process_touch(1, TouchPhase::Moved, 1060.0, 500.0),
+            InputAction::Seek(DRAG_SEEK_SECONDS * 0.5)
+        );
+        input.process_touch(1, TouchPhase::Ended, 1060.0, 500.0);
+        assert_eq!(input.process_touch(1, TouchPhase::Moved, 0.0, 500.0), InputAction::None);
+    }
+
+    #[test]
+    fn test_pinch_zooms() {
+        let mut input = InputHandler::new();
+        input.process_touch(1, TouchPhase::Started, 100.0, 100.0);
+        input.process_touch(2, TouchPhase::Started, 200.0, 100.0);
+        // Spreading the fingers to twice the distance halves the time window
+        assert_eq!(input.process_touch(2, TouchPhase::Moved, 300.0, 100.0), InputAction::Zoom(0.5));
+    }
+
+    #[test]
+    fn test_held_keys_repeat() {
+        let mut input = InputHandler::new();
+        let start = Instant::now();
+        let speed_up = InputAction::Player(PlayerAction::IncreaseSpeed);
+
+        assert!(input.press_repeatable(NamedKey::ArrowUp, false, start));
+        // The system's repeats are replaced by our own
+        assert!(!input.press_repeatable(NamedKey::ArrowUp, true, start + Duration::from_millis(50)));
+        assert!(input.repeat_actions(start + Duration::from_millis(100)).is_empty());
+
+        // After the delay, one action per repeat interval (15 per second by default)
+        let actions = input.repeat_actions(start + KEY_REPEAT_DELAY + Duration::from_millis(180));
+        assert_eq!(actions, vec![speed_up; 3]);
+        assert!(input.repeat_actions(start + KEY_REPEAT_DELAY + Duration::from_millis(190)).is_empty());
+
+        input.process_event(&WindowEvent::Focused(false));
+        assert!(!input.has_held_keys());
+
+        input.set_controls(&ControlsConfig {
+            key_repeat: false,
+            ..Default::default()
+        });
+        assert!(input.press_repeatable(NamedKey::ArrowUp, true, start));
+        assert!(!input.has_held_keys());
+    }
+
+    #[test]
+    fn test_digit_channels() {
+        assert_eq!(InputHandler::digit_channel(PhysicalKey::Code(KeyCode::Digit1)), Some(0));
+        assert_eq!(InputHandler::digit_channel(PhysicalKey::Code(KeyCode::Digit9)), Some(8));
+        assert_eq!(InputHandler::digit_channel(PhysicalKey::Code(KeyCode::Digit0)), Some(9));
+        assert_eq!(InputHandler::digit_channel(PhysicalKey::Code(KeyCode::KeyA)), None);
+    }
+}
@@ -0,0 +1,157 @@
+// src/ui/legend.rs
+
+use crate::config::AppConfig;
+use crate::midi::{Note, TrackInfo};
+use crate::renderer::text_renderer::TextRenderer;
+
+/// Right edge of the legend (normalized), clear of the minimap and its tempo labels
+const LEGEND_RIGHT: f32 = 0.92;
+/// Top edge of the legend (normalized)
+const LEGEND_TOP: f32 = 0.97;
+/// Longest track name shown, longer ones are cut off
+const LEGEND_MAX_NAME: usize = 24;
+/// Swatch color of muted channels
+const MUTED_SWATCH: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+const MUTED_TEXT_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 0.9];
+
+/// A channel used by the loaded song
+#[derive(Debug, Clone, PartialEq)]
+struct LegendEntry {
+    channel: u8,
+    /// Name of the lowest-numbered track playing on the channel
+    name: Option<String>,
+}
+
+/// Shows which note color is which channel, named after the tracks playing on it
+pub struct ChannelLegend {
+    entries: Vec<LegendEntry>,
+}
+
+impl ChannelLegend {
+    pub fn new() -> Self {
+        ChannelLegend { entries: Vec::new() }
+    }
+
+    /// List the channels of a newly loaded song, naming each after the lowest-numbered track using it
+    pub fn set_song(&mut self, notes: &[Note], tracks: &[TrackInfo]) {
+        let mut first_track: [Option<u16>; 16] = [None; 16];
+        for note in notes {
+            let slot = &mut first_track[(note.channel & 0x0F) as usize];
+            if slot.is_none_or(|track| note.track_index < track) {
+                *slot = Some(note.track_index);
+            }
+        }
+
+        self.entries = (0..16u8)
+            .filter_map(|channel| {
+                let track = first_track[channel as usize]?;
+                let name = tracks
+                    .get(track as usize)
+                    .and_then(TrackInfo::label)
+                    .map(|name| name.chars().take(LEGEND_MAX_NAME).collect());
+                Some(LegendEntry { channel, name })
+            })
+            .collect();
+    }
+
+    /// Queue the legend in the top-right corner, marking soloed and muted channels
+    pub fn draw(&self, text: &mut TextRenderer, config: &AppConfig) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let channels = &config.channels;
+        let labels: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut label = format!("Ch {}", entry.channel + 1);
+                if let Some(name) = &entry.name {
+                    label.push(' ');
+                    label.push_str(name);
+                }
+                if channels.is_soloed(entry.channel) {
+                    label.push_str(" (solo)");
+                } else if channels.is_muted(entry.channel) {
+                    label.push_str(" (muted)");
+                }
+                label
+            })
+            .collect();
+
+        let scale = (text.default_scale() * 0.5).round().max(1.0);
+        let line_height = text.text_size("X", scale)[1];
+        let spacing = line_height * 0.5;
+        let swatch = [line_height * text.aspect(), line_height];
+        let text_width = labels.iter().map(|label| text.text_size(label, scale)[0]).fold(0.0, f32::max);
+        let width = swatch[0] + spacing * text.aspect() + text_width;
+        let height = labels.len() as f32 * (line_height + spacing) - spacing;
+        let left = LEGEND_RIGHT - width;
+        let margin = [spacing * text.aspect(), spacing];
+
+        text.draw_rect(
+            [left - margin[0], LEGEND_TOP - height - margin[1]],
+            [width + margin[0] * 2.0, height + margin[1] * 2.0],
+            [0.0, 0.0, 0.0, 0.6],
+        );
+
+        let mut top = LEGEND_TOP;
+        for (entry, label) in self.entries.iter().zip(&labels) {
+            let muted = channels.is_muted(entry.channel);
+            let color = if muted {
+                MUTED_SWATCH
+            } else {
+                // The color of a loud note on the channel
+                Note::new(0, 127, 0.0, 0.0, entry.channel).get_color_with_params(1.0, &config.display.note_colors)
+            };
+            text.draw_rect([left, top - line_height], swatch, color);
+            text.draw_text(
+                label,
+                [left + swatch[0] + spacing * text.aspect(), top],
+                scale,
+                if muted { MUTED_TEXT_COLOR } else { TEXT_COLOR },
+            );
+            top -= line_height + spacing;
+        }
+    }
+}
+
+impl Default for ChannelLegend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legend_entries() {
+        let mut notes = vec![
+            Note::new(60, 100, 0.0, 1.0, 9),
+            Note::new(60, 100, 0.0, 1.0, 0),
+            Note::new(64, 100, 1.0, 1.0, 0),
+        ];
+        notes[0].track_index = 2;
+        notes[1].track_index = 1;
+        notes[2].track_index = 0;
+        let tracks = vec![
+            TrackInfo { name: Some("Melody".to_string()), instrument: None },
+            TrackInfo::default(),
+            TrackInfo { name: None, instrument: Some("Drum Kit".to_string()) },
+        ];
+
+        let mut legend = ChannelLegend::new();
+        legend.set_song(&notes, &tracks);
+        // Channels in order, named after the lowest-numbered track playing on them
+        assert_eq!(
+            legend.entries,
+            vec![
+                LegendEntry { channel: 0, name: Some("Melody".to_string()) },
+                LegendEntry { channel: 9, name: Some("Drum Kit".to_string()) },
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+// src/ui/lyrics_view.rs
+
+use crate::config::AppConfig;
+use crate::midi::Lyrics;
+use crate::renderer::text_renderer::TextRenderer;
+
+/// Height of the lyric line's center above the playhead (normalized), clear of its glow
+const LYRICS_ABOVE_PLAYHEAD: f32 = 0.1;
+/// Fraction of the screen width a lyric line may cover
+const LYRICS_MAX_WIDTH: f32 = 0.9;
+/// Color of the syllables already sung
+const SUNG_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+/// Color of the syllables still to come
+const UPCOMING_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Shows the current lyric line of karaoke files, highlighting it as it is sung
+pub struct LyricsView {
+    lyrics: Lyrics,
+    visible: bool,
+}
+
+impl LyricsView {
+    pub fn new(config: &AppConfig) -> Self {
+        LyricsView {
+            lyrics: Lyrics::default(),
+            visible: config.display.show_lyrics,
+        }
+    }
+
+    /// Set the lyrics of the loaded file
+    pub fn set_lyrics(&mut self, lyrics: Lyrics) {
+        self.lyrics = lyrics;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Queue the line sung at `time` above the `playhead` height
+    pub fn draw(&self, text: &mut TextRenderer, time: f32, playhead: f32) {
+        if !self.visible {
+            return;
+        }
+        let Some(line) = self.lyrics.line_at(time) else {
+            return;
+        };
+
+        let full = line.text();
+        let mut scale = text.default_scale();
+        while scale > 1.0 && text.text_size(&full, scale)[0] > LYRICS_MAX_WIDTH {
+            scale -= 1.0;
+        }
+        let size = text.text_size(&full, scale);
+        let center = playhead + LYRICS_ABOVE_PLAYHEAD;
+        let left = (0.5 - size[0] * 0.5).max(0.0);
+        let top = center + size[1] * 0.5;
+        let margin = [size[1] * 0.4 * text.aspect(), size[1] * 0.4];
+
+        text.draw_rect(
+            [left - margin[0], center - size[1] * 0.5 - margin[1]],
+            [size[0] + margin[0] * 2.0, size[1] + margin[1] * 2.0],
+            [0.0, 0.0, 0.0, 0.6],
+        );
+
+        // Sung syllables in the highlight color, the rest after them
+        let sung = line.sung_chars(time);
+        let sung_text: String = full.chars().take(sung).collect();
+        let upcoming_text: String = full.chars().skip(sung).collect();
+        text.draw_text(&sung_text, [left, top], scale, SUNG_COLOR);
+        text.draw_text(
+            &upcoming_text,
+            [left + text.column_offset(sung, scale), top],
+            scale,
+            UPCOMING_COLOR,
+        );
+    }
+}
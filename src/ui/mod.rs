@@ -1,7 +1,25 @@
 // src/ui/mod.rs
 
+pub mod actions;
+pub mod banner;
+pub mod control_window;
+pub mod density_gauge;
 pub mod input;
+pub mod legend;
+pub mod lyrics_view;
+pub mod pitch_histogram;
+pub mod visibility;
+pub mod welcome;
 pub mod controls;
 
+pub use actions::{apply_player_action, PlayerAction};
+pub use banner::MessageBanner;
+pub use control_window::ControlWindow;
+pub use density_gauge::DensityGauge;
 pub use input::{InputAction, InputHandler};
+pub use legend::ChannelLegend;
+pub use lyrics_view::LyricsView;
+pub use pitch_histogram::PitchHistogram;
+pub use visibility::WindowVisibility;
+pub use welcome::WelcomeScreen;
 pub use controls::{UIControls, ButtonAction};
@@ -0,0 +1,153 @@
+// src/ui/pitch_histogram.rs
+
+use crate::config::AppConfig;
+use crate::midi::Note;
+use crate::renderer::text_renderer::TextRenderer;
+
+/// Height of the tallest bar above the piano, as a fraction of the space above it
+const HISTOGRAM_HEIGHT: f32 = 0.4;
+const BAR_COLOR: [f32; 4] = [0.35, 0.75, 1.0, 0.55];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+const NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// Shows how often each key is played across the whole song, as bars along the keyboard
+///
+/// The counts are taken once when a song loads, so the bars stand still during playback
+/// and show the range the song uses at a glance.
+pub struct PitchHistogram {
+    visible: bool,
+    /// Notes of the song on each MIDI key
+    counts: [u32; 128],
+}
+
+impl PitchHistogram {
+    pub fn new(config: &AppConfig) -> Self {
+        PitchHistogram {
+            visible: config.display.show_pitch_histogram,
+            counts: [0; 128],
+        }
+    }
+
+    /// Count the notes of a newly loaded song on each key
+    pub fn set_notes(&mut self, notes: &[Note]) {
+        self.counts = [0; 128];
+        for note in notes {
+            self.counts[(note.pitch & 0x7F) as usize] += 1;
+        }
+    }
+
+    pub fn toggle_visibility(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Get how many notes of the song are on `pitch`
+    pub fn count(&self, pitch: u8) -> u32 {
+        self.counts[(pitch & 0x7F) as usize]
+    }
+
+    /// Get the lowest and highest key the song plays, None for a song without notes
+    pub fn range(&self) -> Option<(u8, u8)> {
+        let low = self.counts.iter().position(|&count| count > 0)?;
+        let high = self.counts.iter().rposition(|&count| count > 0)?;
+        Some((low as u8, high as u8))
+    }
+
+    /// Get the key played most often, the lowest one on ties
+    pub fn most_used(&self) -> Option<u8> {
+        let top = self.counts.iter().copied().max().filter(|&count| count > 0)?;
+        self.counts.iter().position(|&count| count == top).map(|pitch| pitch as u8)
+    }
+
+    /// Queue the bars above the piano at `bottom`, one per key of the shown key range
+    pub fn draw(&self, text: &mut TextRenderer, config: &AppConfig, bottom: f32) {
+        if !self.visible {
+            return;
+        }
+        let (Some((low, high)), Some(most_used)) = (self.range(), self.most_used()) else {
+            return;
+        };
+
+        let display = &config.display;
+        let (first, last) = display.key_bounds();
+        let key_width = display.key_scale() / 128.0;
+        let peak = self.count(most_used) as f32;
+        let max_height = (1.0 - bottom) * HISTOGRAM_HEIGHT;
+        for pitch in first..=last {
+            let count = self.count(pitch);
+            if count == 0 {
+                continue;
+            }
+            let x = display.key_x(pitch as f32 / 128.0);
+            text.draw_rect([x, bottom], [key_width, max_height * count as f32 / peak], BAR_COLOR);
+        }
+
+        let scale = (text.default_scale() * 0.5).round().max(1.0);
+        let label = format!(
+            "Range {}-{}, most used {} ({})",
+            pitch_name(low),
+            pitch_name(high),
+            pitch_name(most_used),
+            self.count(most_used)
+        );
+        let [width, height] = text.text_size(&label, scale);
+        // Centered over the tallest possible bar
+        text.draw_text(&label, [0.5 - width * 0.5, bottom + max_height + height * 1.5], scale, TEXT_COLOR);
+    }
+}
+
+/// Get the name of a MIDI key with its octave, middle C (60) being C4
+fn pitch_name(pitch: u8) -> String {
+    format!("{}{}", NAMES[(pitch % 12) as usize], pitch as i32 / 12 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_counts() {
+        let notes = vec![
+            Note::new(48, 100, 0.0, 1.0, 0),
+            Note::new(64, 100, 0.0, 1.0, 0),
+            Note::new(64, 100, 1.0, 1.0, 1),
+            Note::new(72, 100, 2.0, 1.0, 0),
+        ];
+        let mut histogram = PitchHistogram::new(&AppConfig::default());
+        assert_eq!(histogram.range(), None);
+        assert_eq!(histogram.most_used(), None);
+
+        histogram.set_notes(&notes);
+        assert_eq!(histogram.count(64), 2);
+        assert_eq!(histogram.count(60), 0);
+        assert_eq!(histogram.range(), Some((48, 72)));
+        assert_eq!(histogram.most_used(), Some(64));
+        assert_eq!(pitch_name(60), "C4");
+        assert_eq!(pitch_name(61), "C#4");
+        assert_eq!(pitch_name(0), "C-1");
+
+        // A new file replaces the counts
+        histogram.set_notes(&notes[..1]);
+        assert_eq!(histogram.count(64), 0);
+        assert_eq!(histogram.most_used(), Some(48));
+    }
+
+    #[test]
+    fn test_fit_key_range() {
+        let notes = vec![Note::new(40, 100, 0.0, 1.0, 0), Note::new(90, 100, 0.5, 1.0, 0)];
+        let mut histogram = PitchHistogram::new(&AppConfig::default());
+        histogram.set_notes(&notes);
+        let mut config = AppConfig::default();
+        let (low, high) = histogram.range().unwrap();
+        config.display.fit_key_range(low, high);
+        // The played keys fill the window, with a little room on each side
+        assert_eq!(config.display.key_range, [38, 92]);
+
+        // The margin stops at the ends of the MIDI range
+        config.display.fit_key_range(127, 1);
+        assert_eq!(config.display.key_range, [0, 127]);
+    }
+}
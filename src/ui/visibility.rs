@@ -0,0 +1,61 @@
+// src/ui/visibility.rs
+
+use winit::dpi::PhysicalSize;
+use winit::event::WindowEvent;
+
+/// Tracks whether the main window can be seen, so no frames are drawn for nobody
+#[derive(Debug, Default)]
+pub struct WindowVisibility {
+    /// Whether the compositor reported the main window as fully covered
+    occluded: bool,
+}
+
+impl WindowVisibility {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follow a window event; only the main window's occlusion counts, a covered or
+    /// minimized control window leaves the main window visible
+    pub fn handle_event(&mut self, is_main_window: bool, event: &WindowEvent) {
+        if let WindowEvent::Occluded(occluded) = *event {
+            if is_main_window && occluded != self.occluded {
+                self.occluded = occluded;
+                log::debug!("Window {}", if occluded { "hidden" } else { "visible again" });
+            }
+        }
+    }
+
+    /// Check whether the main window of `size` can't be seen
+    ///
+    /// Minimized windows report a zero size on Windows and may not be able to present at all.
+    pub fn is_hidden(&self, size: PhysicalSize<u32>, minimized: bool) -> bool {
+        self.occluded || size.width == 0 || size.height == 0 || minimized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_main_window_occlusion_hides() {
+        let size = PhysicalSize::new(800, 600);
+        let mut visibility = WindowVisibility::new();
+        assert!(!visibility.is_hidden(size, false));
+
+        // Covering the control window keeps the main window drawing
+        visibility.handle_event(false, &WindowEvent::Occluded(true));
+        assert!(!visibility.is_hidden(size, false));
+
+        visibility.handle_event(true, &WindowEvent::Occluded(true));
+        assert!(visibility.is_hidden(size, false));
+        visibility.handle_event(false, &WindowEvent::Occluded(false));
+        assert!(visibility.is_hidden(size, false));
+        visibility.handle_event(true, &WindowEvent::Occluded(false));
+        assert!(!visibility.is_hidden(size, false));
+
+        assert!(visibility.is_hidden(PhysicalSize::new(0, 0), false));
+        assert!(visibility.is_hidden(size, true));
+    }
+}
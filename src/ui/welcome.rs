@@ -0,0 +1,57 @@
+// src/ui/welcome.rs
+
+use crate::renderer::text_renderer::TextRenderer;
+
+/// Vertical center of the title (normalized), below the shader's top fade
+const TITLE_CENTER: f32 = 0.75;
+/// Top of the drop prompt (normalized), under the error banner
+const PROMPT_TOP: f32 = 0.5;
+
+/// Shortcuts listed for first-time users
+const HINTS: &[&str] = &[
+    "Space  Play / pause",
+    "Up / Down  Speed    Left / Right  Seek",
+    "M  Minimap    P  Stats    F11  Fullscreen",
+];
+
+/// The screen shown while no file is loaded: what to do and the main shortcuts
+pub struct WelcomeScreen;
+
+impl WelcomeScreen {
+    pub fn new() -> Self {
+        WelcomeScreen
+    }
+
+    /// Queue the welcome text
+    pub fn draw(&self, text: &mut TextRenderer) {
+        let scale = text.default_scale();
+
+        let title = "MIDI-RS";
+        let title_scale = scale * 2.0;
+        let size = text.text_size(title, title_scale);
+        text.draw_text(
+            title,
+            [0.5 - size[0] * 0.5, TITLE_CENTER + size[1] * 0.5],
+            title_scale,
+            [1.0, 1.0, 1.0, 1.0],
+        );
+
+        let prompt = "Drop a .mid file here to start";
+        let size = text.text_size(prompt, scale);
+        text.draw_text(prompt, [0.5 - size[0] * 0.5, PROMPT_TOP], scale, [0.8, 0.8, 0.8, 1.0]);
+
+        let line_step = size[1] * 2.0;
+        let mut top = PROMPT_TOP - line_step * 1.5;
+        for hint in HINTS {
+            let width = text.text_size(hint, scale)[0];
+            text.draw_text(hint, [0.5 - width * 0.5, top], scale, [0.5, 0.5, 0.55, 1.0]);
+            top -= line_step;
+        }
+    }
+}
+
+impl Default for WelcomeScreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}